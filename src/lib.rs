@@ -89,6 +89,332 @@
 //!     "PubStruct { a: Some(ReplacementSomeValue), b: Ok(ReplacementOkValue) }",
 //! );
 //! ```
+//!
+//! # Field attributes
+//!
+//! - `#[debug_stub = "..."]`: replaces the field value with the given text, which may contain the
+//!   placeholders `{len}`, `{type}`, and `{ptr}`, printing the length, the type name, and the
+//!   address of the field, like `#[debug_stub = "<{type}, {len} bytes>"]`; other braces print as
+//!   written. Placeholders work in all replacement texts of nested keys, like `some = "..."`
+//! - `#[debug_stub(const = "path::to::MARKER")]`: replaces the field value with the text of the
+//!   given `&str` constant, so that markers shared across a code base are defined in one place;
+//!   can be nested like a replacement text, as in `some(const = "...")`
+//! - `#[debug_stub(some = "...", none = "...")]`: replaces the value inside `Some(...)` and/or
+//!   the `None` of an `Option` field; an omitted side is printed as is
+//! - `#[debug_stub(ok = "...", err = "...")]`: replaces the values inside `Ok(...)` and/or
+//!   `Err(...)` of a `Result` field; an omitted side is printed as is
+//! - `#[debug_stub(ok = "...", err_max_len = N, err_display)]`: `err_max_len` cuts the `Err`
+//!   value printed as is after `N` characters, and `err_display` prints it through its
+//!   `fmt::Display` impl instead, so that huge errors do not flood the output
+//! - `#[debug_stub(some(ok = "...", err = "..."))]`: `some`, `ok`, and `err` also take nested
+//!   `debug_stub` keys instead of a replacement text, which stub the inner layers of compositions
+//!   like `Option<Result<T, E>>` while keeping the `Some(Ok(...))` shape
+//! - `#[debug_stub(future_state = "path::to::is_done")]`: prints an `Option` holding a future as
+//!   `Pending` when `Some`, and as `Done` or `NotStarted` when `None`, depending on whether the
+//!   given `fn(&Self) -> bool` reports completion
+//! - `#[debug_stub(len)]`: prints only the length of a collection or string field, like
+//!   `Vec(len = 15000)`. This and the other collection keys (`limit`, `first`, `head`, `tail`,
+//!   `each`, `set`, `key`, `value`, `variant_counts`) also work on collections behind a `Box`,
+//!   `Rc`, or `Arc`, like `Box<[T]>`, `Rc<[T]>`, or `Arc<Vec<T>>`, which print like the
+//!   collection itself, as in `Slice(len = 3)`
+//! - `#[debug_stub(shallow)]`: prints a one level summary of the field instead of recursing into
+//!   it: the length of collections and strings as with `len`, the variant of an `Option` or
+//!   `Result`, like `Some(..)`, and the type name otherwise, like `Config(..)`. Primitive values
+//!   are printed as is
+//! - `#[debug_stub(opaque)]`: prints the name of the field type as written followed by `{ .. }`,
+//!   like `Connection { .. }`, the way std marks hidden contents of non-exhaustive Debug output
+//! - `#[debug_stub(scientific)]`: prints a number in scientific notation, like `1.5e-7`, or with
+//!   an uppercase exponent with `scientific = "E"`. Combined with `precision = N`, the mantissa is
+//!   printed with `N` decimals
+//! - `#[debug_stub(separators)]`: prints an integer with its digits grouped by three, like
+//!   `12_345_678`, or with another separator given as `separators = ","`
+//! - `#[debug_stub(type_name)]`: prints the name of the field type, as returned by
+//!   [`core::any::type_name`](https://doc.rust-lang.org/core/any/fn.type_name.html)
+//! - `#[debug_stub(hash)]`: prints a short, stable hash of a `Hash` field (`#a1b2c3d4`), which
+//!   allows correlating values across log lines without printing them
+//! - `#[debug_stub(limit = N)]`: prints at most `N` elements of a sequence, set, or map field,
+//!   followed by an `… and 213 more entries` marker counting the omitted ones
+//! - `#[debug_stub(sha256)]`: prints a truncated SHA-256 digest of a byte buffer
+//!   (`AsRef<[u8]>`), like `sha256:ba7816bf8f01cfea…`; requires the `sha256` feature
+//! - `#[debug_stub(display)]`: prints the field through its `fmt::Display` impl instead
+//! - `#[debug_stub(display, sanitize)]`: additionally escapes control characters (including ANSI
+//!   escape sequences) in the printed text, preventing log injection through untrusted strings
+//! - `#[debug_stub(truncate = N)]`: cuts string fields (`AsRef<str>`) longer than `N` characters,
+//!   like `"SELECT * FROM us…" (1234 chars)`
+//! - `#[debug_stub(peek = N)]`: prints only the first and last `N` characters of string fields
+//!   (`AsRef<str>`) longer than `2 * N` characters, like `"AKIA…Z9Q2" (40 chars)`, hiding the
+//!   middle while keeping both ends for correlation
+//! - `#[debug_stub(first = N)]`: like `limit`, but always prints the field as a sequence of its
+//!   first `N` elements (`[1, 2, … and 4992 more entries]`)
+//! - `#[debug_stub(utc)]`: converts a `chrono::DateTime` or `time::OffsetDateTime` field to UTC
+//!   before printing it; requires the `chrono` or `time` feature, and `utc = "chrono"` or
+//!   `utc = "time"` if both are enabled
+//! - `#[debug_stub(rfc3339)]`: prints a `std::time::SystemTime` field as an RFC 3339 timestamp in
+//!   UTC, like `2024-05-01T12:34:56Z`; requires the `chrono` or `time` feature like `utc`
+//! - `#[debug_stub(be)]` / `#[debug_stub(le)]`: prints an integer field stored in big / little
+//!   endian wire order as its raw value and its decoded value, like `0x0100 (be: 1)`
+//! - `#[debug_stub(head = N, tail = M)]`: prints only the first `N` and the last `M` elements of
+//!   a sequence, set, or map field, with an `… and 213 more entries` marker in between
+//! - `#[debug_stub(max_len = N)]`: cuts the printed text of the field after `N` characters, like
+//!   `"SELECT * FROM us…`
+//! - `#[debug_stub(mask)]` / `#[debug_stub(mask(keep_start = N, keep_end = M))]`: replaces all
+//!   but the first `N` and last `M` characters of the printed text of the field with `*`
+//! - `max_len` and `mask` can be chained after each other and after any other key, like
+//!   `#[debug_stub(display, max_len = 32, mask(keep_start = 4))]`, and apply from left to right
+//!   to the output of that key, or of the field printed as is
+//! - `#[debug_stub(precision = N)]`: prints the floats of a field with `N` decimals, like
+//!   `Some((1.000, 0.333))`, keeping the structure of its Debug output; can follow other keys,
+//!   like `#[debug_stub(each(some = "..."), precision = 2)]`
+//! - `#[debug_stub(inherit_precision)]`: passes on the precision given at the call site, like
+//!   `{:.2?}`, to the field when keys render it into text, like `max_len`, `mask`, and
+//!   `on_error`, which otherwise print its floats with all decimals; fields printed as is, or
+//!   through keys like `some`, already receive it. Alternate mode, the width, and the hex flags of
+//!   `{:x?}` and `{:X?}` are always passed on, like std does for nested values, but the `+` and
+//!   `0` flags are not
+//! - `#[debug_stub(on_error = "...")]`: prints the given text instead of the field if printing
+//!   it panics or returns an error, keeping the `Debug` impl total; can follow any other keys,
+//!   like `#[debug_stub(display, on_error = "<unprintable>")]`. Needs `panic = "unwind"` to catch
+//!   panics, and the panic hook still runs for caught panics, so their message is printed to
+//!   stderr as usual even though the marker is written
+//! - `#[debug_stub(spill(dir = "...", over = N))]`: writes the printed text of the field to a new
+//!   file in the given directory, or the temporary directory of the system without `dir`, if it
+//!   is longer than `N` bytes, and prints `<spilled to /tmp/dbg-1234-0.txt>` instead, keeping
+//!   logs readable while preserving enormous dumps for post-mortems. The text is printed as is if
+//!   writing the file fails; can follow any other keys, like `on_error`
+//! - `#[debug_stub(if = "path::to::predicate", then = "...", else_debug)]`: prints the field through
+//!   the `then` stub when the given `fn(&T) -> bool` returns true for it, like for users who are
+//!   external, and as is otherwise; `then` also takes nested keys, like `then(truncate = 4)`, and
+//!   `else_debug`, which states the fallback, may be omitted
+//! - `#[debug_stub(release_only = "...")]`: prints the given text instead of the field in builds
+//!   without `debug_assertions`, like release builds, and the field as is otherwise, keeping full
+//!   local visibility while production logs stay redacted; also takes nested keys, like
+//!   `release_only(len)`. The field type needs to implement `Debug`
+//! - `#[debug_stub(alternate = "...")]`: prints the given text instead of the field in alternate
+//!   mode (`{:#?}`), and the field as is, or through the other keys, otherwise; also takes nested
+//!   keys, like `#[debug_stub(len, alternate(limit = 10))]`, which prints a short stub in the
+//!   compact output and a detailed one in the pretty-printed output
+//! - `#[debug_stub(as_inner)]`: prints the inner value of a newtype field, like a
+//!   `#[repr(transparent)]` ID wrapper, through its `.0` field, as `42` instead of `UserId(42)`.
+//!   For wrappers whose field is private, `#[debug_stub(as_inner, inner = "u64")]` reads the inner
+//!   value through the `AsRef<u64>` impl of the wrapper instead
+//! - `#[debug_stub(as_deref)]`: prints an `Option` field through `Option::as_deref`, like an
+//!   `Option<String>` as `Some("x")` or an `Option<Box<T>>` as `Some(T)`; combined with `some`
+//!   and `none`, like `#[debug_stub(as_deref, some(truncate = 8))]`, the `some` stub applies to
+//!   the dereferenced value
+//! - `#[debug_stub(deref_to = "Type")]`: prints a field as the type it dereferences to, through as
+//!   many `Deref` impls as needed, like an `Arc<PathBuf>` as `Path` or a custom string type as
+//!   `str`
+//! - `#[debug_stub(resolve = "path::to::lookup")]`: prints an ID field followed by the name the
+//!   given `fn(Id) -> Option<&str>` returns for a copy of it, like `Entity(42 "Player")`, and just
+//!   the ID when the name is unknown
+//! - `#[debug_stub(method = "status")]`: prints the return value of the given method, called on
+//!   the field without arguments, for types without a Debug impl which expose their state through
+//!   methods like `.id()` or `.status()`
+//! - `#[debug_stub(getter = "path::to::pool_stats")]`: prints the return value of the given
+//!   `fn(&Field) -> impl Debug` in place of the field, for representations computed by free
+//!   functions or accessors defined outside of the field type
+//! - `#[debug_stub(via = "path::to::Summary")]`: converts a reference to the field into the given
+//!   type with its `From<&Field>` impl and prints the converted value, for crates which provide
+//!   Debug info types for their opaque handles
+//! - `#[debug_stub(expr = "self.items.len()")]`: prints the given expression instead of the field,
+//!   evaluated on every call with `self` in scope, like `format_args!("<len {}>", self.len())`.
+//!   In enum variants, the fields of the variant are also in scope by name, or as `tuple_0`,
+//!   `tuple_1`, ... for tuple variants
+//! - `#[debug_stub(with = |v, f| write!(f, "{} items", v.len()))]`: prints the field with the
+//!   given closure, called with a reference to the field and the formatter, for one-off formatting
+//!   without a named helper function. A function path or closure may also be given as a string,
+//!   like `with = "path::to::fmt_items"`, which can be combined with other keys
+//! - `#[debug_stub(wrap = "path::to::Adapter")]`: prints the field through the Debug impl of the
+//!   given adapter newtype, like `struct Adapter<'a>(&'a Foreign)`, which is constructed with a
+//!   reference to the field, for adapter types shared across a code base
+//! - `#[debug_stub(trait)]`: prints the field through the `StubDebug` trait in scope, which is
+//!   defined by the crate using the derive as
+//!   `trait StubDebug { fn stub_fmt(&self, f: &mut fmt::Formatter) -> fmt::Result; }`, so that
+//!   types from other crates get their stub formatting implemented once instead of at every field.
+//!   Another trait with such a method can be given as `trait = "path::to::Trait"`
+//! - `#[debug_stub(crc32)]`: prints the CRC-32 checksum of a byte buffer (`AsRef<[u8]>`), like
+//!   `crc32:cbf43926`, which tells whether two payloads are identical without dumping them
+//! - `#[debug_stub(bits(format = "vvvv_ffff", v = "version", f = "flags:b"))]`: prints the bit
+//!   groups of an integer field, like `version=3 flags=0b1010`. Each character of the format
+//!   stands for a bit, from the highest to the lowest one, and adjacent bits with the same letter
+//!   make up a group; `_` separates groups visually and `-` marks unused bits. Groups are named
+//!   after their letter unless renamed, and printed in binary with a `:b` suffix on the name
+//! - `#[debug_stub(path)]`: prints a `Path`, `PathBuf`, `OsStr`, or `OsString` field (or any other
+//!   `AsRef<Path>`) through `Path::display`, like `/var/log/app.log`, without the quotes and
+//!   escapes of its Debug output and with invalid Unicode replaced
+//! - `#[debug_stub(c_str)]`: prints a `CStr` or `CString` field as a string, like `"text"`, if it
+//!   is valid UTF-8, and as an escaped byte string, like `b"\xfftext"`, otherwise
+//! - `#[debug_stub(any_of(u32, String, MyType), else = "...")]`: prints the value of a field of a
+//!   type parameter as is if it is one of the given types, found with `Any` downcasting, and the
+//!   given text otherwise, or the name of the type without `else`. The type parameter needs to
+//!   be `'static` but not `Debug`, like with `ignore_generics, bound = "T: 'static"`
+//! - `#[debug_stub(json(redact("password", "token")))]`: prints a field as JSON serialized with
+//!   `serde_json`, pretty-printed with `{:#?}`, with the values of the given object keys replaced
+//!   by `"<redacted>"` at any depth; the list may be omitted, as in `#[debug_stub(json)]`. The
+//!   field can be a `serde_json::Value` or of any type implementing `serde::Serialize`; values
+//!   failing to serialize, like maps with non-string keys, print as `<json error: ...>`. Requires
+//!   the `serde_json` feature
+//! - `#[debug_stub(variant_counts)]`: prints a `Vec`, array, or slice of an enum as the number of
+//!   elements of each variant, like `{A: 120, B: 3, C: 0}`; the enum needs to derive `DebugStub`
+//!   with `#[debug_stub(variant_index)]` as well, and its type needs to be written out
+//! - `#[debug_stub(bytes)]`: prints an integer field holding a size in bytes in the largest binary
+//!   unit it has a whole amount of, like `512 B` or `1.44 MiB`; negative sizes of signed fields,
+//!   like differences, get a leading `-`
+//! - `#[debug_stub(duration)]`: prints a `std::time::Duration` field in the largest unit it has a
+//!   whole amount of, with up to three decimals, like `1.5s`, `320ms`, or `12µs`
+//! - `#[debug_stub(elapsed)]`: prints the time passed since a `std::time::Instant` field when
+//!   printing it, like `duration`, as in `42.3s ago`
+//! - `#[debug_stub(counter)]`: increments a `Cell<u32>` (or other `Cell` of an integer) or atomic
+//!   integer field each time the value is printed and prints the new count, like
+//!   `formatted 14 times`, which points out values that are logged over and over
+//! - `#[debug_stub(ptr)]`: prints the address a `Box`, `Rc`, `Arc`, reference, or raw pointer
+//!   field points to (`0x7ffd5c2a1b40`), which identifies instances without requiring `T: Debug`;
+//!   with `addr_style = "full"`, addresses are zero-padded to the pointer width of the target
+//!   (`0x00007ffd5c2a1b40`), which lines up logs of 32 and 64-bit builds
+//! - `#[debug_stub(each = "...")]`: prints a `Vec`, array, or slice field as a list with each
+//!   element replaced, like `[X, X, X]`; also takes nested keys, like `each(some = "...")`
+//! - `#[debug_stub(set = "...")]`: like `each`, but prints a `HashSet` or `BTreeSet` field as a
+//!   set, like `{X, X, X}`
+//! - `#[debug_stub(boxed = "...")]`, `#[debug_stub(rc = "...")]`, `#[debug_stub(arc = "...")]`:
+//!   replaces the value behind a `Box`, `Rc`, or `Arc` field, like `Box(ReplacementValue)`; also
+//!   take nested keys, like `boxed(some = "...")`
+//! - `#[debug_stub(mutex)]`: prints the value behind a `std::sync::Mutex` field if it can be
+//!   locked without blocking, like `Mutex(42)`, and `Mutex(<locked>)` otherwise; also takes a
+//!   replacement text or nested keys for the value, like `mutex = "..."` or `mutex(len)`
+//! - `#[debug_stub(rwlock)]`: like `mutex`, but for a `std::sync::RwLock` field, which is read
+//!   locked without blocking and printed as `RwLock(<write-locked>)` otherwise
+//! - `#[debug_stub(refcell)]`: like `mutex`, but for a `std::cell::RefCell` field, which is
+//!   borrowed with `try_borrow` and printed as `RefCell(<mutably borrowed>)` otherwise
+//! - `#[debug_stub(cow)]`: prints whether a `Cow` field is borrowed or owned around its value,
+//!   like `Borrowed("a")` or `Owned("a")`; also takes a replacement text or nested keys for the
+//!   value, like `cow = "..."` or `cow(len)`
+//! - `#[debug_stub(once)]`: prints a `OnceCell` or `OnceLock` field as `Uninit`, or as `Init(42)`
+//!   once it is set; also takes a replacement text or nested keys for the value, like
+//!   `once = "..."` or `once(len)`
+//! - `#[debug_stub(atomic)]`: loads the value of a `std::sync::atomic` field with
+//!   `Ordering::Relaxed` and prints it; takes nested keys for the loaded value, like `atomic(be)`
+//! - `#[debug_stub(uninit)]` / `#[debug_stub(uninit = "...")]`: prints a `MaybeUninit` field as
+//!   `<uninit>` or the given text, without reading it
+//! - `#[debug_stub(unsafe_assume_init)]`: prints the value of a `MaybeUninit` field through
+//!   `assume_init_ref`, for debugging initialization code; takes nested keys for the value, like
+//!   `unsafe_assume_init(len)`. Printing the field while it is not initialized is undefined
+//!   behavior. Only options whose name starts with `unsafe_` generate `unsafe` code
+//! - `#[debug_stub(cell)]`: prints the value of a `std::cell::Cell` field with a `Copy` value,
+//!   like `Cell(42)`; also takes a replacement text or nested keys for the value, like
+//!   `cell = "..."` or `cell(hash)`
+//! - `#[debug_stub(counts)]`: prints the strong and weak reference counts of an `Rc` or `Arc`
+//!   field next to its value, like `Arc(strong = 3, weak = 1, 42)`; also takes a replacement text
+//!   or nested keys for the value, like `counts = "..."` or `counts(len)`
+//! - `#[debug_stub(weak)]`: prints whether a `std::rc::Weak` or `std::sync::Weak` field can still
+//!   be upgraded, like `Weak(alive)` or `Weak(dangling)`; also takes a replacement text or nested
+//!   keys printing the upgraded value, like `weak = "..."` or `weak(len)`
+//! - `#[debug_stub(key = "...", value = "...")]`: prints a `HashMap` or `BTreeMap` field with its
+//!   keys and/or values replaced, like `{X: 1, X: 2}`; an omitted side is printed as is, and both
+//!   also take nested keys
+//! - `#[debug_stub(filter_keys = "...")]`: prints only the entries of a map field whose key starts
+//!   with the given prefix, like `{"user_id": 1, … and 2 more entries}`, for keys that are
+//!   `AsRef<str>`; `#[debug_stub(filter_keys_with = "path::to::predicate")]` keeps the entries
+//!   for which a `fn(&K) -> bool` returns true instead. Both can be combined with `key` and `value`
+//! - `#[debug_stub(brief)]`: includes the field in the one-line summary returned by the generated
+//!   `fn brief(&self) -> impl fmt::Display`, like `Job id=42 state=Running`, which holds the type
+//!   or variant name followed by the marked fields and is meant for log lines that do not need
+//!   the full Debug output. Can be combined with any other keys, which apply to both outputs
+//! - `#[debug_stub(priority = N)]`: sets the priority of the field for `max_output`, in which
+//!   fields of lower priority are elided first; can be combined with any other keys
+//! - `#[debug_stub(skip_if = "path::to::predicate")]`: leaves the field out of the output, and of
+//!   the `brief` summary, when the given `fn(&T) -> bool` returns true for it, like for empty
+//!   vectors or default settings; can be combined with any other keys
+//! - `#[debug_stub(skip_none)]`: leaves an `Option` field out of the output when it is `None`,
+//!   and prints it as is otherwise; can be combined with any other keys, but not with another
+//!   `skip_` key
+//! - `#[debug_stub(skip_empty)]`: leaves a field out of the output when its `is_empty()` method
+//!   returns true, like for strings, vectors, and maps without elements; can be combined with any
+//!   other keys, but not with another `skip_` key
+//! - `#[debug_stub(skip_default)]`: leaves a field out of the output when it equals the
+//!   `Default::default()` of its type, which needs to implement `Default` and `PartialEq`, like
+//!   for the many untouched settings of a configuration type; can be combined with any other
+//!   keys, but not with another `skip_` key
+//!
+//! # Container attributes
+//!
+//! By default, the generated impls bound every type parameter by `fmt::Debug`, except for those
+//! which only appear in the types of fields whose value is not printed at all, like
+//! `#[debug_stub = "..."]` fields.
+//!
+//! - `#[debug_stub(ignore_generics)]`: does not add `fmt::Debug` bounds to type parameters
+//! - `#[debug_stub(bound = "K: Debug, V: Debug")]`: adds the given where clause predicates to the
+//!   generated impls, which combined with `ignore_generics` bounds exactly the type parameters that
+//!   are printed
+//! - `#[debug_stub(dot)]`: additionally generates `fn to_dot(&self) -> String`, which renders
+//!   the value as a [Graphviz](https://graphviz.org/) graph with one leaf per field; requires the
+//!   `dot` feature
+//! - `#[debug_stub(debug_fields)]`: additionally generates
+//!   `fn debug_fields(&self) -> Vec<(&'static str, String)>`, which returns the name and Debug
+//!   output of each printed field
+//! - `#[debug_stub(explain)]`: additionally generates a `DEBUG_STUB_EXPLAIN: &'static str`
+//!   associated constant describing the added generic bounds and how each field is printed
+//! - `#[debug_stub(trait = "path::to::AltDebug")]`: implements the given trait instead of
+//!   `fmt::Debug`, for types which already have a manual Debug impl. The trait needs a single
+//!   `fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result` method; the additionally generated
+//!   `fn debug_stub(&self) -> impl fmt::Debug` prints the value through it with `{:?}`
+//! - `#[debug_stub(types("ffi::*" = "<ffi>", "Handle" = "<handle>"))]`: replaces the value of every
+//!   field without its own `#[debug_stub]` attribute whose type path, as written and without
+//!   generic arguments, matches one of the patterns, in which `*` stands for any sequence of
+//!   characters; the first matching pattern wins
+//! - `#[debug_stub(fingerprint)]`: additionally generates a `DEBUG_FORMAT_FINGERPRINT: u64`
+//!   associated constant hashing the shape of the Debug output (type, variant, and field names,
+//!   their order, and their stubs), which lets code parsing the output assert at compile time that
+//!   the format did not change
+//! - `#[debug_stub(dedup_key)]`: additionally generates `fn debug_dedup_key(&self) -> u64`, a hash
+//!   of the Debug output with the stubs applied, which is equal for values that print identically
+//!   and lets logging layers cheaply suppress repeated dumps. Fields whose changes should not
+//!   count, like timestamps, can be replaced with `#[debug_stub = "..."]`. The hash function is
+//!   fixed, but the output it hashes may change between Rust versions, like the layout of
+//!   `Formatter::debug_struct()` (see `stable_layout`) or the Debug impls of std types, so keys
+//!   are not meant to be persisted
+//! - `#[debug_stub(pure)]`: rejects field options whose generated code does more than read the
+//!   field, like taking locks (`mutex`, `rwlock`, `refcell`), calling user functions
+//!   (`future_state`, `resolve`, `method`, `getter`, `via`, `expr`, `with`, `trait`, `if`,
+//!   `skip_if`, `filter_keys_with`, `redactor`), reading the clock (`elapsed`), counting formats
+//!   (`counter`), or writing files (`spill`), keeping the Debug impl free of side effects and
+//!   reentrant
+//! - `#[debug_stub(strict)]`: turns the warning about `.clone()` calls in the expressions given
+//!   to `expr` and `with`, which copy the data on every format instead of borrowing it, into an
+//!   error
+//! - `#[debug_stub(stable_layout)]`: writes the braces, separators, and indentation of the output
+//!   from the generated code instead of delegating to `Formatter::debug_struct()` and
+//!   `Formatter::debug_tuple()`, so that it cannot change with the toolchain. The layout is the
+//!   one std currently uses; in alternate mode, formatting flags other than `#`, the width, the
+//!   precision, and the hex flags of `{:x?}` and `{:X?}` are not passed on to the fields
+//! - `#[debug_stub(redactor = "path::to::redact")]`: passes the printed text of every field to the
+//!   given `fn(field: &str, text: &str) -> Cow<str>` and prints the returned text instead, giving
+//!   a central policy, like scrubbing personal data, the final say over the output of all fields.
+//!   Fields are named by their index in tuple structs and variants
+//! - `#[debug_stub(max_output = N)]`: limits the printed text of the fields to about `N`
+//!   characters by replacing fields with `…`, starting with those of the lowest
+//!   `#[debug_stub(priority = N)]`, which defaults to 0, and the later ones among fields of the
+//!   same priority. The fields with the highest priority of the type or variant are always
+//!   printed. Each field is rendered once into a `String` before printing, so this requires `std`
+//! - `#[debug_stub(mark_used)]`: makes the Debug impl touch fields whose printed value does not
+//!   use them (like `#[debug_stub = "..."]` fields), so that they are not reported as dead code
+//!   when Debug is their only consumer
+//! - `#[debug_stub(skip_none)]`: marks every field whose type is written as `Option<...>`
+//!   `#[debug_stub(skip_none)]`, except for those with their own `skip_` key, which shrinks the
+//!   output of sparse configuration types to the options that are set
+//! - `#[debug_stub(variant_index)]`: additionally generates the hidden `DEBUG_STUB_VARIANTS`
+//!   associated constant and `fn debug_stub_variant_index(&self) -> usize` of an enum, which
+//!   `#[debug_stub(variant_counts)]` fields holding it need
+//!
+//! # Stub report
+//!
+//! With the `report` feature enabled, building with the `DEBUG_STUB_REPORT` environment variable
+//! set makes every `#[derive(DebugStub)]` of a crate with a build script write a
+//! `type<TAB>field<TAB>description` line per field to `$OUT_DIR/debug_stub_report/<type>.tsv`,
+//! using the descriptions of `#[debug_stub(explain)]`. This gives an inventory of which fields are
+//! redacted and how, for example for a compliance review. Each expansion overwrites the file of
+//! its type, so rebuilds do not duplicate records, and changing the variable rebuilds the crate.
+//! Types of the same name in different modules share a file, which holds the last one expanded,
+//! and the files of removed or renamed types stay until `cargo clean`.
 #![deny(
     trivial_casts,
     trivial_numeric_casts,
@@ -100,11 +426,19 @@
 extern crate proc_macro;
 
 use proc_macro2::Span;
-use quote::{quote, ToTokens as _};
+use quote::{quote, quote_spanned, ToTokens as _};
 use syn::{
-    parse_macro_input, parse_quote, spanned::Spanned as _, Arm, Attribute, Data, DataEnum,
-    DataStruct, DataUnion, DeriveInput, Expr, Fields, FieldsNamed, FieldsUnnamed, Generics, Ident,
-    Lit, LitStr, Meta, MetaList, MetaNameValue, NestedMeta, Pat, Stmt,
+    parenthesized,
+    parse::{Parse, ParseStream, Parser as _},
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned as _,
+    token::{Comma, Paren},
+    visit::Visit,
+    Arm, Attribute, Data, DataEnum, DataStruct, DataUnion, DeriveInput, Expr, Field, Fields,
+    FieldsNamed, FieldsUnnamed, GenericArgument, Generics, Ident, Lit, LitStr, Meta, MetaList,
+    MetaNameValue, NestedMeta, Pat, Path, PathArguments, Stmt, Token, Type, TypeArray, TypeGroup,
+    TypeParen, TypePath, TypeReference, TypeSlice, WherePredicate,
 };
 
 /// Implementation of the `#[derive(DebugStub)]` derive macro.
@@ -120,74 +454,469 @@ pub fn derive_debug_stub(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 
 /// Central expansion function
 fn expand_derive_serialize(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
-    // check if there's an `#[debug_stub(ignore_generics)]` attribute
-    let mut ignore_generics = false;
-    for attr in &ast.attrs {
-        let meta = match attr.parse_meta() {
-            Ok(meta) if meta.path().is_ident("debug_stub") => meta,
-            _ => continue,
-        };
+    let ContainerOptions {
+        ignore_generics,
+        dot,
+        debug_fields,
+        explain,
+        mark_used,
+        fingerprint,
+        dedup_key,
+        pure,
+        strict,
+        skip_none,
+        variant_index,
+        layout,
+        max_output,
+        redactor,
+        debug_trait,
+        bounds,
+        type_defaults,
+    } = ContainerOptions::parse(&ast.attrs)?;
 
-        if let Meta::List(inner) = &meta {
-            for nested_meta in &inner.nested {
-                match nested_meta {
-                    NestedMeta::Meta(meta) if meta.path().is_ident("ignore_generics") => {
-                        ignore_generics = true
-                    }
-                    _ => return Err(syn::Error::new(meta.span(), "expected `ignore_generics`")),
-                }
-            }
-        } else {
-            return Err(syn::Error::new(meta.span(), "expected `ignore_generics`"));
-        }
+    // fields without their own `#[debug_stub]` attribute get the default of their type, if any
+    let mut ast = ast.clone();
+    apply_type_defaults(&mut ast, &type_defaults);
+    if skip_none {
+        apply_skip_none(&mut ast);
     }
+    let ast = &ast;
+    if pure {
+        check_pure(ast, redactor.as_ref())?;
+    }
+    let clone_warnings = check_clones(ast, strict)?;
 
     let mut generics_debug_bounded = ast.generics.clone();
     if !ignore_generics {
+        let opaque = opaque_type_params(ast)?;
         for generic_param in &mut generics_debug_bounded.params {
             if let syn::GenericParam::Type(generic_type_param) = generic_param {
+                if opaque.contains(&generic_type_param.ident) {
+                    continue;
+                }
                 generic_type_param
                     .bounds
                     .push(parse_quote!(::core::fmt::Debug));
             }
         }
     }
+    for bound in &bounds {
+        let predicates = bound.parse_with(Punctuated::<WherePredicate, Comma>::parse_terminated)?;
+        generics_debug_bounded
+            .make_where_clause()
+            .predicates
+            .extend(predicates);
+    }
 
-    match &ast.data {
-        Data::Struct(DataStruct { fields, .. }) => match fields {
-            Fields::Named(fields) => {
-                let stmts = generate_field_stmts(&fields)?;
-                Ok(implement_named_fields_struct_debug(
+    let trait_path = match &debug_trait {
+        Some(debug_trait) => debug_trait.clone(),
+        None => parse_quote!(::core::fmt::Debug),
+    };
+
+    let mut expanded = match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => {
+            let mut rendered = generate_struct_fields(fields)?;
+            if let Some(redactor) = &redactor {
+                apply_redactor(&mut rendered, redactor);
+            }
+            let prelude = match max_output {
+                Some(max_output) => implement_output_budget(&mut rendered, max_output),
+                None => vec![],
+            };
+            let mut stmts = rendered
+                .iter()
+                .map(implement_field_stmt)
+                .collect::<Vec<_>>();
+            if mark_used {
+                stmts.extend(implement_mark_used_stmts(&rendered));
+            }
+
+            match fields {
+                Fields::Named(_) => implement_named_fields_struct_debug(
                     &ast.ident,
                     &generics_debug_bounded,
+                    &trait_path,
+                    layout,
+                    &prelude,
                     &stmts,
-                ))
-            }
-            Fields::Unnamed(fields) => {
-                let stmts = generate_tuple_field_stmts(&fields)?;
-                Ok(implement_unnamed_fields_struct_debug(
+                ),
+                Fields::Unnamed(_) => implement_unnamed_fields_struct_debug(
                     &ast.ident,
                     &generics_debug_bounded,
+                    &trait_path,
+                    layout,
+                    &prelude,
                     &stmts,
-                ))
+                ),
+                Fields::Unit => {
+                    implement_unit_struct_debug(&ast.ident, &generics_debug_bounded, &trait_path)
+                }
             }
-            Fields::Unit => Ok(implement_unit_struct_debug(
-                &ast.ident,
-                &generics_debug_bounded,
-            )),
-        },
-        Data::Enum(DataEnum { variants, .. }) => Ok(implement_enum_debug(
+        }
+        Data::Enum(DataEnum { variants, .. }) => implement_enum_debug(
             &ast.ident,
             &generics_debug_bounded,
+            &trait_path,
+            layout,
             &variants
                 .iter()
-                .map(|variant| generate_arm(&ast.ident, variant))
+                .map(|variant| {
+                    generate_arm(
+                        &ast.ident,
+                        variant,
+                        mark_used,
+                        layout,
+                        max_output,
+                        redactor.as_ref(),
+                    )
+                })
                 .collect::<syn::Result<Vec<_>>>()?,
-        )),
-        Data::Union(DataUnion { union_token, .. }) => Err(syn::Error::new_spanned(
-            union_token,
-            "expected struct or enum",
-        )),
+        ),
+        Data::Union(DataUnion { union_token, .. }) => {
+            return Err(syn::Error::new_spanned(
+                union_token,
+                "expected struct or enum",
+            ))
+        }
+    };
+
+    if variant_index {
+        match &ast.data {
+            Data::Enum(DataEnum { variants, .. }) => {
+                expanded.extend(implement_variant_index(ast, variants));
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &ast.ident,
+                    "`variant_index` requires an enum",
+                ))
+            }
+        }
+    }
+    if has_brief_fields(ast) {
+        expanded.extend(implement_brief(
+            ast,
+            &generics_debug_bounded,
+            redactor.as_ref(),
+        )?);
+    }
+    if let Some(debug_trait) = &debug_trait {
+        expanded.extend(implement_debug_trait_adapter(
+            &ast.ident,
+            &generics_debug_bounded,
+            debug_trait,
+        ));
+    }
+    if dot {
+        expanded.extend(implement_dot(
+            ast,
+            &generics_debug_bounded,
+            redactor.as_ref(),
+        )?);
+    }
+    if debug_fields {
+        expanded.extend(implement_debug_fields(
+            ast,
+            &generics_debug_bounded,
+            redactor.as_ref(),
+        )?);
+    }
+    if explain {
+        let bounds = bounds.iter().map(LitStr::value).collect::<Vec<_>>();
+        expanded.extend(implement_explain(ast, &generics_debug_bounded, &bounds)?);
+    }
+    if fingerprint {
+        expanded.extend(implement_fingerprint(ast)?);
+    }
+    if dedup_key {
+        expanded.extend(implement_dedup_key(
+            &ast.ident,
+            &generics_debug_bounded,
+            &trait_path,
+        ));
+    }
+    if cfg!(feature = "report") {
+        expanded.extend(write_report(ast)?);
+    }
+    expanded.extend(clone_warnings);
+
+    Ok(expanded)
+}
+
+/// Options given by the container level `#[debug_stub(...)]` attributes, which may be spread over
+/// several attributes and given in any order and combination
+#[derive(Default)]
+struct ContainerOptions {
+    /// `ignore_generics`
+    ignore_generics: bool,
+    /// `dot`
+    dot: bool,
+    /// `debug_fields`
+    debug_fields: bool,
+    /// `explain`
+    explain: bool,
+    /// `mark_used`
+    mark_used: bool,
+    /// `fingerprint`
+    fingerprint: bool,
+    /// `dedup_key`
+    dedup_key: bool,
+    /// `pure`
+    pure: bool,
+    /// `strict`
+    strict: bool,
+    /// `skip_none`
+    skip_none: bool,
+    /// `variant_index`
+    variant_index: bool,
+    /// `stable_layout`
+    layout: Layout,
+    /// `max_output = N`
+    max_output: Option<usize>,
+    /// `redactor = "..."`
+    redactor: Option<Path>,
+    /// `trait = "..."`
+    debug_trait: Option<Path>,
+    /// `bound = "..."`, which may be repeated
+    bounds: Vec<LitStr>,
+    /// `types(...)`, which may be repeated
+    type_defaults: Vec<TypeDefault>,
+}
+
+impl ContainerOptions {
+    /// Parses the container level `#[debug_stub(...)]` attributes among the given ones
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let expected = "expected `ignore_generics`, `dot`, `debug_fields`, `explain`, \
+                        `mark_used`, `fingerprint`, `dedup_key`, `pure`, `strict`, `skip_none`, \
+                        `variant_index`, `stable_layout`, \
+                        `max_output = N`, `redactor = \"...\"`, `bound = \"...\"`, \
+                        `trait = \"...\"`, or `types(...)`";
+        let mut options = ContainerOptions::default();
+        for attr in attrs {
+            if !attr.path.is_ident("debug_stub") {
+                continue;
+            }
+
+            for item in parse_container_items(attr, expected)? {
+                let meta = match item {
+                    ContainerItem::Types(defaults) => {
+                        options.type_defaults.extend(defaults);
+                        continue;
+                    }
+                    ContainerItem::Meta(NestedMeta::Meta(meta)) => meta,
+                    ContainerItem::Meta(nested_meta) => {
+                        return Err(syn::Error::new(nested_meta.span(), expected))
+                    }
+                };
+                options.apply(&meta, expected)?;
+            }
+        }
+        Ok(options)
+    }
+
+    /// Applies a single item of a container level attribute, like `explain` or `bound = "..."`
+    fn apply(&mut self, meta: &Meta, expected: &str) -> syn::Result<()> {
+        let key = match meta.path().get_ident() {
+            Some(key) => key.to_string(),
+            None => return Err(syn::Error::new(meta.span(), expected)),
+        };
+        let flag = matches!(meta, Meta::Path(_));
+
+        match (key.as_str(), meta) {
+            ("ignore_generics", _) if flag => self.ignore_generics = true,
+            ("dot", _) if flag => {
+                if !cfg!(feature = "dot") {
+                    return Err(syn::Error::new(
+                        meta.span(),
+                        "`dot` requires the `dot` feature of `debug_stub_derive`",
+                    ));
+                }
+                self.dot = true
+            }
+            ("debug_fields", _) if flag => self.debug_fields = true,
+            ("explain", _) if flag => self.explain = true,
+            ("mark_used", _) if flag => self.mark_used = true,
+            ("fingerprint", _) if flag => self.fingerprint = true,
+            ("dedup_key", _) if flag => self.dedup_key = true,
+            ("pure", _) if flag => self.pure = true,
+            ("strict", _) if flag => self.strict = true,
+            ("skip_none", _) if flag => self.skip_none = true,
+            ("variant_index", _) if flag => self.variant_index = true,
+            ("stable_layout", _) if flag => self.layout = Layout::Stable,
+            (
+                "max_output",
+                Meta::NameValue(MetaNameValue {
+                    lit: Lit::Int(lit), ..
+                }),
+            ) => self.max_output = Some(lit.base10_parse()?),
+            (
+                "trait",
+                Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(lit), ..
+                }),
+            ) => self.debug_trait = Some(lit.parse()?),
+            (
+                "redactor",
+                Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(lit), ..
+                }),
+            ) => self.redactor = Some(lit.parse()?),
+            (
+                "bound",
+                Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(lit), ..
+                }),
+            ) => self.bounds.push(lit.clone()),
+            _ => return Err(syn::Error::new(meta.span(), expected)),
+        }
+        Ok(())
+    }
+}
+
+/// An item of a container level `#[debug_stub(...)]` attribute
+enum ContainerItem {
+    /// `types("path::*" = "...", ...)`
+    Types(Vec<TypeDefault>),
+    /// Any other item, like `explain` or `trait = "..."`
+    Meta(NestedMeta),
+}
+
+impl Parse for ContainerItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Ident) && input.peek2(Paren) && input.fork().parse::<Ident>()? == "types" {
+            input.parse::<Ident>()?;
+            let content;
+            parenthesized!(content in input);
+            let defaults = Punctuated::<TypeDefault, Comma>::parse_terminated(&content)?;
+            Ok(ContainerItem::Types(defaults.into_iter().collect()))
+        } else {
+            input.parse().map(ContainerItem::Meta)
+        }
+    }
+}
+
+/// A `"path::*" = "..."` entry of `#[debug_stub(types(...))]`, replacing the value of every field
+/// whose type path matches the pattern
+struct TypeDefault {
+    pattern: LitStr,
+    value: LitStr,
+}
+
+impl Parse for TypeDefault {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pattern = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(TypeDefault { pattern, value })
+    }
+}
+
+/// Parses the items of a container level `#[debug_stub(...)]` attribute
+fn parse_container_items(attr: &Attribute, expected: &str) -> syn::Result<Vec<ContainerItem>> {
+    let parser = |input: ParseStream| {
+        if !input.peek(Paren) {
+            return Err(syn::Error::new(attr.span(), expected));
+        }
+        let content;
+        parenthesized!(content in input);
+        let items = Punctuated::<ContainerItem, Comma>::parse_terminated(&content)?;
+        Ok(items.into_iter().collect())
+    };
+    parser.parse2(attr.tokens.clone())
+}
+
+/// Adds a `#[debug_stub = "..."]` attribute to every field without a `#[debug_stub]` attribute
+/// whose type matches one of the `#[debug_stub(types(...))]` patterns, first match winning
+fn apply_type_defaults(ast: &mut DeriveInput, defaults: &[TypeDefault]) {
+    if defaults.is_empty() {
+        return;
+    }
+
+    let fields: Vec<&mut Field> = match &mut ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields.iter_mut().collect(),
+        Data::Enum(DataEnum { variants, .. }) => variants
+            .iter_mut()
+            .flat_map(|variant| variant.fields.iter_mut())
+            .collect(),
+        Data::Union(_) => return,
+    };
+
+    for field in fields {
+        // attributes holding only markers like `brief` do not count as a stub of the field;
+        // invalid ones are reported when the field is rendered
+        if !matches!(parse_field_stub(&field.attrs), Ok(None)) {
+            continue;
+        }
+
+        let type_path = match type_path_name(&field.ty) {
+            Some(type_path) => type_path,
+            None => continue,
+        };
+        if let Some(TypeDefault { value, .. }) = defaults
+            .iter()
+            .find(|default| glob_match(&default.pattern.value(), &type_path))
+        {
+            field.attrs.push(parse_quote!(#[debug_stub = #value]));
+        }
+    }
+}
+
+/// Marks the `Option` fields without a `skip_` key of their own `#[debug_stub(skip_none)]`, for the
+/// container level `#[debug_stub(skip_none)]`
+fn apply_skip_none(ast: &mut DeriveInput) {
+    let fields: Vec<&mut Field> = match &mut ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields.iter_mut().collect(),
+        Data::Enum(DataEnum { variants, .. }) => variants
+            .iter_mut()
+            .flat_map(|variant| variant.fields.iter_mut())
+            .collect(),
+        Data::Union(_) => return,
+    };
+
+    for field in fields {
+        if short_type_name(&field.ty) == "Option"
+            && SKIP_KEYS
+                .iter()
+                .all(|key| field_markers(&field.attrs, key).is_empty())
+        {
+            field.attrs.push(parse_quote!(#[debug_stub(skip_none)]));
+        }
+    }
+}
+
+/// Returns the path of a field type as written, without generic arguments, like `ffi::Handle` for
+/// `&ffi::Handle<u8>`
+fn type_path_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => Some(
+            path.segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::"),
+        ),
+        Type::Reference(TypeReference { elem, .. })
+        | Type::Paren(TypeParen { elem, .. })
+        | Type::Group(TypeGroup { elem, .. }) => type_path_name(elem),
+        _ => None,
+    }
+}
+
+/// Matches `text` against a pattern in which `*` stands for any sequence of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == text,
+        Some(star) => {
+            let (prefix, rest) = (&pattern[..star], &pattern[star + 1..]);
+            if !text.starts_with(prefix) {
+                return false;
+            }
+            let text = &text[prefix.len()..];
+            text.char_indices()
+                .map(|(index, _)| index)
+                .chain(Some(text.len()))
+                .any(|index| glob_match(rest, &text[index..]))
+        }
     }
 }
 
@@ -196,15 +925,22 @@ fn expand_derive_serialize(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenS
 fn implement_named_fields_struct_debug(
     ident: &Ident,
     generics: &Generics,
+    debug_trait: &Path,
+    layout: Layout,
+    prelude: &[Stmt],
     stmts: &[Stmt],
 ) -> proc_macro2::TokenStream {
     let name = ident.to_string();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let items = layout.items();
+    let builder = layout.builder(&name, true);
 
     quote!(
-        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+        impl #impl_generics #debug_trait for #ident #ty_generics #where_clause {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                let mut f = f.debug_struct(#name);
+                #items
+                #(#prelude)*
+                let mut f = #builder;
                 #(#stmts)*
                 f.finish()
             }
@@ -217,15 +953,22 @@ fn implement_named_fields_struct_debug(
 fn implement_unnamed_fields_struct_debug(
     ident: &Ident,
     generics: &Generics,
+    debug_trait: &Path,
+    layout: Layout,
+    prelude: &[Stmt],
     stmts: &[Stmt],
 ) -> proc_macro2::TokenStream {
     let name = ident.to_string();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let items = layout.items();
+    let builder = layout.builder(&name, false);
 
     quote!(
-        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+        impl #impl_generics #debug_trait for #ident #ty_generics #where_clause {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                let mut f = f.debug_tuple(#name);
+                #items
+                #(#prelude)*
+                let mut f = #builder;
                 #(#stmts)*
                 f.finish()
             }
@@ -233,13 +976,176 @@ fn implement_unnamed_fields_struct_debug(
     )
 }
 
+/// Layout of the generated Debug output, selected by `#[debug_stub(stable_layout)]`
+#[derive(Clone, Copy)]
+enum Layout {
+    /// Delegating to the `debug_struct()` and `debug_tuple()` builders of std
+    Std,
+    /// Writing the same separators and indentation from the generated code itself
+    Stable,
+}
+
+// deriving it needs `#[default]` on the variant, which is only stable since Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::Std
+    }
+}
+
+impl Layout {
+    /// Returns the expression creating the builder the field statements are called on
+    fn builder(self, name: &str, named: bool) -> proc_macro2::TokenStream {
+        match (self, named) {
+            (Layout::Std, true) => quote!(f.debug_struct(#name)),
+            (Layout::Std, false) => quote!(f.debug_tuple(#name)),
+            (Layout::Stable, true) => quote!(StableStruct(StableLayout::new(f, #name, true))),
+            (Layout::Stable, false) => quote!(StableTuple(StableLayout::new(f, #name, false))),
+        }
+    }
+
+    /// Returns the items the builders need in scope, which the stable layout defines locally
+    fn items(self) -> proc_macro2::TokenStream {
+        match self {
+            Layout::Std => quote!(),
+            Layout::Stable => implement_stable_layout(),
+        }
+    }
+}
+
+/// Generates the builders of `#[debug_stub(stable_layout)]`, which print structs and tuples like
+/// `debug_struct()` and `debug_tuple()` currently do (`A { b: 1 }` and `A(1)`, with one indented
+/// field per line and trailing commas in alternate mode), but without depending on them
+fn implement_stable_layout() -> proc_macro2::TokenStream {
+    let flags = implement_debug_flags(quote!(self.f), quote!(self.f.precision()));
+    let write = implement_write_debug(quote!(&mut padded), quote!(flags), quote!(value));
+    quote! {
+        struct StableLayout<'a, 'b> {
+            f: &'a mut ::core::fmt::Formatter<'b>,
+            result: ::core::fmt::Result,
+            fields: usize,
+            braces: bool,
+        }
+
+        impl<'a, 'b> StableLayout<'a, 'b> {
+            fn new(f: &'a mut ::core::fmt::Formatter<'b>, name: &str, braces: bool) -> Self {
+                let result = f.write_str(name);
+                StableLayout { f, result, fields: 0, braces }
+            }
+
+            fn entry(&mut self, name: ::core::option::Option<&str>, value: &dyn ::core::fmt::Debug) {
+                if self.result.is_ok() {
+                    self.result = self.write_entry(name, value);
+                }
+                self.fields += 1;
+            }
+
+            fn write_entry(
+                &mut self,
+                name: ::core::option::Option<&str>,
+                value: &dyn ::core::fmt::Debug,
+            ) -> ::core::fmt::Result {
+                if self.f.alternate() {
+                    if self.fields == 0 {
+                        self.f.write_str(if self.braces { " {
+" } else { "(
+" })?;
+                    }
+                    let flags = #flags;
+                    let mut padded = Padded { f: &mut *self.f, on_newline: true };
+                    if let ::core::option::Option::Some(name) = name {
+                        ::core::fmt::Write::write_str(&mut padded, name)?;
+                        ::core::fmt::Write::write_str(&mut padded, ": ")?;
+                    }
+                    #write?;
+                    ::core::fmt::Write::write_str(&mut padded, ",\n")
+                } else {
+                    self.f.write_str(match (self.fields, self.braces) {
+                        (0, true) => " { ",
+                        (0, false) => "(",
+                        _ => ", ",
+                    })?;
+                    if let ::core::option::Option::Some(name) = name {
+                        self.f.write_str(name)?;
+                        self.f.write_str(": ")?;
+                    }
+                    ::core::fmt::Debug::fmt(value, self.f)
+                }
+            }
+
+            fn finish(&mut self) -> ::core::fmt::Result {
+                self.result?;
+                match (self.fields, self.braces, self.f.alternate()) {
+                    (0, _, _) => ::core::result::Result::Ok(()),
+                    (_, true, true) => self.f.write_str("}"),
+                    (_, true, false) => self.f.write_str(" }"),
+                    (_, false, _) => self.f.write_str(")"),
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        struct StableStruct<'a, 'b>(StableLayout<'a, 'b>);
+
+        #[allow(dead_code)]
+        impl<'a, 'b> StableStruct<'a, 'b> {
+            fn field(&mut self, name: &str, value: &dyn ::core::fmt::Debug) -> &mut Self {
+                self.0.entry(::core::option::Option::Some(name), value);
+                self
+            }
+
+            fn finish(&mut self) -> ::core::fmt::Result {
+                self.0.finish()
+            }
+        }
+
+        #[allow(dead_code)]
+        struct StableTuple<'a, 'b>(StableLayout<'a, 'b>);
+
+        #[allow(dead_code)]
+        impl<'a, 'b> StableTuple<'a, 'b> {
+            fn field(&mut self, value: &dyn ::core::fmt::Debug) -> &mut Self {
+                self.0.entry(::core::option::Option::None, value);
+                self
+            }
+
+            fn finish(&mut self) -> ::core::fmt::Result {
+                self.0.finish()
+            }
+        }
+
+        // indents everything written after a line break, like the private `PadAdapter` of std
+        struct Padded<'a, 'b> {
+            f: &'a mut ::core::fmt::Formatter<'b>,
+            on_newline: bool,
+        }
+
+        impl<'a, 'b> ::core::fmt::Write for Padded<'a, 'b> {
+            fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+                for line in s.split_inclusive('\n') {
+                    if self.on_newline {
+                        self.f.write_str("    ")?;
+                    }
+                    self.on_newline = line.ends_with('\n');
+                    self.f.write_str(line)?;
+                }
+                ::core::result::Result::Ok(())
+            }
+        }
+    }
+}
+
 /// Generates unit struct Debug impl (`MyStruct`)
-fn implement_unit_struct_debug(ident: &Ident, generics: &Generics) -> proc_macro2::TokenStream {
+fn implement_unit_struct_debug(
+    ident: &Ident,
+    generics: &Generics,
+    debug_trait: &Path,
+) -> proc_macro2::TokenStream {
     let name = ident.to_string();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote!(
-        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+        impl #impl_generics #debug_trait for #ident #ty_generics #where_clause {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                 f.write_str(#name)
             }
@@ -252,13 +1158,17 @@ fn implement_unit_struct_debug(ident: &Ident, generics: &Generics) -> proc_macro
 fn implement_enum_debug(
     ident: &Ident,
     generics: &Generics,
+    debug_trait: &Path,
+    layout: Layout,
     arms: &[Arm],
 ) -> proc_macro2::TokenStream {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let items = layout.items();
 
     quote! {
-        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+        impl #impl_generics #debug_trait for #ident #ty_generics #where_clause {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                #items
                 match self {
                     #(#arms)*
                 }
@@ -267,290 +1177,4709 @@ fn implement_enum_debug(
     }
 }
 
-/// Generates Formatter statements for a named fields struct like `f.field("a", self.a)`
-fn generate_field_stmts(fields: &FieldsNamed) -> syn::Result<Vec<Stmt>> {
-    fields
-        .named
-        .iter()
-        .map(|field| {
-            let ident = field.ident.as_ref().unwrap();
-            let expr = parse_quote!(self.#ident);
-            let name = ident.to_string();
-            let (_, stmt) = extract_value_attr(&expr, &field.attrs, Some(name))?;
-            Ok(stmt)
-        })
-        .collect()
+/// Generates the hidden `DEBUG_STUB_VARIANTS` constant and `fn debug_stub_variant_index(&self)` of
+/// an enum for `#[debug_stub(variant_index)]`, which `#[debug_stub(variant_counts)]` fields
+/// holding it count variants with
+fn implement_variant_index(
+    ast: &DeriveInput,
+    variants: &Punctuated<syn::Variant, Comma>,
+) -> proc_macro2::TokenStream {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let names = variants.iter().map(|variant| variant.ident.to_string());
+    let arms = variants.iter().enumerate().map(|(index, variant)| {
+        let variant = &variant.ident;
+        quote!(#ident::#variant { .. } => #index,)
+    });
+
+    quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Names of the variants, in declaration order.
+            #[doc(hidden)]
+            pub const DEBUG_STUB_VARIANTS: &'static [&'static str] = &[#(#names),*];
+
+            /// Returns the index of the variant of `self` in `DEBUG_STUB_VARIANTS`.
+            #[doc(hidden)]
+            pub fn debug_stub_variant_index(&self) -> usize {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }
 }
 
-/// Generates Formatter statements for a tuple struct like `f.field(self.0)`
-fn generate_tuple_field_stmts(fields: &FieldsUnnamed) -> syn::Result<Vec<Stmt>> {
-    fields
-        .unnamed
-        .iter()
-        .enumerate()
-        .map(|(index, field)| {
-            let index = syn::Index::from(index);
-            let expr = parse_quote!(self.#index);
-            let (_, stmt) = extract_value_attr(&expr, &field.attrs, None)?;
-            Ok(stmt)
-        })
-        .collect()
+/// Generates `fn debug_stub(&self) -> impl fmt::Debug`, which adapts the impl of the alternate
+/// trait given by `#[debug_stub(trait = "...")]` for use with `{:?}`
+fn implement_debug_trait_adapter(
+    ident: &Ident,
+    generics: &Generics,
+    debug_trait: &Path,
+) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let adapter = implement_debug_with(quote!(<Self as #debug_trait>::fmt(self, f)));
+
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns a `fmt::Debug` adapter printing `self` through its `DebugStub` impl
+            pub fn debug_stub(&self) -> impl ::core::fmt::Debug + '_ {
+                #adapter
+            }
+        }
+    }
 }
 
-/// Generates a single match arm for an enum Debug impl
-fn generate_arm(ident: &Ident, variant: &syn::Variant) -> syn::Result<Arm> {
-    let variant_ident = &variant.ident;
-    let variant_name = variant_ident.to_string();
+/// Generates `fn debug_dedup_key(&self) -> u64` for `#[debug_stub(dedup_key)]`, hashing the Debug
+/// output of the value, with its stubs applied, without allocating it
+fn implement_dedup_key(
+    ident: &Ident,
+    generics: &Generics,
+    trait_path: &Path,
+) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let output = implement_debug_with(quote!(<Self as #trait_path>::fmt(self, f)));
 
-    match &variant.fields {
-        Fields::Named(FieldsNamed { named, .. }) => {
-            let fields = named
-                .iter()
-                .map(|field| {
-                    let ident = field
-                        .ident
-                        .clone()
-                        .expect("Tuple struct variant has unnamed fields");
-                    (ident.clone(), &field.attrs[..], Some(ident.to_string()))
-                })
-                .collect();
-            let (pats, stmts) = generate_enum_variant_fields(fields)?;
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns a hash of the `#[derive(DebugStub)]` output of `self`, which is equal for
+            /// values printed identically. It is only as stable as that output, which may change
+            /// between Rust versions.
+            pub fn debug_dedup_key(&self) -> u64 {
+                // FNV-1a over the bytes of the output
+                struct FnvWriter(u64);
 
-            Ok(parse_quote! {
-                #ident::#variant_ident { #(#pats),* } => {
-                    let mut f = f.debug_struct(#variant_name);
-                    #(#stmts)*
-                    f.finish()
+                impl ::core::fmt::Write for FnvWriter {
+                    fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+                        for byte in s.bytes() {
+                            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+                        }
+                        ::core::result::Result::Ok(())
+                    }
                 }
-            })
+
+                let mut writer = FnvWriter(0xcbf2_9ce4_8422_2325);
+                let _ = ::core::fmt::Write::write_fmt(&mut writer, format_args!("{:?}", #output));
+                writer.0
+            }
         }
-        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
-            let fields = unnamed
+    }
+}
+
+/// Generates an expression evaluating the code generated by `body` for the struct or for the
+/// matched enum variant, given the struct or variant name and its rendered fields, which print
+/// through the `#[debug_stub(redactor = "...")]` like in the Debug impl
+fn implement_for_each_variant(
+    ast: &DeriveInput,
+    redactor: Option<&Path>,
+    body: impl Fn(&str, &[RenderedField]) -> proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let redact = |fields: &mut [RenderedField]| {
+        if let Some(redactor) = redactor {
+            apply_redactor(fields, redactor);
+        }
+    };
+
+    match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => {
+            let mut fields = generate_struct_fields(fields)?;
+            redact(&mut fields);
+            Ok(body(&ident.to_string(), &fields))
+        }
+        Data::Enum(DataEnum { variants, .. }) => {
+            let arms = variants
                 .iter()
-                .enumerate()
-                .map(|(index, field)| {
-                    (
-                        Ident::new(&format!("tuple_{}", index), Span::call_site()),
-                        &field.attrs[..],
-                        None,
-                    )
+                .map(|variant| {
+                    let (pat, mut fields) = generate_variant_fields(ident, variant, false)?;
+                    redact(&mut fields);
+                    let body = body(&variant.ident.to_string(), &fields);
+                    Ok(quote!(#pat => { #body }))
                 })
-                .collect();
-            let (pats, stmts) = generate_enum_variant_fields(fields)?;
+                .collect::<syn::Result<Vec<_>>>()?;
 
-            Ok(parse_quote! {
-                #ident::#variant_ident( #(#pats),* ) => {
-                    let mut f = f.debug_tuple(#variant_name);
-                    #(#stmts)*
-                    f.finish()
-                }
-            })
+            Ok(quote!(match self { #(#arms)* }))
         }
-        Fields::Unit => Ok(parse_quote! {
-            #ident::#variant_ident => f.write_str(#variant_name),
-        }),
+        Data::Union(_) => unreachable!("unions are rejected before"),
     }
 }
 
-/// Generates match arm pattern and Formatter statements for an enum variant
-fn generate_enum_variant_fields(
-    fields: Vec<(Ident, &[Attribute], Option<String>)>,
-) -> syn::Result<(Vec<Pat>, Vec<Stmt>)> {
-    let mut pats = vec![];
-    let mut unused_fields = false;
+/// Generates the `to_dot()` method for `#[debug_stub(dot)]`, which renders an instance as a
+/// Graphviz graph with one edge per field, leading to a leaf labelled with the field's output
+fn implement_dot(
+    ast: &DeriveInput,
+    generics: &Generics,
+    redactor: Option<&Path>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let body = implement_for_each_variant(ast, redactor, implement_dot_node)?;
 
-    let stmts = fields
-        .into_iter()
-        .map(|(ident, attrs, name)| {
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Renders this value as a Graphviz DOT graph.
+            pub fn to_dot(&self) -> ::std::string::String {
+                let mut dot = ::std::string::String::from("digraph {\n");
+                #body
+                dot.push_str("}\n");
+                dot
+            }
+        }
+    })
+}
+
+/// Generates the statements appending a DOT root node (`node0`) and its field leaves to `dot`
+fn implement_dot_node(name: &str, fields: &[RenderedField]) -> proc_macro2::TokenStream {
+    let leaves = fields.iter().enumerate().map(|(index, field)| {
+        let node = index + 1;
+        let label = field.label(index);
+        let value = &field.value;
+
+        field.unless_skipped(quote! {
+            dot.push_str(&::std::format!(
+                "    node{0} [label={1:?}];\n    node0 -> node{0} [label={2:?}];\n",
+                #node,
+                ::std::format!("{:?}", &#value),
+                #label,
+            ));
+        })
+    });
+
+    quote! {
+        dot.push_str(&::std::format!("    node0 [label={:?}];\n", #name));
+        #(#leaves)*
+    }
+}
+
+/// Generates the `debug_fields()` method for `#[debug_stub(debug_fields)]`, which returns the name
+/// and Debug output of each printed field
+fn implement_debug_fields(
+    ast: &DeriveInput,
+    generics: &Generics,
+    redactor: Option<&Path>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let body = implement_for_each_variant(ast, redactor, |_, fields| {
+        let entries = fields.iter().enumerate().map(|(index, field)| {
+            let label = field.label(index);
+            let value = &field.value;
+            field.unless_skipped(quote! {
+                debug_fields.push((#label, ::std::format!("{:?}", &#value)));
+            })
+        });
+        quote! {
+            let mut debug_fields = ::std::vec::Vec::new();
+            #(#entries)*
+            debug_fields
+        }
+    })?;
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the name and Debug output of each field printed by the Debug impl.
+            pub fn debug_fields(
+                &self,
+            ) -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                #body
+            }
+        }
+    })
+}
+
+/// Returns whether any field of the struct or its enum variants is marked `#[debug_stub(brief)]`
+fn has_brief_fields(ast: &DeriveInput) -> bool {
+    match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => {
+            fields.iter().any(|field| is_brief_field(&field.attrs))
+        }
+        Data::Enum(DataEnum { variants, .. }) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .any(|field| is_brief_field(&field.attrs)),
+        Data::Union(_) => false,
+    }
+}
+
+/// Generates the `brief()` method for fields marked `#[debug_stub(brief)]`, which returns a one-line
+/// summary of the struct or variant name followed by the marked fields, like `Job id=42 state=Running`
+fn implement_brief(
+    ast: &DeriveInput,
+    generics: &Generics,
+    redactor: Option<&Path>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let body = implement_for_each_variant(ast, redactor, |name, fields| {
+        let entries = fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.brief)
+            .map(|(index, field)| {
+                let format = format!(" {}={{:?}}", field.label(index));
+                let value = &field.value;
+                field.unless_skipped(quote!(f.write_fmt(format_args!(#format, &#value))?;))
+            });
+        // enum variants bind every printed field, which the summary may leave out
+        let skipped = fields
+            .iter()
+            .filter(|field| field.used && !field.brief)
+            .map(|field| &field.access);
+        quote!({
+            #(let _ = #skipped;)*
+            f.write_str(#name)?;
+            #(#entries)*
+            ::core::result::Result::Ok(())
+        })
+    })?;
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns a one-line summary of this value, printing only the fields marked
+            /// `#[debug_stub(brief)]`.
+            pub fn brief(&self) -> impl ::core::fmt::Display + '_ {
+                struct Brief<F>(F);
+
+                impl<F> ::core::fmt::Display for Brief<F>
+                where
+                    F: Fn(&mut ::core::fmt::Formatter) -> ::core::fmt::Result,
+                {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        (self.0)(f)
+                    }
+                }
+
+                Brief(move |f: &mut ::core::fmt::Formatter| #body)
+            }
+        }
+    })
+}
+
+/// Generates the `DEBUG_STUB_EXPLAIN` constant for `#[debug_stub(explain)]`, which describes the
+/// added generic bounds, including the `#[debug_stub(bound = "...")]` ones, and how each field is
+/// printed
+fn implement_explain(
+    ast: &DeriveInput,
+    generics: &Generics,
+    extra_bounds: &[String],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut lines = vec![ident.to_string()];
+    let bounds = generics
+        .type_params()
+        .zip(ast.generics.type_params())
+        .filter(|(bounded, original)| bounded.bounds.len() > original.bounds.len())
+        .map(|(bounded, _)| format!("{}: Debug", bounded.ident))
+        .chain(extra_bounds.iter().cloned())
+        .collect::<Vec<_>>();
+    if bounds.is_empty() {
+        lines.push("  bounds: none".to_string());
+    } else {
+        lines.push(format!("  bounds: {}", bounds.join(", ")));
+    }
+
+    for (label, description) in describe_fields(ast)? {
+        lines.push(format!("  {}: {}", label, description));
+    }
+
+    let explanation = lines.join("\n");
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Describes how `#[derive(DebugStub)]` prints this type.
+            pub const DEBUG_STUB_EXPLAIN: &'static str = #explanation;
+        }
+    })
+}
+
+/// Generates the `DEBUG_FORMAT_FINGERPRINT` constant for `#[debug_stub(fingerprint)]`, a hash of
+/// the shape of the Debug output: the type, variant, and field names, their order, and the stubs
+fn implement_fingerprint(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let fields_shape = |fields: &Fields| match fields {
+        Fields::Named(_) => " {}",
+        Fields::Unnamed(_) => " ()",
+        Fields::Unit => "",
+    };
+    let mut shape = vec![ident.to_string()];
+    match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => shape.push(fields_shape(fields).to_string()),
+        Data::Enum(DataEnum { variants, .. }) => shape.extend(
+            variants
+                .iter()
+                .map(|variant| format!("{}{}", variant.ident, fields_shape(&variant.fields))),
+        ),
+        Data::Union(_) => unreachable!("unions are rejected before"),
+    }
+    for (label, description) in describe_fields(ast)? {
+        shape.push(format!("{}: {}", label, description));
+    }
+
+    // FNV-1a, so that the fingerprint does not change between Rust versions
+    let fingerprint = shape
+        .join("\n")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Hash of the shape of the `#[derive(DebugStub)]` output of this type, which changes
+            /// whenever the names, order, or stubs of the printed fields do.
+            pub const DEBUG_FORMAT_FINGERPRINT: u64 = #fingerprint;
+        }
+    })
+}
+
+/// Checks the expressions given to `expr` and `with` for `.clone()` calls, which copy the
+/// data on every format, rejecting them for `#[debug_stub(strict)]` and otherwise generating a
+/// warning for each. Proc macros cannot emit warnings on stable, so the warning is the one for
+/// using a deprecated constant, spanned on the call
+fn check_clones(ast: &DeriveInput, strict: bool) -> syn::Result<proc_macro2::TokenStream> {
+    let fields: Vec<&Field> = match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields.iter().collect(),
+        Data::Enum(DataEnum { variants, .. }) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .collect(),
+        Data::Union(_) => return Ok(quote!()),
+    };
+
+    let mut warnings = proc_macro2::TokenStream::new();
+    for field in fields {
+        let stub = match parse_field_stub(&field.attrs)? {
+            Some(stub) => stub,
+            None => continue,
+        };
+        let (key, call) = match stub.clone_call() {
+            Some(clone_call) => clone_call,
+            None => continue,
+        };
+        if strict {
+            return Err(syn::Error::new_spanned(
+                call,
+                format!(
+                    "`{}` clones the field on every format, which `strict` does not allow",
+                    key
+                ),
+            ));
+        }
+        let note = format!(
+            "`{}` clones the field on every format; borrow it instead, or deny this with \
+             `#[debug_stub(strict)]`",
+            key
+        );
+        let constant = quote_spanned!(call.method.span()=> debug_stub_clone);
+        warnings.extend(quote! {
+            const _: () = {
+                #[deprecated(note = #note)]
+                #[allow(non_upper_case_globals)]
+                const debug_stub_clone: () = ();
+                #constant
+            };
+        });
+    }
+    Ok(warnings)
+}
+
+/// Returns the first `.clone()` call in the given expression, including the arguments of macros
+/// like `write!` which parse as expressions
+fn find_clone_call(expr: &Expr) -> Option<syn::ExprMethodCall> {
+    struct CloneCall(Option<syn::ExprMethodCall>);
+
+    impl<'ast> Visit<'ast> for CloneCall {
+        fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+            if self.0.is_none() && call.method == "clone" && call.args.is_empty() {
+                self.0 = Some(call.clone());
+            }
+            syn::visit::visit_expr_method_call(self, call);
+        }
+
+        fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+            if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Comma>::parse_terminated) {
+                for arg in &args {
+                    self.visit_expr(arg);
+                }
+            }
+        }
+    }
+
+    let mut visitor = CloneCall(None);
+    visitor.visit_expr(expr);
+    visitor.0
+}
+
+/// Rejects field stubs and container options with side effects for `#[debug_stub(pure)]`
+fn check_pure(ast: &DeriveInput, redactor: Option<&Path>) -> syn::Result<()> {
+    if let Some(redactor) = redactor {
+        return Err(syn::Error::new_spanned(
+            redactor,
+            "`redactor` has side effects, which `pure` does not allow",
+        ));
+    }
+
+    let fields: Vec<&Field> = match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields.iter().collect(),
+        Data::Enum(DataEnum { variants, .. }) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .collect(),
+        Data::Union(_) => return Ok(()),
+    };
+
+    for field in fields {
+        let key = match parse_field_stub(&field.attrs)? {
+            Some(stub) => stub.side_effect(),
+            None => None,
+        };
+        // `skip_if` calls a user predicate on the field, whatever its stub
+        let key = match field_skip_condition(&field.attrs)? {
+            Some(SkipCondition::If(_)) => key.or(Some("skip_if")),
+            _ => key,
+        };
+        if let Some(key) = key {
+            let attr = field
+                .attrs
+                .iter()
+                .find(|attr| attr.path.is_ident("debug_stub"));
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("`{}` has side effects, which `pure` does not allow", key),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the type parameters that only appear in the types of fields whose printed value does not
+/// use the field, like `#[debug_stub = "..."]` fields. Those types are never printed, so their
+/// parameters need no `fmt::Debug` bound, however complex the types are (like
+/// `Box<dyn Fn(&mut T) + Send + 'static>`)
+fn opaque_type_params(ast: &DeriveInput) -> syn::Result<Vec<Ident>> {
+    // collects every identifier of a type, which includes the type parameters it mentions
+    struct Idents(Vec<Ident>);
+
+    impl<'ast> Visit<'ast> for Idents {
+        fn visit_ident(&mut self, ident: &'ast Ident) {
+            self.0.push(ident.clone());
+        }
+    }
+
+    let fields: Vec<&Field> = match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields.iter().collect(),
+        Data::Enum(DataEnum { variants, .. }) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .collect(),
+        Data::Union(_) => return Ok(vec![]),
+    };
+
+    let (mut printed, mut opaque) = (Idents(vec![]), Idents(vec![]));
+    for field in fields {
+        let used = match parse_field_stub(&field.attrs)? {
+            Some(stub) => implement_stub(&stub, &parse_quote!(value), &field.ty).0,
+            None => true,
+        };
+        if used {
+            printed.visit_type(&field.ty);
+        } else {
+            opaque.visit_type(&field.ty);
+        }
+    }
+
+    Ok(ast
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .filter(|ident| opaque.0.contains(ident) && !printed.0.contains(ident))
+        .cloned()
+        .collect())
+}
+
+/// Returns the label (`field`, or `Variant.field` for enums) and the attribute syntax description
+/// of how each field is printed, like `("Variant.0", "limit = 3")`
+fn describe_fields(ast: &DeriveInput) -> syn::Result<Vec<(String, String)>> {
+    let describe = |prefix: &str, fields: &[RenderedField]| {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let stub = field
+                    .stub
+                    .as_ref()
+                    .map_or_else(|| "debug".to_string(), FieldStub::describe);
+                let description = match &field.skip {
+                    Some(skip) => format!("{}, {}", stub, skip.describe()),
+                    None => stub,
+                };
+                (format!("{}{}", prefix, field.label(index)), description)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => {
+            Ok(describe("", &generate_struct_fields(fields)?))
+        }
+        Data::Enum(DataEnum { variants, .. }) => {
+            let mut descriptions = vec![];
+            for variant in variants {
+                let (_, fields) = generate_variant_fields(&ast.ident, variant, false)?;
+                descriptions.extend(describe(&format!("{}.", variant.ident), &fields));
+            }
+            Ok(descriptions)
+        }
+        Data::Union(_) => unreachable!("unions are rejected before"),
+    }
+}
+
+/// Writes a `type<TAB>field<TAB>description` line per field to
+/// `$OUT_DIR/debug_stub_report/<type>.tsv` if the `DEBUG_STUB_REPORT` environment variable is set,
+/// building an inventory of how each type is printed. Expansions without an `OUT_DIR` (crates
+/// without a build script) are not recorded. Returns an item reading the variable with
+/// `option_env!`, which makes cargo rebuild the crate when it changes
+fn write_report(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let tracked = quote!(
+        const _: ::core::option::Option<&str> = option_env!("DEBUG_STUB_REPORT");
+    );
+
+    match std::env::var_os("DEBUG_STUB_REPORT") {
+        Some(report) if !report.is_empty() => {}
+        _ => return Ok(tracked),
+    }
+    let out_dir = match std::env::var_os("OUT_DIR") {
+        Some(out_dir) => std::path::PathBuf::from(out_dir).join("debug_stub_report"),
+        None => return Ok(tracked),
+    };
+
+    let records = describe_fields(ast)?
+        .into_iter()
+        .map(|(label, description)| format!("{}\t{}\t{}\n", ast.ident, label, description))
+        .collect::<String>();
+
+    std::fs::create_dir_all(&out_dir)
+        .and_then(|()| std::fs::write(out_dir.join(format!("{}.tsv", ast.ident)), records))
+        .map(|()| tracked)
+        .map_err(|err| {
+            syn::Error::new(
+                Span::call_site(),
+                format!("failed to write the `debug_stub` report: {}", err),
+            )
+        })
+}
+
+/// A single field of the derived type, as it is printed by the generated code
+struct RenderedField {
+    /// Field name, or `None` for tuple fields
+    name: Option<String>,
+    /// `fmt::Debug` value printed for the field
+    value: Expr,
+    /// Stub the value was generated from, or `None` if the field is debugged as is
+    stub: Option<FieldStub>,
+    /// Expression accessing the field (as `&T`)
+    access: Expr,
+    /// Whether `value` uses the field at all
+    used: bool,
+    /// Whether the field is marked `#[debug_stub(brief)]`
+    brief: bool,
+    /// Priority given by `#[debug_stub(priority = N)]`, or 0
+    priority: usize,
+    /// Condition under which the field is left out, like `#[debug_stub(skip_if = "...")]`
+    skip: Option<SkipCondition>,
+}
+
+impl RenderedField {
+    /// Returns the field name, or the field index for tuple fields
+    fn label(&self, index: usize) -> String {
+        self.name.clone().unwrap_or_else(|| index.to_string())
+    }
+
+    /// Wraps statements printing the field so that they only run if it is not skipped
+    fn unless_skipped(&self, stmts: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match &self.skip {
+            Some(skip) => {
+                let condition = skip.implement(&self.access);
+                quote!(if !#condition { #stmts })
+            }
+            None => stmts,
+        }
+    }
+}
+
+/// Keys of the field markers giving a `SkipCondition`
+const SKIP_KEYS: &[&str] = &["skip_if", "skip_none", "skip_empty", "skip_default"];
+
+/// Condition under which a field is left out of the output
+enum SkipCondition {
+    /// `#[debug_stub(skip_if = "...")]`, calling the predicate with a reference to the field
+    If(Path),
+    /// `#[debug_stub(skip_none)]`
+    None,
+    /// `#[debug_stub(skip_empty)]`
+    Empty,
+    /// `#[debug_stub(skip_default)]`
+    Default,
+}
+
+impl SkipCondition {
+    /// Generates the condition for the field accessed through `access`
+    fn implement(&self, access: &Expr) -> Expr {
+        match self {
+            SkipCondition::If(predicate) => parse_quote!(#predicate(#access)),
+            SkipCondition::None => parse_quote!(::core::option::Option::is_none(#access)),
+            SkipCondition::Empty => parse_quote!((#access).is_empty()),
+            SkipCondition::Default => parse_quote!({
+                fn is_default<T>(value: &T) -> bool
+                where
+                    T: ::core::default::Default + ::core::cmp::PartialEq,
+                {
+                    *value == T::default()
+                }
+
+                is_default(#access)
+            }),
+        }
+    }
+
+    /// Describes the condition in attribute syntax, for `#[debug_stub(explain)]`
+    fn describe(&self) -> String {
+        match self {
+            SkipCondition::If(predicate) => format!("skip_if = {:?}", describe_tokens(predicate)),
+            SkipCondition::None => "skip_none".to_string(),
+            SkipCondition::Empty => "skip_empty".to_string(),
+            SkipCondition::Default => "skip_default".to_string(),
+        }
+    }
+}
+
+/// Generates the rendered fields of a struct, accessing them through `self` (`self.a`, `self.0`)
+fn generate_struct_fields(fields: &Fields) -> syn::Result<Vec<RenderedField>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let (expr, name) = match &field.ident {
+                Some(ident) => (parse_quote!((&self.#ident)), Some(ident.to_string())),
+                None => {
+                    let index = syn::Index::from(index);
+                    (parse_quote!((&self.#index)), None)
+                }
+            };
+            extract_value_attr(&expr, field, name)
+        })
+        .collect()
+}
+
+/// Generates a single match arm for an enum Debug impl
+fn generate_arm(
+    ident: &Ident,
+    variant: &syn::Variant,
+    mark_used: bool,
+    layout: Layout,
+    max_output: Option<usize>,
+    redactor: Option<&Path>,
+) -> syn::Result<Arm> {
+    let variant_name = variant.ident.to_string();
+    // `expr = "..."` may refer to any field of the variant, so all of them need to be bound
+    let mut mark_used = mark_used;
+    for field in &variant.fields {
+        if let Some(stub) = parse_field_stub(&field.attrs)? {
+            mark_used |= stub.contains_expr();
+        }
+    }
+    let (pat, mut fields) = generate_variant_fields(ident, variant, mark_used)?;
+    if let Some(redactor) = redactor {
+        apply_redactor(&mut fields, redactor);
+    }
+    let prelude = match max_output {
+        Some(max_output) => implement_output_budget(&mut fields, max_output),
+        None => vec![],
+    };
+    let mut stmts = fields.iter().map(implement_field_stmt).collect::<Vec<_>>();
+    if mark_used {
+        stmts.extend(implement_mark_used_stmts(&fields));
+    }
+
+    Ok(match &variant.fields {
+        Fields::Named(_) | Fields::Unnamed(_) => {
+            let named = matches!(variant.fields, Fields::Named(_));
+            let builder = layout.builder(&variant_name, named);
+            parse_quote! {
+                #pat => {
+                    #(#prelude)*
+                    let mut f = #builder;
+                    #(#stmts)*
+                    f.finish()
+                }
+            }
+        }
+        Fields::Unit => parse_quote! {
+            #pat => f.write_str(#variant_name),
+        },
+    })
+}
+
+/// Generates the match pattern and rendered fields for a single enum variant. With `mark_used`, the
+/// pattern also binds fields the rendered values do not use
+fn generate_variant_fields(
+    ident: &Ident,
+    variant: &syn::Variant,
+    mark_used: bool,
+) -> syn::Result<(Pat, Vec<RenderedField>)> {
+    let variant_ident = &variant.ident;
+
+    match &variant.fields {
+        Fields::Named(FieldsNamed { named, .. }) => {
+            let fields = named
+                .iter()
+                .map(|field| {
+                    let ident = field
+                        .ident
+                        .clone()
+                        .expect("Tuple struct variant has unnamed fields");
+                    (ident.clone(), field, Some(ident.to_string()))
+                })
+                .collect();
+            let (pats, fields) = generate_enum_variant_fields(fields, mark_used)?;
+
+            Ok((parse_quote!(#ident::#variant_ident { #(#pats),* }), fields))
+        }
+        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
+            let fields = unnamed
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    (
+                        Ident::new(&format!("tuple_{}", index), Span::call_site()),
+                        field,
+                        None,
+                    )
+                })
+                .collect();
+            let (pats, fields) = generate_enum_variant_fields(fields, mark_used)?;
+
+            Ok((parse_quote!(#ident::#variant_ident( #(#pats),* )), fields))
+        }
+        Fields::Unit => Ok((parse_quote!(#ident::#variant_ident), vec![])),
+    }
+}
+
+/// Generates match arm pattern and rendered fields for an enum variant
+fn generate_enum_variant_fields(
+    fields: Vec<(Ident, &Field, Option<String>)>,
+    mark_used: bool,
+) -> syn::Result<(Vec<Pat>, Vec<RenderedField>)> {
+    let mut pats = vec![];
+    let mut unused_fields = false;
+
+    let fields = fields
+        .into_iter()
+        .map(|(ident, field, name)| {
             let unnamed = name.is_none();
-            let (ident_used, stmt) = extract_value_attr(&parse_quote!(#ident), attrs, name)?;
+            let field = extract_value_attr(&parse_quote!(#ident), field, name)?;
+
+            if field.used || mark_used {
+                pats.push(parse_quote!(#ident));
+            } else if unnamed {
+                // Skip unused tuple fields to avoid "unused variable" warnings
+                pats.push(parse_quote!(_));
+            } else {
+                // Skip unused struct fields to avoid "unused variable" warnings
+                unused_fields = true;
+            }
+
+            Ok(field)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    if unused_fields {
+        pats.push(parse_quote!(..));
+    }
+
+    Ok((pats, fields))
+}
+
+/// Replacement rendering of a single field, as declared by its `#[debug_stub]` attribute
+enum FieldStub {
+    /// `#[debug_stub = "..."]`
+    Replace(String),
+    /// `#[debug_stub = "..."]` with placeholders like `{len}`, split into its parts
+    Template(Vec<TemplatePart>),
+    /// `#[debug_stub(const = "...")]`, replacing the field value with the text of the given constant
+    Const(Path),
+    /// `#[debug_stub(some = "...", none = "...")]`, where either side may be omitted and `some`
+    /// may be nested like `some(ok = "...")`
+    Option(Option<Box<FieldStub>>, Option<String>),
+    /// `#[debug_stub(ok = "...", err = "...")]`, where either side may be omitted or nested like
+    /// `ok(some = "...")`
+    Result(
+        Option<Box<FieldStub>>,
+        Option<Box<FieldStub>>,
+        ErrPassthrough,
+    ),
+    /// `#[debug_stub(future_state = "...")]`
+    FutureState(Path),
+    /// `#[debug_stub(len)]`
+    Len,
+    /// `#[debug_stub(type_name)]`
+    TypeName,
+    /// `#[debug_stub(shallow)]`
+    Shallow,
+    /// `#[debug_stub(opaque)]`
+    Opaque,
+    /// `#[debug_stub(scientific)]` or `#[debug_stub(scientific = "E")]`, with whether the exponent
+    /// is written in uppercase
+    Scientific(bool),
+    /// `#[debug_stub(separators)]` or `#[debug_stub(separators = "...")]`, with the separator
+    Separators(String),
+    /// `#[debug_stub(hash)]`
+    Hash,
+    /// `#[debug_stub(limit = N)]`
+    Limit(usize),
+    /// `#[debug_stub(sha256)]`
+    Sha256,
+    /// `#[debug_stub(display)]`, optionally with `sanitize`
+    Display { sanitize: bool },
+    /// `#[debug_stub(truncate = N)]`
+    Truncate(usize),
+    /// `#[debug_stub(peek = N)]`
+    Peek(usize),
+    /// `#[debug_stub(first = N)]`
+    First(usize),
+    /// `#[debug_stub(utc)]`
+    Utc(DateTimeCrate),
+    /// `#[debug_stub(rfc3339)]`
+    Rfc3339(DateTimeCrate),
+    /// `#[debug_stub(be)]` and `#[debug_stub(le)]`
+    ByteOrder(ByteOrder),
+    /// `#[debug_stub(head = N, tail = M)]`, where either side may be omitted
+    HeadTail { head: usize, tail: usize },
+    /// `#[debug_stub(crc32)]`
+    Crc32,
+    /// `#[debug_stub(any_of(...), else = "...")]`, where `else` may be omitted
+    AnyOf(Vec<Path>, Option<String>),
+    /// `#[debug_stub(variant_counts)]`
+    VariantCounts,
+    /// `#[debug_stub(path)]`
+    Path,
+    /// `#[debug_stub(json)]` or `#[debug_stub(json(redact(...)))]`, with the redacted keys
+    Json(Vec<String>),
+    /// `#[debug_stub(c_str)]`
+    CStr,
+    /// `#[debug_stub(duration)]`
+    Duration,
+    /// `#[debug_stub(bytes)]`
+    Bytes,
+    /// `#[debug_stub(bits(format = "...", ...))]`, with the format and names as written, and the
+    /// bit groups they describe
+    Bits {
+        format: String,
+        names: Vec<(char, String)>,
+        groups: Vec<BitGroup>,
+    },
+    /// `#[debug_stub(elapsed)]`
+    Elapsed,
+    /// `#[debug_stub(counter)]`
+    Counter,
+    /// `#[debug_stub(ptr)]`, optionally with `addr_style = "..."`
+    Ptr(AddrStyle),
+    /// `#[debug_stub(each = "...")]`, which may be nested like `each(some = "...")`
+    Each(Box<FieldStub>),
+    /// `#[debug_stub(key = "...", value = "...")]`, where either side may be omitted or nested,
+    /// and optionally `filter_keys = "..."` or `filter_keys_with = "..."`
+    Map(
+        Option<Box<FieldStub>>,
+        Option<Box<FieldStub>>,
+        Option<KeyFilter>,
+    ),
+    /// `#[debug_stub(set = "...")]`, which may be nested like `set(hash)`
+    Set(Box<FieldStub>),
+    /// `#[debug_stub(boxed = "...")]`, `#[debug_stub(rc = "...")]`, and
+    /// `#[debug_stub(arc = "...")]`, which may be nested like `boxed(some = "...")`
+    SmartPointer(SmartPointer, Box<FieldStub>),
+    /// `#[debug_stub(mutex)]`, `#[debug_stub(rwlock)]`, and `#[debug_stub(refcell)]`, optionally with a replacement like
+    /// `mutex = "..."` or nested like `mutex(len)`
+    Lock(Lock, Option<Box<FieldStub>>),
+    /// `#[debug_stub(cell)]`, optionally with a replacement like `cell = "..."` or nested like
+    /// `cell(hash)`
+    Cell(Option<Box<FieldStub>>),
+    /// `#[debug_stub(uninit)]`, optionally with a replacement like `uninit = "..."`
+    Uninit(Option<String>),
+    /// `#[debug_stub(unsafe_assume_init)]`, optionally nested like `unsafe_assume_init(len)`
+    AssumeInit(Option<Box<FieldStub>>),
+    /// `#[debug_stub(cow)]`, optionally with a replacement like `cow = "..."` or nested like
+    /// `cow(len)`
+    Cow(Option<Box<FieldStub>>),
+    /// `#[debug_stub(once)]`, optionally with a replacement like `once = "..."` or nested like
+    /// `once(len)` for the initialized value
+    Once(Option<Box<FieldStub>>),
+    /// `#[debug_stub(atomic)]`, optionally nested like `atomic(be)` for the loaded value
+    Atomic(Option<Box<FieldStub>>),
+    /// `#[debug_stub(weak)]`, optionally with a replacement like `weak = "..."` or nested like
+    /// `weak(len)` for the upgraded value
+    Weak(Option<Box<FieldStub>>),
+    /// `#[debug_stub(counts)]`, optionally with a replacement like `counts = "..."` or nested like
+    /// `counts(len)` for the pointee
+    Counts(Option<Box<FieldStub>>),
+    /// `#[debug_stub(as_inner)]`, optionally with `inner = "..."` naming the `AsRef` target
+    AsInner(Option<Type>),
+    /// `#[debug_stub(deref_to = "...")]`, printing the field as the given `Deref` target type
+    DerefTo(Type),
+    /// `#[debug_stub(as_deref)]`, optionally with the `some` and `none` stubs applied to the
+    /// dereferenced option
+    AsDeref(Option<Box<FieldStub>>),
+    /// `#[debug_stub(resolve = "...")]`, printing the name looked up for an ID field next to it
+    Resolve(Path),
+    /// `#[debug_stub(method = "...")]`, printing the return value of the given method of the field
+    Method(Ident),
+    /// `#[debug_stub(getter = "...")]`, printing the return value of the given function called
+    /// with the field
+    Getter(Path),
+    /// `#[debug_stub(via = "...")]`, printing the field converted into the given type with `From`
+    Via(Type),
+    /// `#[debug_stub(expr = "...")]`, printing the given expression instead of the field
+    Expr(Expr),
+    /// `#[debug_stub(with = |v, f| ...)]` or `#[debug_stub(with = "...")]`, printing the field
+    /// with the given closure or function
+    With(Expr),
+    /// `#[debug_stub(wrap = "...")]`, printing the field through the given adapter newtype
+    Wrap(Path),
+    /// `#[debug_stub(trait)]` or `#[debug_stub(trait = "...")]`, printing the field through the
+    /// `stub_fmt()` method of the `StubDebug` trait in scope, or of the given one
+    StubTrait(Option<Path>),
+    /// Text transforms like `max_len = N` applied in order to the output of a stub, or of the
+    /// field printed as is
+    Pipeline(Option<Box<FieldStub>>, Vec<Transform>),
+    /// `#[debug_stub(precision = N)]`, printing the floats in the output of a stub, or of the field
+    /// printed as is, with `N` decimals, or `#[debug_stub(inherit_precision)]` with `None`, passing
+    /// on the precision of the call site to keys which render it into text
+    Precision(Option<Box<FieldStub>>, Option<usize>),
+    /// `#[debug_stub(on_error = "...")]`, printing the marker instead of the output of a stub, or
+    /// of the field printed as is, if it panics or fails
+    OnError(Option<Box<FieldStub>>, String),
+    /// `#[debug_stub(if = "...", then = "...")]`, printing the field through the `then` stub if
+    /// the predicate returns true for it, and as is otherwise
+    If(Path, Box<FieldStub>),
+    /// `#[debug_stub(release_only = "...")]`, or nested like `release_only(len)`, printing the field
+    /// through the stub only without `debug_assertions`
+    ReleaseOnly(Box<FieldStub>),
+    /// `#[debug_stub(spill(...))]`, writing the output of a stub, or of the field printed as is, to
+    /// a file if it is too long
+    Spill(Option<Box<FieldStub>>, Spill),
+    /// `#[debug_stub(alternate = "...")]`, printing the field through the nested stub in alternate
+    /// mode, and through the other keys, or as is, otherwise
+    Alternate(Option<Box<FieldStub>>, Box<FieldStub>),
+}
+
+/// Part of a replacement text with placeholders, like `<{type}, {len} bytes>`
+enum TemplatePart {
+    /// Literal text, with `{{` and `}}` already unescaped
+    Text(String),
+    /// `{len}`, the length of the field
+    Len,
+    /// `{type}`, the name of the field type
+    Type,
+    /// `{ptr}`, the address of the field
+    Ptr,
+}
+
+impl TemplatePart {
+    /// Describes the part as written in the replacement text, for `#[debug_stub(explain)]`
+    fn describe(&self) -> String {
+        match self {
+            TemplatePart::Text(text) => text.clone(),
+            TemplatePart::Len => "{len}".to_string(),
+            TemplatePart::Type => "{type}".to_string(),
+            TemplatePart::Ptr => "{ptr}".to_string(),
+        }
+    }
+}
+
+/// Filter of the entries printed for a map field
+enum KeyFilter {
+    /// `filter_keys = "..."`, keeping entries whose key starts with the prefix
+    Prefix(String),
+    /// `filter_keys_with = "..."`, keeping entries for which the predicate returns true
+    With(Path),
+}
+
+impl KeyFilter {
+    /// Describes the filter in attribute syntax, for `#[debug_stub(explain)]`
+    fn describe(&self) -> String {
+        match self {
+            KeyFilter::Prefix(prefix) => format!("filter_keys = {:?}", prefix),
+            KeyFilter::With(predicate) => {
+                format!("filter_keys_with = {:?}", describe_tokens(predicate))
+            }
+        }
+    }
+}
+
+/// Transform of the printed text of a field, chained through `FieldStub::Pipeline`
+enum Transform {
+    /// `max_len = N`, cutting the text after `N` characters
+    MaxLen(usize),
+    /// `mask`, or `mask(keep_start = N, keep_end = M)`, replacing all but the first `N` and last `M`
+    /// characters with `*`
+    Mask { keep_start: usize, keep_end: usize },
+}
+
+impl Transform {
+    /// Describes the transform in attribute syntax, for `#[debug_stub(explain)]`
+    fn describe(&self) -> String {
+        match self {
+            Transform::MaxLen(max_len) => format!("max_len = {}", max_len),
+            Transform::Mask {
+                keep_start,
+                keep_end,
+            } => format!("mask(keep_start = {}, keep_end = {})", keep_start, keep_end),
+        }
+    }
+}
+
+/// Named group of adjacent bits of an integer field, printed by `#[debug_stub(bits(...))]`
+struct BitGroup {
+    name: String,
+    /// Index of the lowest bit of the group
+    shift: usize,
+    width: usize,
+    /// Whether the group is printed in binary (`name:b`) rather than decimal
+    binary: bool,
+}
+
+/// Formatting of the addresses printed by `#[debug_stub(ptr)]`
+#[derive(Clone, Copy)]
+enum AddrStyle {
+    /// `addr_style = "short"`, the default, printing addresses like `{:p}` (`0x1000`)
+    Short,
+    /// `addr_style = "full"`, zero-padding addresses to the pointer width of the target
+    /// (`0x0000000000001000`)
+    Full,
+}
+
+/// Byte order of an integer field, as stored on the wire
+#[derive(Clone, Copy)]
+enum ByteOrder {
+    Big,
+    Little,
+}
+
+/// Smart pointer type of a field
+#[derive(Clone, Copy)]
+enum SmartPointer {
+    Box,
+    Rc,
+    Arc,
+}
+
+impl SmartPointer {
+    /// Returns the `debug_stub` key selecting the pointer type
+    fn key(self) -> &'static str {
+        match self {
+            SmartPointer::Box => "boxed",
+            SmartPointer::Rc => "rc",
+            SmartPointer::Arc => "arc",
+        }
+    }
+}
+
+/// Lock or cell type of a field, which is probed without blocking or panicking when printing it
+// variants are named after the std types
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy)]
+enum Lock {
+    Mutex,
+    RwLock,
+    RefCell,
+}
+
+impl Lock {
+    /// Returns the `debug_stub` key selecting the lock type
+    fn key(self) -> &'static str {
+        match self {
+            Lock::Mutex => "mutex",
+            Lock::RwLock => "rwlock",
+            Lock::RefCell => "refcell",
+        }
+    }
+}
+
+/// Modifiers of the `Err` value of a `#[debug_stub(ok = "...")]` field, which is printed as is
+#[derive(Default)]
+struct ErrPassthrough {
+    /// `err_max_len = N`, cutting the printed error after `N` characters
+    max_len: Option<usize>,
+    /// `err_display`, printing the error through its `fmt::Display` impl
+    display: bool,
+}
+
+/// Options of `#[debug_stub(spill(dir = "...", over = N))]`
+struct Spill {
+    /// `dir = "..."`, the directory of the files, or the temporary directory of the system
+    dir: Option<String>,
+    /// `over = N`, the length in bytes above which the text is written to a file
+    over: usize,
+}
+
+impl Spill {
+    /// Describes the options in attribute syntax, for `#[debug_stub(explain)]`
+    fn describe(&self) -> String {
+        match &self.dir {
+            Some(dir) => format!("spill(dir = {:?}, over = {})", dir, self.over),
+            None => format!("spill(over = {})", self.over),
+        }
+    }
+}
+
+impl ErrPassthrough {
+    /// Returns whether any modifier is set
+    fn is_set(&self) -> bool {
+        self.max_len.is_some() || self.display
+    }
+}
+
+/// Crate providing the date time type of a field, selected through its cargo feature
+#[derive(Clone, Copy)]
+enum DateTimeCrate {
+    /// `chrono::DateTime`
+    Chrono,
+    /// `time::OffsetDateTime`
+    Time,
+}
+
+impl FieldStub {
+    /// Returns the key of the first option of the stub, or of its nested stubs, whose generated
+    /// code has effects beyond reading the field, for `#[debug_stub(pure)]`: taking locks or
+    /// borrows, calling user functions, or reading the clock
+    fn side_effect(&self) -> Option<&'static str> {
+        match self {
+            FieldStub::Lock(lock, _) => Some(lock.key()),
+            FieldStub::FutureState(_) => Some("future_state"),
+            FieldStub::Resolve(_) => Some("resolve"),
+            FieldStub::Method(_) => Some("method"),
+            FieldStub::Getter(_) => Some("getter"),
+            FieldStub::Via(_) => Some("via"),
+            FieldStub::Expr(_) => Some("expr"),
+            FieldStub::With(_) => Some("with"),
+            FieldStub::StubTrait(_) => Some("trait"),
+            FieldStub::Map(_, _, Some(KeyFilter::With(_))) => Some("filter_keys_with"),
+            FieldStub::Elapsed => Some("elapsed"),
+            FieldStub::Counter => Some("counter"),
+            FieldStub::Spill(_, _) => Some("spill"),
+            FieldStub::If(_, _) => Some("if"),
+            _ => self
+                .nested()
+                .into_iter()
+                .find_map(|inner| inner.side_effect()),
+        }
+    }
+
+    /// Returns the first `.clone()` call in the expression of the stub, or of one of its nested
+    /// stubs, with the key giving the expression
+    fn clone_call(&self) -> Option<(&'static str, syn::ExprMethodCall)> {
+        match self {
+            FieldStub::Expr(expr) => find_clone_call(expr).map(|call| ("expr", call)),
+            FieldStub::With(with) => find_clone_call(with).map(|call| ("with", call)),
+            _ => self.nested().into_iter().find_map(FieldStub::clone_call),
+        }
+    }
+
+    /// Returns whether the stub, or one of its nested stubs, is `expr = "..."`, whose expression
+    /// may refer to any field of an enum variant
+    fn contains_expr(&self) -> bool {
+        match self {
+            FieldStub::Expr(_) => true,
+            _ => self.nested().into_iter().any(FieldStub::contains_expr),
+        }
+    }
+
+    /// Returns whether the stub, or one of its nested stubs, is `inherit_precision`
+    fn inherits_precision(&self) -> bool {
+        match self {
+            FieldStub::Precision(_, None) => true,
+            _ => self.nested().into_iter().any(FieldStub::inherits_precision),
+        }
+    }
+
+    /// Returns the stubs nested in this one, like the `some(...)` one of an `Option` stub
+    fn nested(&self) -> Vec<&FieldStub> {
+        let nested = match self {
+            FieldStub::Result(ok, err, _) => vec![ok, err],
+            FieldStub::Map(key, value, _) => vec![key, value],
+            FieldStub::Option(inner, _)
+            | FieldStub::Cell(inner)
+            | FieldStub::AssumeInit(inner)
+            | FieldStub::Cow(inner)
+            | FieldStub::Once(inner)
+            | FieldStub::Atomic(inner)
+            | FieldStub::Weak(inner)
+            | FieldStub::Counts(inner)
+            | FieldStub::Pipeline(inner, _)
+            | FieldStub::Precision(inner, _)
+            | FieldStub::OnError(inner, _)
+            | FieldStub::Spill(inner, _)
+            | FieldStub::AsDeref(inner) => vec![inner],
+            FieldStub::Each(inner)
+            | FieldStub::Set(inner)
+            | FieldStub::SmartPointer(_, inner)
+            | FieldStub::If(_, inner)
+            | FieldStub::ReleaseOnly(inner) => return vec![inner],
+            FieldStub::Alternate(compact, alternate) => {
+                return compact
+                    .iter()
+                    .chain(Some(alternate))
+                    .map(|inner| &**inner)
+                    .collect()
+            }
+            _ => vec![],
+        };
+        nested.into_iter().flatten().map(|inner| &**inner).collect()
+    }
+
+    /// Describes the stub in attribute syntax, for `#[debug_stub(explain)]`
+    fn describe(&self) -> String {
+        match self {
+            FieldStub::Replace(value) => format!("replace = {:?}", value),
+            FieldStub::Template(parts) => format!("replace = {:?}", describe_template(parts)),
+            FieldStub::Const(value) => format!("const = {:?}", describe_tokens(value)),
+            FieldStub::Option(some, none) => some
+                .iter()
+                .map(|some| describe_nested("some", some))
+                .chain(none.iter().map(|none| format!("none = {:?}", none)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::Result(ok, err, passthrough) => ok
+                .iter()
+                .map(|ok| describe_nested("ok", ok))
+                .chain(err.iter().map(|err| describe_nested("err", err)))
+                .chain(
+                    passthrough
+                        .max_len
+                        .map(|max_len| format!("err_max_len = {}", max_len)),
+                )
+                .chain(Some("err_display".to_string()).filter(|_| passthrough.display))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::FutureState(done) => format!("future_state = {:?}", describe_tokens(done)),
+            FieldStub::Len => "len".to_string(),
+            FieldStub::TypeName => "type_name".to_string(),
+            FieldStub::Shallow => "shallow".to_string(),
+            FieldStub::Opaque => "opaque".to_string(),
+            FieldStub::Scientific(false) => "scientific".to_string(),
+            FieldStub::Scientific(true) => "scientific = \"E\"".to_string(),
+            FieldStub::Separators(separator) if separator == "_" => "separators".to_string(),
+            FieldStub::Separators(separator) => format!("separators = {:?}", separator),
+            FieldStub::Hash => "hash".to_string(),
+            FieldStub::Limit(limit) => format!("limit = {}", limit),
+            FieldStub::Sha256 => "sha256".to_string(),
+            FieldStub::Display { sanitize: false } => "display".to_string(),
+            FieldStub::Display { sanitize: true } => "display, sanitize".to_string(),
+            FieldStub::Truncate(chars) => format!("truncate = {}", chars),
+            FieldStub::Peek(chars) => format!("peek = {}", chars),
+            FieldStub::First(first) => format!("first = {}", first),
+            FieldStub::Utc(DateTimeCrate::Chrono) => "utc = \"chrono\"".to_string(),
+            FieldStub::Utc(DateTimeCrate::Time) => "utc = \"time\"".to_string(),
+            FieldStub::Rfc3339(DateTimeCrate::Chrono) => "rfc3339 = \"chrono\"".to_string(),
+            FieldStub::Rfc3339(DateTimeCrate::Time) => "rfc3339 = \"time\"".to_string(),
+            FieldStub::ByteOrder(ByteOrder::Big) => "be".to_string(),
+            FieldStub::ByteOrder(ByteOrder::Little) => "le".to_string(),
+            FieldStub::HeadTail { head, tail } => format!("head = {}, tail = {}", head, tail),
+            FieldStub::Crc32 => "crc32".to_string(),
+            FieldStub::AnyOf(types, fallback) => Some(format!(
+                "any_of({})",
+                types
+                    .iter()
+                    .map(describe_tokens)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .into_iter()
+            .chain(
+                fallback
+                    .iter()
+                    .map(|fallback| format!("else = {:?}", fallback)),
+            )
+            .collect::<Vec<_>>()
+            .join(", "),
+            FieldStub::VariantCounts => "variant_counts".to_string(),
+            FieldStub::Path => "path".to_string(),
+            FieldStub::Json(redact) if redact.is_empty() => "json".to_string(),
+            FieldStub::Json(redact) => format!(
+                "json(redact({}))",
+                redact
+                    .iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            FieldStub::CStr => "c_str".to_string(),
+            FieldStub::Duration => "duration".to_string(),
+            FieldStub::Bytes => "bytes".to_string(),
+            FieldStub::Bits { format, names, .. } => format!(
+                "bits({})",
+                Some(format!("format = {:?}", format))
+                    .into_iter()
+                    .chain(
+                        names
+                            .iter()
+                            .map(|(letter, name)| format!("{} = {:?}", letter, name))
+                    )
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            FieldStub::Elapsed => "elapsed".to_string(),
+            FieldStub::Counter => "counter".to_string(),
+            FieldStub::Ptr(AddrStyle::Short) => "ptr".to_string(),
+            FieldStub::Ptr(AddrStyle::Full) => "ptr, addr_style = \"full\"".to_string(),
+            FieldStub::Each(each) => describe_nested("each", each),
+            FieldStub::Set(set) => describe_nested("set", set),
+            FieldStub::SmartPointer(pointer, inner) => describe_nested(pointer.key(), inner),
+            FieldStub::Lock(lock, None) => lock.key().to_string(),
+            FieldStub::Lock(lock, Some(inner)) => describe_nested(lock.key(), inner),
+            FieldStub::Cell(None) => "cell".to_string(),
+            FieldStub::Cell(Some(inner)) => describe_nested("cell", inner),
+            FieldStub::Uninit(None) => "uninit".to_string(),
+            FieldStub::Uninit(Some(value)) => format!("uninit = {:?}", value),
+            FieldStub::AssumeInit(None) => "unsafe_assume_init".to_string(),
+            FieldStub::AssumeInit(Some(inner)) => describe_nested("unsafe_assume_init", inner),
+            FieldStub::Cow(None) => "cow".to_string(),
+            FieldStub::Cow(Some(inner)) => describe_nested("cow", inner),
+            FieldStub::Once(None) => "once".to_string(),
+            FieldStub::Once(Some(inner)) => describe_nested("once", inner),
+            FieldStub::Atomic(None) => "atomic".to_string(),
+            FieldStub::Atomic(Some(inner)) => describe_nested("atomic", inner),
+            FieldStub::Weak(None) => "weak".to_string(),
+            FieldStub::Weak(Some(inner)) => describe_nested("weak", inner),
+            FieldStub::AsInner(None) => "as_inner".to_string(),
+            FieldStub::AsInner(Some(inner)) => {
+                format!("as_inner, inner = {:?}", describe_tokens(inner))
+            }
+            FieldStub::DerefTo(target) => format!("deref_to = {:?}", describe_tokens(target)),
+            FieldStub::AsDeref(None) => "as_deref".to_string(),
+            FieldStub::AsDeref(Some(inner)) => format!("as_deref, {}", inner.describe()),
+            FieldStub::Resolve(lookup) => format!("resolve = {:?}", describe_tokens(lookup)),
+            FieldStub::Method(method) => format!("method = \"{}\"", method),
+            FieldStub::Getter(getter) => format!("getter = {:?}", describe_tokens(getter)),
+            FieldStub::Via(target) => format!("via = {:?}", describe_tokens(target)),
+            FieldStub::Expr(expr) => format!("expr = {:?}", describe_tokens(expr)),
+            FieldStub::With(with) => format!("with = {:?}", describe_tokens(with)),
+            FieldStub::Wrap(adapter) => format!("wrap = {:?}", describe_tokens(adapter)),
+            FieldStub::StubTrait(None) => "trait".to_string(),
+            FieldStub::StubTrait(Some(path)) => format!("trait = {:?}", describe_tokens(path)),
+            FieldStub::Counts(None) => "counts".to_string(),
+            FieldStub::Counts(Some(inner)) => describe_nested("counts", inner),
+            FieldStub::Pipeline(base, transforms) => base
+                .iter()
+                .map(|base| base.describe())
+                .chain(transforms.iter().map(Transform::describe))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::Precision(base, precision) => base
+                .iter()
+                .map(|base| base.describe())
+                .chain(Some(match precision {
+                    Some(precision) => format!("precision = {}", precision),
+                    None => "inherit_precision".to_string(),
+                }))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::OnError(base, marker) => base
+                .iter()
+                .map(|base| base.describe())
+                .chain(Some(format!("on_error = {:?}", marker)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::If(predicate, then) => format!(
+                "if = {:?}, {}, else_debug",
+                describe_tokens(predicate),
+                describe_nested("then", then)
+            ),
+            FieldStub::ReleaseOnly(inner) => describe_nested("release_only", inner),
+            FieldStub::Spill(base, spill) => base
+                .iter()
+                .map(|base| base.describe())
+                .chain(Some(spill.describe()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::Alternate(compact, alternate) => compact
+                .iter()
+                .map(|compact| compact.describe())
+                .chain(Some(describe_nested("alternate", alternate)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            FieldStub::Map(key, value, filter) => key
+                .iter()
+                .map(|key| describe_nested("key", key))
+                .chain(value.iter().map(|value| describe_nested("value", value)))
+                .chain(filter.iter().map(KeyFilter::describe))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+/// Describes the stub of a nested `key = "..."` or `key(...)` item, for `#[debug_stub(explain)]`
+fn describe_nested(key: &str, stub: &FieldStub) -> String {
+    match stub {
+        FieldStub::Replace(value) => format!("{} = {:?}", key, value),
+        FieldStub::Template(parts) => format!("{} = {:?}", key, describe_template(parts)),
+        stub => format!("{}({})", key, stub.describe()),
+    }
+}
+
+/// Describes a replacement text with placeholders as written, for `#[debug_stub(explain)]`
+fn describe_template(parts: &[TemplatePart]) -> String {
+    parts.iter().map(TemplatePart::describe).collect()
+}
+
+/// Prints tokens without the spacing inserted by `TokenStream`'s `Display` impl
+fn describe_tokens(tokens: &impl quote::ToTokens) -> String {
+    tokens.to_token_stream().to_string().replace(' ', "")
+}
+
+/// Parses a `#[debug_stub(with = |v, f| ...)]` attribute with an inline closure, which may be
+/// accompanied by other keys, into the closure and the remaining items. `err` is the error of
+/// parsing the attribute as meta, returned if it holds no such closure
+fn parse_inline_with(
+    attr: &Attribute,
+    err: syn::Error,
+) -> syn::Result<(Expr, Punctuated<NestedMeta, Comma>)> {
+    let parser = |input: ParseStream| {
+        let content;
+        parenthesized!(content in input);
+        let (mut closure, mut nested) = (None, Punctuated::new());
+        while !content.is_empty() {
+            let fork = content.fork();
+            let is_with = matches!(fork.parse::<Ident>(), Ok(key) if key == "with")
+                && fork.peek(Token![=])
+                && fork.peek2(Token![|]);
+            if is_with {
+                let key = content.parse::<Ident>()?;
+                content.parse::<Token![=]>()?;
+                let with = content.parse::<syn::ExprClosure>()?;
+                if closure.replace(Expr::Closure(with)).is_some() {
+                    return Err(syn::Error::new(key.span(), "conflicting `debug_stub` keys"));
+                }
+            } else {
+                nested.push_value(content.parse::<NestedMeta>()?);
+            }
+            if content.is_empty() {
+                break;
+            }
+            let comma = content.parse::<Token![,]>()?;
+            if !nested.empty_or_trailing() {
+                nested.push_punct(comma);
+            }
+        }
+        Ok((closure, nested))
+    };
+    match parser.parse2(attr.tokens.clone())? {
+        (Some(closure), nested) => Ok((closure, nested)),
+        (None, _) => Err(err),
+    }
+}
+
+/// Generates a single rendered field from given field value and attributes
+fn extract_value_attr(
+    expr: &Expr,
+    field: &Field,
+    name: Option<String>,
+) -> syn::Result<RenderedField> {
+    let stub = parse_field_stub(&field.attrs)?;
+    let (used, value) = match &stub {
+        Some(stub) => implement_stub(stub, expr, &field.ty),
+        None => (true, expr.clone()),
+    };
+    let skip = field_skip_condition(&field.attrs)?;
+
+    Ok(RenderedField {
+        name,
+        value,
+        stub,
+        access: expr.clone(),
+        // the skip condition reads the field even if its printed value does not
+        used: used || skip.is_some(),
+        brief: is_brief_field(&field.attrs),
+        priority: field_priority(&field.attrs)?,
+        skip,
+    })
+}
+
+/// Returns whether a `#[debug_stub(...)]` item only marks the field, like `brief`,
+/// `priority = N`, or the `skip_` keys, without changing how it is printed
+fn is_marker_key(nested: &NestedMeta) -> bool {
+    match nested {
+        NestedMeta::Meta(Meta::Path(path)) => {
+            path.is_ident("brief")
+                || path.is_ident("skip_none")
+                || path.is_ident("skip_empty")
+                || path.is_ident("skip_default")
+        }
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, .. })) => {
+            path.is_ident("priority") || path.is_ident("skip_if")
+        }
+        _ => false,
+    }
+}
+
+/// Returns the marker items of the `#[debug_stub(...)]` attributes of a field with the given key
+fn field_markers(attrs: &[Attribute], key: &str) -> Vec<NestedMeta> {
+    attrs
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(MetaList { path, nested, .. })) if path.is_ident("debug_stub") => {
+                Some(nested)
+            }
+            Err(err) if attr.path.is_ident("debug_stub") => {
+                parse_inline_with(attr, err).ok().map(|(_, nested)| nested)
+            }
+            _ => None,
+        })
+        .flatten()
+        .filter(|nested| is_marker_key(nested) && nested_key_is(nested, key))
+        .collect()
+}
+
+/// Returns whether the key of a `#[debug_stub(...)]` item is the given one
+fn nested_key_is(nested: &NestedMeta, key: &str) -> bool {
+    match nested {
+        NestedMeta::Meta(meta) => meta.path().is_ident(key),
+        NestedMeta::Lit(_) => false,
+    }
+}
+
+/// Returns whether a field is marked `#[debug_stub(brief)]`
+fn is_brief_field(attrs: &[Attribute]) -> bool {
+    !field_markers(attrs, "brief").is_empty()
+}
+
+/// Returns the priority of a field given by `#[debug_stub(priority = N)]`, or 0
+fn field_priority(attrs: &[Attribute]) -> syn::Result<usize> {
+    match field_markers(attrs, "priority").first() {
+        Some(nested @ NestedMeta::Meta(Meta::NameValue(MetaNameValue { lit, .. }))) => {
+            expect_lit_int(nested, Some(lit))
+        }
+        _ => Ok(0),
+    }
+}
+
+/// Returns the condition under which a field is left out of the output, given by one of the
+/// `skip_` keys
+fn field_skip_condition(attrs: &[Attribute]) -> syn::Result<Option<SkipCondition>> {
+    let markers = SKIP_KEYS
+        .iter()
+        .flat_map(|key| field_markers(attrs, key))
+        .collect::<Vec<_>>();
+    let nested = match markers.as_slice() {
+        [] => return Ok(None),
+        [nested] => nested,
+        [_, nested, ..] => return Err(syn::Error::new_spanned(nested, "conflicting `skip_` keys")),
+    };
+    match nested {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+            if path.is_ident("skip_if") =>
+        {
+            Ok(Some(SkipCondition::If(
+                expect_lit_str(nested, Some(lit))?.parse()?,
+            )))
+        }
+        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip_none") => {
+            Ok(Some(SkipCondition::None))
+        }
+        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip_empty") => {
+            Ok(Some(SkipCondition::Empty))
+        }
+        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip_default") => {
+            Ok(Some(SkipCondition::Default))
+        }
+        _ => unreachable!("only skip markers are collected"),
+    }
+}
+
+/// Parses the first `#[debug_stub]` attribute of a field which does more than mark it, if present
+fn parse_field_stub(attrs: &[Attribute]) -> syn::Result<Option<FieldStub>> {
+    for attr in attrs {
+        let meta = match attr.parse_meta() {
+            Ok(meta) if meta.path().is_ident("debug_stub") => meta,
+            // `#[debug_stub(with = |v, f| ...)]`, which is no valid meta item
+            Err(err) if attr.path.is_ident("debug_stub") => {
+                let (with, nested) = parse_inline_with(attr, err)?;
+                let nested = nested
+                    .into_iter()
+                    .filter(|nested| !is_marker_key(nested))
+                    .collect::<Punctuated<_, Comma>>();
+                return extract_named_value_attrs(&nested, Some(FieldStub::With(with))).map(Some);
+            }
+            _ => continue,
+        };
+
+        return match meta {
+            // `#[debug_stub]`
+            Meta::Path(path) => Err(syn::Error::new_spanned(
+                path,
+                "expected `List` or `NameValue`",
+            )),
+            // `#[debug_stub(key1 = val1, key2 = val2)]`, where markers like `brief` do not
+            // change how the field is printed, so that an attribute holding only markers leaves
+            // the stub to the next one
+            Meta::List(MetaList { nested, .. }) => {
+                let nested = nested
+                    .into_iter()
+                    .filter(|nested| !is_marker_key(nested))
+                    .collect::<Punctuated<_, Comma>>();
+                if nested.is_empty() {
+                    continue;
+                }
+                extract_named_value_attrs(&nested, None).map(Some)
+            }
+            // `#[debug_stub = "literal"]`
+            Meta::NameValue(MetaNameValue { lit, .. }) => {
+                let lit = syn::parse2::<LitStr>(lit.to_token_stream())?;
+                Ok(Some(parse_replacement(&lit)))
+            }
+        };
+    }
+
+    Ok(None)
+}
+
+/// Extracts the keys of a `#[debug_stub(...)]` field attribute into a `FieldStub`, starting from
+/// the given stub of an inline `with` closure, if any
+fn extract_named_value_attrs(
+    nested: &Punctuated<NestedMeta, Comma>,
+    stub: Option<FieldStub>,
+) -> syn::Result<FieldStub> {
+    let (mut ok, mut err, mut stub) = (None, None, stub);
+    let (mut some, mut none) = (None, None);
+    let (mut map_key, mut map_value, mut key_filter) = (None, None, None);
+    let (mut head, mut tail) = (None, None);
+    let (mut passthrough, mut passthrough_key) = (ErrPassthrough::default(), None);
+    let (mut any_of, mut any_else) = (None, None);
+    let mut transforms = vec![];
+    let (mut sanitize, mut on_error, mut addr_style, mut inner) = (None, None, None, None);
+    let (mut spill, mut as_deref) = (None, None);
+    let (mut condition, mut then, mut else_debug) = (None, None, None);
+    let mut precision: Option<Option<usize>> = None;
+    let mut alternate = None;
+
+    for nested in nested {
+        let (path, lit, list) = match nested {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => {
+                (path, Some(lit), None)
+            }
+            NestedMeta::Meta(Meta::Path(path)) => (path, None, None),
+            NestedMeta::Meta(Meta::List(MetaList { path, nested, .. })) => {
+                (path, None, Some(nested))
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    nested,
+                    "expected `key` or `key = \"...\"`",
+                ))
+            }
+        };
+        let key = path.get_ident().map(Ident::to_string).unwrap_or_default();
+
+        let next = match key.as_str() {
+            "ok" => {
+                ok = Some(expect_nested_stub(nested, lit, list)?);
+                continue;
+            }
+            "err" => {
+                err = Some(expect_nested_stub(nested, lit, list)?);
+                continue;
+            }
+            "some" => {
+                some = Some(expect_nested_stub(nested, lit, list)?);
+                continue;
+            }
+            "release_only" => FieldStub::ReleaseOnly(expect_nested_stub(nested, lit, list)?),
+            "then" => {
+                then = Some((expect_nested_stub(nested, lit, list)?, nested));
+                continue;
+            }
+            "alternate" => {
+                alternate = Some(expect_nested_stub(nested, lit, list)?);
+                continue;
+            }
+            "key" => {
+                map_key = Some(expect_nested_stub(nested, lit, list)?);
+                continue;
+            }
+            "value" => {
+                map_value = Some(expect_nested_stub(nested, lit, list)?);
+                continue;
+            }
+            "each" => FieldStub::Each(expect_nested_stub(nested, lit, list)?),
+            "set" => FieldStub::Set(expect_nested_stub(nested, lit, list)?),
+            "mutex" => {
+                FieldStub::Lock(Lock::Mutex, expect_optional_nested_stub(nested, lit, list)?)
+            }
+            "rwlock" => FieldStub::Lock(
+                Lock::RwLock,
+                expect_optional_nested_stub(nested, lit, list)?,
+            ),
+            "refcell" => FieldStub::Lock(
+                Lock::RefCell,
+                expect_optional_nested_stub(nested, lit, list)?,
+            ),
+            "cell" => FieldStub::Cell(expect_optional_nested_stub(nested, lit, list)?),
+            "unsafe_assume_init" => {
+                FieldStub::AssumeInit(expect_optional_nested_stub(nested, lit, list)?)
+            }
+            "atomic" => FieldStub::Atomic(expect_optional_nested_stub(nested, lit, list)?),
+            "once" => FieldStub::Once(expect_optional_nested_stub(nested, lit, list)?),
+            "cow" => FieldStub::Cow(expect_optional_nested_stub(nested, lit, list)?),
+            "weak" => FieldStub::Weak(expect_optional_nested_stub(nested, lit, list)?),
+            "counts" => FieldStub::Counts(expect_optional_nested_stub(nested, lit, list)?),
+            "boxed" => {
+                FieldStub::SmartPointer(SmartPointer::Box, expect_nested_stub(nested, lit, list)?)
+            }
+            "rc" => {
+                FieldStub::SmartPointer(SmartPointer::Rc, expect_nested_stub(nested, lit, list)?)
+            }
+            "arc" => {
+                FieldStub::SmartPointer(SmartPointer::Arc, expect_nested_stub(nested, lit, list)?)
+            }
+            "mask" => {
+                transforms.push(expect_mask(nested, lit, list)?);
+                continue;
+            }
+            "bits" => expect_bits(nested, list)?,
+            "spill" => {
+                spill = Some(expect_spill(nested, list)?);
+                continue;
+            }
+            "json" => FieldStub::Json(expect_json(nested, lit, list)?),
+            "any_of" => {
+                any_of = Some(expect_types(nested, list)?);
+                continue;
+            }
+            _ if list.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    nested,
+                    "expected `key` or `key = \"...\"`",
+                ))
+            }
+            "filter_keys" | "filter_keys_with" => {
+                let filter = match key.as_str() {
+                    "filter_keys" => KeyFilter::Prefix(expect_lit_str(nested, lit)?.value()),
+                    _ => KeyFilter::With(expect_lit_str(nested, lit)?.parse()?),
+                };
+                if key_filter.replace(filter).is_some() {
+                    return Err(syn::Error::new_spanned(
+                        nested,
+                        "conflicting `debug_stub` keys",
+                    ));
+                }
+                continue;
+            }
+            "none" => {
+                none = Some(expect_lit_str(nested, lit)?.value());
+                continue;
+            }
+            "err_max_len" => {
+                passthrough.max_len = Some(expect_lit_int(nested, lit)?);
+                passthrough_key = Some(nested);
+                continue;
+            }
+            "err_display" => {
+                passthrough.display = expect_flag(nested, lit, true)?;
+                passthrough_key = Some(nested);
+                continue;
+            }
+            "max_len" => {
+                transforms.push(Transform::MaxLen(expect_lit_int(nested, lit)?));
+                continue;
+            }
+            "else" => {
+                any_else = Some((expect_lit_str(nested, lit)?.value(), nested));
+                continue;
+            }
+            "head" => {
+                head = Some(expect_lit_int(nested, lit)?);
+                continue;
+            }
+            "tail" => {
+                tail = Some(expect_lit_int(nested, lit)?);
+                continue;
+            }
+            "sanitize" => {
+                sanitize = Some(expect_flag(nested, lit, nested)?);
+                continue;
+            }
+            "inner" => {
+                inner = Some((expect_lit_str(nested, lit)?.parse::<Type>()?, nested));
+                continue;
+            }
+            "as_deref" => {
+                as_deref = Some(expect_flag(nested, lit, nested)?);
+                continue;
+            }
+            "if" => {
+                condition = Some((expect_lit_str(nested, lit)?.parse::<Path>()?, nested));
+                continue;
+            }
+            "else_debug" => {
+                else_debug = Some(expect_flag(nested, lit, nested)?);
+                continue;
+            }
+            "addr_style" => {
+                let style = match expect_lit_str(nested, lit)?.value().as_str() {
+                    "short" => AddrStyle::Short,
+                    "full" => AddrStyle::Full,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            nested,
+                            "expected `addr_style = \"short\"` or `addr_style = \"full\"`",
+                        ))
+                    }
+                };
+                addr_style = Some((style, nested));
+                continue;
+            }
+            "precision" | "inherit_precision" => {
+                let inherit = key == "inherit_precision";
+                if matches!(precision, Some(precision) if precision.is_none() != inherit) {
+                    return Err(syn::Error::new_spanned(
+                        nested,
+                        "`precision` and `inherit_precision` are mutually exclusive",
+                    ));
+                }
+                precision = Some(match inherit {
+                    false => Some(expect_lit_int(nested, lit)?),
+                    true => expect_flag(nested, lit, None)?,
+                });
+                continue;
+            }
+            "on_error" => {
+                on_error = Some(expect_lit_str(nested, lit)?.value());
+                continue;
+            }
+            "separators" => FieldStub::Separators(match lit {
+                Some(_) => expect_lit_str(nested, lit)?.value(),
+                None => "_".to_string(),
+            }),
+            "uninit" => FieldStub::Uninit(match lit {
+                Some(_) => Some(expect_lit_str(nested, lit)?.value()),
+                None => None,
+            }),
+            "future_state" => FieldStub::FutureState(expect_lit_str(nested, lit)?.parse()?),
+            "resolve" => FieldStub::Resolve(expect_lit_str(nested, lit)?.parse()?),
+            "method" => FieldStub::Method(expect_lit_str(nested, lit)?.parse()?),
+            "getter" => FieldStub::Getter(expect_lit_str(nested, lit)?.parse()?),
+            "const" => FieldStub::Const(expect_lit_str(nested, lit)?.parse()?),
+            "via" => FieldStub::Via(expect_lit_str(nested, lit)?.parse()?),
+            "expr" => FieldStub::Expr(expect_lit_str(nested, lit)?.parse()?),
+            "with" => FieldStub::With(expect_lit_str(nested, lit)?.parse()?),
+            "wrap" => FieldStub::Wrap(expect_lit_str(nested, lit)?.parse()?),
+            "trait" => FieldStub::StubTrait(match lit {
+                Some(_) => Some(expect_lit_str(nested, lit)?.parse()?),
+                None => None,
+            }),
+            "as_inner" => expect_flag(nested, lit, FieldStub::AsInner(None))?,
+            "deref_to" => FieldStub::DerefTo(expect_lit_str(nested, lit)?.parse()?),
+            "len" => expect_flag(nested, lit, FieldStub::Len)?,
+            "type_name" => expect_flag(nested, lit, FieldStub::TypeName)?,
+            "shallow" => expect_flag(nested, lit, FieldStub::Shallow)?,
+            "opaque" => expect_flag(nested, lit, FieldStub::Opaque)?,
+            "scientific" => match lit {
+                None => FieldStub::Scientific(false),
+                Some(_) => match expect_lit_str(nested, lit)?.value().as_str() {
+                    "e" => FieldStub::Scientific(false),
+                    "E" => FieldStub::Scientific(true),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            nested,
+                            "expected `scientific = \"e\"` or `scientific = \"E\"`",
+                        ))
+                    }
+                },
+            },
+            "hash" => expect_flag(nested, lit, FieldStub::Hash)?,
+            "limit" => FieldStub::Limit(expect_lit_int(nested, lit)?),
+            "sha256" => {
+                if !cfg!(feature = "sha256") {
+                    return Err(syn::Error::new_spanned(
+                        path,
+                        "`sha256` requires the `sha256` feature of `debug_stub_derive`",
+                    ));
+                }
+                expect_flag(nested, lit, FieldStub::Sha256)?
+            }
+            "display" => expect_flag(nested, lit, FieldStub::Display { sanitize: false })?,
+            "truncate" => FieldStub::Truncate(expect_lit_int(nested, lit)?),
+            "peek" => FieldStub::Peek(expect_lit_int(nested, lit)?),
+            "first" => FieldStub::First(expect_lit_int(nested, lit)?),
+            "utc" => FieldStub::Utc(expect_date_time_crate(nested, lit)?),
+            "rfc3339" => FieldStub::Rfc3339(expect_date_time_crate(nested, lit)?),
+            "be" => expect_flag(nested, lit, FieldStub::ByteOrder(ByteOrder::Big))?,
+            "le" => expect_flag(nested, lit, FieldStub::ByteOrder(ByteOrder::Little))?,
+            "crc32" => expect_flag(nested, lit, FieldStub::Crc32)?,
+            "ptr" => expect_flag(nested, lit, FieldStub::Ptr(AddrStyle::Short))?,
+            "duration" => expect_flag(nested, lit, FieldStub::Duration)?,
+            "bytes" => expect_flag(nested, lit, FieldStub::Bytes)?,
+            "variant_counts" => expect_flag(nested, lit, FieldStub::VariantCounts)?,
+            "path" => expect_flag(nested, lit, FieldStub::Path)?,
+            "c_str" => expect_flag(nested, lit, FieldStub::CStr)?,
+            "elapsed" => expect_flag(nested, lit, FieldStub::Elapsed)?,
+            "counter" => expect_flag(nested, lit, FieldStub::Counter)?,
+            _ => return Err(syn::Error::new_spanned(path, "unexpected `debug_stub` key")),
+        };
+
+        if stub.replace(next).is_some() {
+            return Err(syn::Error::new_spanned(
+                nested,
+                "conflicting `debug_stub` keys",
+            ));
+        }
+    }
+
+    // stubs made up of several keys
+    let mut stubs = stub.into_iter().collect::<Vec<_>>();
+    if some.is_some() || none.is_some() {
+        stubs.push(FieldStub::Option(some, none));
+    }
+    if let Some(passthrough_key) = passthrough_key {
+        if ok.is_none() || err.is_some() {
+            return Err(syn::Error::new_spanned(
+                passthrough_key,
+                "`err_max_len` and `err_display` require `ok` without `err`",
+            ));
+        }
+    }
+    if ok.is_some() || err.is_some() {
+        stubs.push(FieldStub::Result(ok, err, passthrough));
+    }
+    if map_key.is_some() || map_value.is_some() || key_filter.is_some() {
+        stubs.push(FieldStub::Map(map_key, map_value, key_filter));
+    }
+    match (any_of, any_else) {
+        (Some(types), fallback) => stubs.push(FieldStub::AnyOf(
+            types,
+            fallback.map(|(fallback, _)| fallback),
+        )),
+        (None, Some((_, key))) => {
+            return Err(syn::Error::new_spanned(key, "`else` requires `any_of`"))
+        }
+        (None, None) => {}
+    }
+    match (condition, then, else_debug) {
+        (Some((predicate, _)), Some((then, _)), _) => stubs.push(FieldStub::If(predicate, then)),
+        (Some((_, key)), None, _) => {
+            return Err(syn::Error::new_spanned(key, "`if` requires `then`"))
+        }
+        (None, Some((_, key)), _) => {
+            return Err(syn::Error::new_spanned(key, "`then` requires `if`"))
+        }
+        (None, None, Some(key)) => {
+            return Err(syn::Error::new_spanned(key, "`else_debug` requires `if`"))
+        }
+        (None, None, None) => {}
+    }
+    if head.is_some() || tail.is_some() {
+        stubs.push(FieldStub::HeadTail {
+            head: head.unwrap_or(0),
+            tail: tail.unwrap_or(0),
+        });
+    }
+
+    let stub = match (stubs.pop(), stubs.is_empty()) {
+        (Some(stub), true) => Some(stub),
+        (Some(_), false) => {
+            return Err(syn::Error::new_spanned(
+                nested,
+                "conflicting `debug_stub` keys",
+            ))
+        }
+        (None, _)
+            if !transforms.is_empty()
+                || on_error.is_some()
+                || spill.is_some()
+                || as_deref.is_some()
+                || precision.is_some()
+                || alternate.is_some() =>
+        {
+            None
+        }
+        (None, _) => {
+            let message = match (sanitize, addr_style, inner) {
+                (Some(_), _, _) => "`sanitize` requires `display`",
+                (None, Some(_), _) => "`addr_style` requires `ptr`",
+                (None, None, Some(_)) => "`inner` requires `as_inner`",
+                (None, None, None) => "expected at least one `debug_stub` key",
+            };
+            return Err(syn::Error::new_spanned(nested, message));
+        }
+    };
+
+    let stub = match (stub, sanitize) {
+        (stub, None) => stub,
+        (Some(FieldStub::Display { .. }), Some(_)) => Some(FieldStub::Display { sanitize: true }),
+        (_, Some(sanitize)) => {
+            return Err(syn::Error::new_spanned(
+                sanitize,
+                "`sanitize` requires `display`",
+            ))
+        }
+    };
+
+    let stub = match (stub, addr_style) {
+        (stub, None) => stub,
+        (Some(FieldStub::Ptr(_)), Some((style, _))) => Some(FieldStub::Ptr(style)),
+        (_, Some((_, addr_style))) => {
+            return Err(syn::Error::new_spanned(
+                addr_style,
+                "`addr_style` requires `ptr`",
+            ))
+        }
+    };
+
+    let stub = match (stub, inner) {
+        (stub, None) => stub,
+        (Some(FieldStub::AsInner(_)), Some((inner, _))) => Some(FieldStub::AsInner(Some(inner))),
+        (_, Some((_, inner))) => {
+            return Err(syn::Error::new_spanned(
+                inner,
+                "`inner` requires `as_inner`",
+            ))
+        }
+    };
+
+    let stub = match (stub, as_deref) {
+        (stub, None) => stub,
+        (None, Some(_)) => Some(FieldStub::AsDeref(None)),
+        (Some(stub @ FieldStub::Option(_, _)), Some(_)) => {
+            Some(FieldStub::AsDeref(Some(Box::new(stub))))
+        }
+        (Some(_), Some(as_deref)) => {
+            return Err(syn::Error::new_spanned(
+                as_deref,
+                "`as_deref` can only be combined with `some` and `none`",
+            ))
+        }
+    };
+
+    let stub = match (stub, precision) {
+        (stub, None) => stub,
+        (stub, Some(precision)) => Some(FieldStub::Precision(stub.map(Box::new), precision)),
+    };
+
+    // transforms apply to the output of the stub, in the order they are written
+    let stub = match (stub, transforms.is_empty()) {
+        (stub, true) => stub,
+        (stub, false) => Some(FieldStub::Pipeline(stub.map(Box::new), transforms)),
+    };
+
+    // `spill` receives the text of everything but `on_error`, which guards it too
+    let stub = match (stub, spill) {
+        (stub, None) => stub,
+        (stub, Some(spill)) => Some(FieldStub::Spill(stub.map(Box::new), spill)),
+    };
+
+    // `on_error` guards everything else on the field
+    let stub = match (stub, on_error) {
+        (stub, None) => stub,
+        (stub, Some(marker)) => Some(FieldStub::OnError(stub.map(Box::new), marker)),
+    };
+
+    // `alternate` replaces everything else on the field in alternate mode
+    match (stub, alternate) {
+        (Some(stub), None) => Ok(stub),
+        (stub, Some(alternate)) => Ok(FieldStub::Alternate(stub.map(Box::new), alternate)),
+        (None, None) => unreachable!("a field stub without keys was rejected above"),
+    }
+}
+
+/// Expects the value of a `key = "..."` attribute to be a string literal
+fn expect_lit_str<'a>(nested: &NestedMeta, lit: Option<&'a Lit>) -> syn::Result<&'a LitStr> {
+    match lit {
+        Some(Lit::Str(lit)) => Ok(lit),
+        _ => Err(syn::Error::new_spanned(nested, "expected `key = \"...\"`")),
+    }
+}
+
+/// Expects the stub of a nested `key = "..."` or `key(...)` attribute, like `some(ok = "...")`
+fn expect_nested_stub(
+    nested: &NestedMeta,
+    lit: Option<&Lit>,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<Box<FieldStub>> {
+    match list {
+        Some(list) => extract_named_value_attrs(list, None).map(Box::new),
+        None => Ok(Box::new(parse_replacement(expect_lit_str(nested, lit)?))),
+    }
+}
+
+/// Parses a replacement text, which yields a `FieldStub::Template` if it contains the placeholders
+/// `{len}`, `{type}`, or `{ptr}`, and a plain `FieldStub::Replace` otherwise. Any other braces are
+/// kept as written, so that texts like `"{...}"` print as before placeholders existed
+fn parse_replacement(lit: &LitStr) -> FieldStub {
+    let (mut parts, mut text) = (vec![], String::new());
+    let value = lit.value();
+    let mut rest = value.as_str();
+    while let Some(c) = rest.chars().next() {
+        let placeholder = ["{len}", "{type}", "{ptr}"]
+            .iter()
+            .find(|name| rest.starts_with(**name));
+        match placeholder {
+            Some(name) => {
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(match *name {
+                    "{len}" => TemplatePart::Len,
+                    "{type}" => TemplatePart::Type,
+                    _ => TemplatePart::Ptr,
+                });
+                rest = &rest[name.len()..];
+            }
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        return FieldStub::Replace(text);
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    FieldStub::Template(parts)
+}
+
+/// Like `expect_nested_stub`, but also accepts a bare `key`, which yields `None`
+fn expect_optional_nested_stub(
+    nested: &NestedMeta,
+    lit: Option<&Lit>,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<Option<Box<FieldStub>>> {
+    match (lit, list) {
+        (None, None) => Ok(None),
+        _ => expect_nested_stub(nested, lit, list).map(Some),
+    }
+}
+
+/// Expects `bits(format = "vvvv_ffff", v = "version", f = "flags:b")`, where each character of the
+/// format stands for a bit, from the highest to the lowest one, and adjacent bits with the same
+/// letter make up a group. `_` separates groups visually and `-` marks unused bits. Groups are
+/// named after their letter unless renamed, and printed in binary with a `:b` suffix
+fn expect_bits(
+    nested: &NestedMeta,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<FieldStub> {
+    let expected = "expected `bits(format = \"...\", letter = \"name\", ...)`";
+    let list = list.ok_or_else(|| syn::Error::new_spanned(nested, expected))?;
+
+    let (mut format, mut names) = (None, Vec::new());
+    for item in list {
+        let (path, lit) = match item {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => (path, lit),
+            _ => return Err(syn::Error::new_spanned(item, expected)),
+        };
+        let key = path.get_ident().map(Ident::to_string).unwrap_or_default();
+        let value = expect_lit_str(item, Some(lit))?.value();
+        let mut chars = key.chars();
+        match (key.as_str(), chars.next(), chars.next()) {
+            ("format", _, _) => format = Some((value, lit)),
+            (_, Some(letter), None) => names.push((letter, value)),
+            _ => return Err(syn::Error::new_spanned(item, expected)),
+        }
+    }
+    let (format, format_lit) = format.ok_or_else(|| syn::Error::new_spanned(nested, expected))?;
+
+    let bits = format.chars().filter(|&c| c != '_').collect::<Vec<_>>();
+    if bits.len() > 128 {
+        return Err(syn::Error::new_spanned(
+            format_lit,
+            "expected at most 128 bits",
+        ));
+    }
+    let mut groups: Vec<(char, usize, usize)> = Vec::new();
+    for (index, &letter) in bits.iter().enumerate() {
+        let shift = bits.len() - 1 - index;
+        if let Some((last, last_shift, width)) = groups.last_mut() {
+            if *last == letter {
+                *last_shift = shift;
+                *width += 1;
+                continue;
+            }
+        }
+        if letter != '-' && !letter.is_ascii_alphanumeric() {
+            return Err(syn::Error::new_spanned(
+                format_lit,
+                format!("unexpected `{}`, expected letters, `_`, or `-`", letter),
+            ));
+        }
+        if letter != '-' && groups.iter().any(|(other, _, _)| *other == letter) {
+            return Err(syn::Error::new_spanned(
+                format_lit,
+                format!("the bits of `{}` are not adjacent", letter),
+            ));
+        }
+        groups.push((letter, shift, 1));
+    }
+    if let Some((letter, _)) = names
+        .iter()
+        .find(|(letter, _)| !groups.iter().any(|(other, _, _)| other == letter))
+    {
+        return Err(syn::Error::new_spanned(
+            nested,
+            format!("`{}` does not appear in the format", letter),
+        ));
+    }
+
+    let groups = groups
+        .into_iter()
+        .filter(|(letter, _, _)| *letter != '-')
+        .map(|(letter, shift, width)| {
+            let name = names
+                .iter()
+                .find(|(other, _)| *other == letter)
+                .map_or_else(|| letter.to_string(), |(_, name)| name.clone());
+            let (name, binary) = match name.strip_suffix(":b") {
+                Some(name) => (name.to_string(), true),
+                None => (name, false),
+            };
+            BitGroup {
+                name,
+                shift,
+                width,
+                binary,
+            }
+        })
+        .collect();
+
+    Ok(FieldStub::Bits {
+        format,
+        names,
+        groups,
+    })
+}
+
+/// Expects a bare `json` or `json(redact("key", ...))`, returning the redacted keys
+fn expect_json(
+    nested: &NestedMeta,
+    lit: Option<&Lit>,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<Vec<String>> {
+    if !cfg!(feature = "serde_json") {
+        return Err(syn::Error::new_spanned(
+            nested,
+            "requires the `serde_json` feature of `debug_stub_derive`",
+        ));
+    }
+    let expected = "expected `json` or `json(redact(\"key\", ...))`";
+    if lit.is_some() {
+        return Err(syn::Error::new_spanned(nested, expected));
+    }
+
+    let mut redact = vec![];
+    for item in list.into_iter().flatten() {
+        match item {
+            NestedMeta::Meta(Meta::List(MetaList { path, nested, .. }))
+                if path.is_ident("redact") =>
+            {
+                for key in nested {
+                    match key {
+                        NestedMeta::Lit(Lit::Str(key)) => redact.push(key.value()),
+                        _ => return Err(syn::Error::new_spanned(key, expected)),
+                    }
+                }
+            }
+            _ => return Err(syn::Error::new_spanned(item, expected)),
+        }
+    }
+    Ok(redact)
+}
+
+/// Expects a list of type paths, like `any_of(u32, String)`
+fn expect_types(
+    nested: &NestedMeta,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<Vec<Path>> {
+    let expected = "expected `key(Type, ...)`";
+    let list = list.ok_or_else(|| syn::Error::new_spanned(nested, expected))?;
+    list.iter()
+        .map(|item| match item {
+            NestedMeta::Meta(Meta::Path(path)) => Ok(path.clone()),
+            _ => Err(syn::Error::new_spanned(item, expected)),
+        })
+        .collect()
+}
+
+/// Expects a bare `mask` or `mask(keep_start = N, keep_end = M)`, where either may be omitted
+fn expect_mask(
+    nested: &NestedMeta,
+    lit: Option<&Lit>,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<Transform> {
+    let (mut keep_start, mut keep_end) = (0, 0);
+    for item in list.into_iter().flatten() {
+        match item {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+                if path.is_ident("keep_start") =>
+            {
+                keep_start = expect_lit_int(item, Some(lit))?
+            }
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+                if path.is_ident("keep_end") =>
+            {
+                keep_end = expect_lit_int(item, Some(lit))?
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    item,
+                    "expected `keep_start = N` or `keep_end = N`",
+                ))
+            }
+        }
+    }
+    expect_flag(
+        nested,
+        lit,
+        Transform::Mask {
+            keep_start,
+            keep_end,
+        },
+    )
+}
+
+/// Expects `spill(dir = "...", over = N)`, where `dir` may be omitted
+fn expect_spill(
+    nested: &NestedMeta,
+    list: Option<&Punctuated<NestedMeta, Comma>>,
+) -> syn::Result<Spill> {
+    let expected = "expected `spill(dir = \"...\", over = N)`";
+    let list = list.ok_or_else(|| syn::Error::new_spanned(nested, expected))?;
+    let (mut dir, mut over) = (None, None);
+    for item in list {
+        match item {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+                if path.is_ident("dir") =>
+            {
+                dir = Some(expect_lit_str(item, Some(lit))?.value())
+            }
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+                if path.is_ident("over") =>
+            {
+                over = Some(expect_lit_int(item, Some(lit))?)
+            }
+            _ => return Err(syn::Error::new_spanned(item, expected)),
+        }
+    }
+    match over {
+        Some(over) => Ok(Spill { dir, over }),
+        None => Err(syn::Error::new_spanned(
+            nested,
+            "`spill` requires `over = N`",
+        )),
+    }
+}
+
+/// Expects the value of a `key = N` attribute to be an integer literal
+fn expect_lit_int(nested: &NestedMeta, lit: Option<&Lit>) -> syn::Result<usize> {
+    match lit {
+        Some(Lit::Int(lit)) => lit.base10_parse(),
+        _ => Err(syn::Error::new_spanned(nested, "expected `key = N`")),
+    }
+}
+
+/// Expects the date time crate selected by `key` or `key = "chrono" | "time"`, where the bare
+/// form is allowed if only one of the `chrono` and `time` features is enabled
+fn expect_date_time_crate(nested: &NestedMeta, lit: Option<&Lit>) -> syn::Result<DateTimeCrate> {
+    let (chrono, time) = (cfg!(feature = "chrono"), cfg!(feature = "time"));
+    let name =
+        match lit {
+            Some(_) => expect_lit_str(nested, lit)?.value(),
+            None if chrono && time => return Err(syn::Error::new_spanned(
+                nested,
+                "both `chrono` and `time` features are enabled, expected `key = \"chrono\"` or \
+                 `key = \"time\"`",
+            )),
+            None if chrono => "chrono".to_string(),
+            None if time => "time".to_string(),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    nested,
+                    "requires the `chrono` or `time` feature of `debug_stub_derive`",
+                ))
+            }
+        };
+
+    match name.as_str() {
+        "chrono" if chrono => Ok(DateTimeCrate::Chrono),
+        "time" if time => Ok(DateTimeCrate::Time),
+        "chrono" | "time" => Err(syn::Error::new_spanned(
+            nested,
+            format!("requires the `{}` feature of `debug_stub_derive`", name),
+        )),
+        _ => Err(syn::Error::new_spanned(
+            nested,
+            "expected `key = \"chrono\"` or `key = \"time\"`",
+        )),
+    }
+}
+
+/// Expects a bare `key` attribute without a value
+fn expect_flag<T>(nested: &NestedMeta, lit: Option<&Lit>, value: T) -> syn::Result<T> {
+    match lit {
+        None => Ok(value),
+        Some(_) => Err(syn::Error::new_spanned(
+            nested,
+            "expected `key` without a value",
+        )),
+    }
+}
+
+/// Generates the value passed to `f.field()` for a given stub. Also returns whether the field value
+/// is actually being used in the generated expression
+fn implement_stub(stub: &FieldStub, expr: &Expr, ty: &Type) -> (bool, Expr) {
+    match stub {
+        FieldStub::Replace(value) => (false, implement_replace_attr(value)),
+        FieldStub::Uninit(value) => (
+            false,
+            implement_replace_attr(&value.as_deref().unwrap_or("<uninit>")),
+        ),
+        FieldStub::Template(parts) => implement_template_attr(parts, expr, ty),
+        FieldStub::Const(value) => (false, implement_replace_attr(value)),
+        FieldStub::Option(some, none) => (
+            true,
+            implement_option_attr(some.as_deref(), none.as_deref(), expr, ty),
+        ),
+        FieldStub::Result(ok, err, passthrough) => (
+            true,
+            implement_result_attr(ok.as_deref(), err.as_deref(), passthrough, expr, ty),
+        ),
+        FieldStub::FutureState(done) => (true, implement_future_state_attr(done, expr)),
+        FieldStub::Resolve(lookup) => (true, implement_resolve_attr(lookup, expr)),
+        FieldStub::Method(method) => (true, implement_method_attr(method, expr)),
+        FieldStub::Getter(getter) => (true, implement_getter_attr(getter, expr)),
+        FieldStub::Via(target) => (
+            true,
+            parse_quote!(<#target as ::core::convert::From<_>>::from(#expr)),
+        ),
+        // the expression may read the field through `self` or its binding
+        FieldStub::Expr(value) => (true, implement_expr_attr(value)),
+        FieldStub::With(with) => (true, implement_with_attr(with, expr)),
+        FieldStub::Wrap(adapter) => (true, parse_quote!(#adapter(#expr))),
+        FieldStub::StubTrait(path) => {
+            // without a path, `StubDebug` resolves to the trait in scope at the derive
+            let path = path.clone().unwrap_or_else(|| parse_quote!(StubDebug));
+            (
+                true,
+                implement_debug_with(quote!(#path::stub_fmt(#expr, f))),
+            )
+        }
+        FieldStub::AsInner(None) => (true, parse_quote!((&#expr.0))),
+        FieldStub::AsInner(Some(inner)) => (
+            true,
+            parse_quote!(<_ as ::core::convert::AsRef<#inner>>::as_ref(#expr)),
+        ),
+        // deref coercion follows as many `Deref` impls as needed to reach the target
+        FieldStub::DerefTo(target) => (
+            true,
+            parse_quote!({
+                let target: &#target = #expr;
+                target
+            }),
+        ),
+        FieldStub::AsDeref(inner) => {
+            let expr = parse_quote!(::core::option::Option::as_deref(#expr));
+            match inner {
+                Some(inner) => {
+                    let target = deref_target(&generic_type_arg(ty, 0));
+                    implement_stub(inner, &expr, &parse_quote!(Option<#target>))
+                }
+                None => (true, expr),
+            }
+        }
+        FieldStub::Len => (true, implement_len_attr(expr, ty)),
+        FieldStub::Shallow => implement_shallow_attr(expr, ty),
+        FieldStub::Opaque => {
+            let name = short_type_name(ty);
+            (
+                false,
+                implement_debug_with(quote!(f.debug_struct(#name).finish_non_exhaustive())),
+            )
+        }
+        FieldStub::Scientific(upper) => (true, implement_scientific_attr(*upper, expr)),
+        FieldStub::Separators(separator) => (true, implement_separators_attr(separator, expr)),
+        FieldStub::TypeName => (false, implement_type_name_attr(ty)),
+        FieldStub::Hash => (true, implement_hash_attr(expr)),
+        FieldStub::Limit(limit) => (
+            true,
+            implement_elided_collection(collection_kind(ty), expr, *limit, 0),
+        ),
+        FieldStub::Sha256 => (true, implement_sha256_attr(expr)),
+        FieldStub::Display { sanitize } => (true, implement_display_attr(*sanitize, expr)),
+        FieldStub::Truncate(chars) => (true, implement_truncate_attr(*chars, expr)),
+        FieldStub::Peek(chars) => (true, implement_peek_attr(*chars, expr)),
+        FieldStub::First(first) => (
+            true,
+            implement_elided_collection(CollectionKind::List, expr, *first, 0),
+        ),
+        FieldStub::Utc(krate) => (true, implement_utc_attr(*krate, expr)),
+        FieldStub::Rfc3339(krate) => (true, implement_rfc3339_attr(*krate, expr)),
+        FieldStub::ByteOrder(order) => (true, implement_byte_order_attr(*order, expr, ty)),
+        FieldStub::HeadTail { head, tail } => (
+            true,
+            implement_elided_collection(collection_kind(ty), expr, *head, *tail),
+        ),
+        FieldStub::Crc32 => (true, implement_crc32_attr(expr)),
+        FieldStub::Ptr(style) => (true, implement_ptr_attr(*style, expr)),
+        FieldStub::Bits { groups, .. } => (true, implement_bits_attr(groups, expr)),
+        FieldStub::VariantCounts => (true, implement_variant_counts_attr(expr, ty)),
+        FieldStub::AnyOf(types, fallback) => (
+            true,
+            implement_any_of_attr(types, fallback.as_deref(), expr, ty),
+        ),
+        FieldStub::Path => (true, implement_path_attr(expr)),
+        FieldStub::Json(redact) => (true, implement_json_attr(redact, expr)),
+        FieldStub::CStr => (true, implement_c_str_attr(expr)),
+        FieldStub::Bytes => (true, implement_bytes_attr(expr)),
+        FieldStub::Duration => (
+            true,
+            implement_debug_with(implement_human_duration(expr, "")),
+        ),
+        FieldStub::Elapsed => (
+            true,
+            implement_debug_with(implement_human_duration(
+                &parse_quote!(#expr.elapsed()),
+                " ago",
+            )),
+        ),
+        FieldStub::Counter => (true, implement_counter_attr(expr, ty)),
+        FieldStub::Each(each) => (
+            true,
+            implement_each_attr(CollectionKind::List, each, expr, ty),
+        ),
+        FieldStub::Set(set) => (
+            true,
+            implement_each_attr(CollectionKind::Set, set, expr, ty),
+        ),
+        FieldStub::Map(key, value, filter) => (
+            true,
+            implement_map_attr(key.as_deref(), value.as_deref(), filter.as_ref(), expr, ty),
+        ),
+        FieldStub::SmartPointer(pointer, inner) => {
+            implement_smart_pointer_attr(*pointer, inner, expr, ty)
+        }
+        FieldStub::Pipeline(base, transforms) => {
+            let (used, value) = match base {
+                Some(base) => implement_stub(base, expr, ty),
+                None => (true, expr.clone()),
+            };
+            let inherit = matches!(base.as_deref(), Some(base) if base.inherits_precision());
+            (used, implement_pipeline(&value, transforms, inherit))
+        }
+        FieldStub::Precision(base, precision) => {
+            let (used, value) = match base {
+                Some(base) => implement_stub(base, expr, ty),
+                None => (true, expr.clone()),
+            };
+            match precision {
+                Some(precision) => (used, implement_precision(&value, *precision)),
+                // the value receives the precision of the call site unless it is rendered into
+                // text, which `implement_inherited_precision` takes care of
+                None => (used, value),
+            }
+        }
+        FieldStub::OnError(base, marker) => {
+            let (used, value) = match base {
+                Some(base) => implement_stub(base, expr, ty),
+                None => (true, expr.clone()),
+            };
+            let inherit = matches!(base.as_deref(), Some(base) if base.inherits_precision());
+            (used, implement_on_error(&value, marker, inherit))
+        }
+        FieldStub::Spill(base, spill) => {
+            let (used, value) = match base {
+                Some(base) => implement_stub(base, expr, ty),
+                None => (true, expr.clone()),
+            };
+            let inherit = matches!(base.as_deref(), Some(base) if base.inherits_precision());
+            (used, implement_spill(&value, spill, inherit))
+        }
+        FieldStub::If(predicate, then) => {
+            let (_, then) = implement_stub(then, expr, ty);
+            (
+                true,
+                implement_debug_with(quote! {
+                    if #predicate(#expr) {
+                        ::core::fmt::Debug::fmt(&#then, f)
+                    } else {
+                        ::core::fmt::Debug::fmt(#expr, f)
+                    }
+                }),
+            )
+        }
+        FieldStub::ReleaseOnly(inner) => {
+            let (_, release) = implement_stub(inner, expr, ty);
+            (
+                true,
+                implement_debug_with(quote! {
+                    if ::core::cfg!(debug_assertions) {
+                        ::core::fmt::Debug::fmt(#expr, f)
+                    } else {
+                        ::core::fmt::Debug::fmt(&#release, f)
+                    }
+                }),
+            )
+        }
+        FieldStub::Alternate(compact, alternate) => {
+            let (compact_used, compact) = match compact {
+                Some(compact) => implement_stub(compact, expr, ty),
+                None => (true, expr.clone()),
+            };
+            let (alternate_used, alternate) = implement_stub(alternate, expr, ty);
+            (
+                compact_used || alternate_used,
+                implement_debug_with(quote! {
+                    if f.alternate() {
+                        ::core::fmt::Debug::fmt(&#alternate, f)
+                    } else {
+                        ::core::fmt::Debug::fmt(&#compact, f)
+                    }
+                }),
+            )
+        }
+        FieldStub::Cell(inner) => implement_cell_attr(inner.as_deref(), expr, ty),
+        FieldStub::AssumeInit(inner) => {
+            // safety: the field has been declared initialized with `unsafe_assume_init`
+            let expr = implement_unsafe("unsafe_assume_init", quote!(#expr.assume_init_ref()));
+            match inner {
+                Some(inner) => implement_stub(inner, &expr, &generic_type_arg(ty, 0)),
+                None => (true, expr),
+            }
+        }
+        FieldStub::Atomic(inner) => implement_atomic_attr(inner.as_deref(), expr, ty),
+        FieldStub::Once(inner) => (true, implement_once_attr(inner.as_deref(), expr, ty)),
+        FieldStub::Cow(inner) => (true, implement_cow_attr(inner.as_deref(), expr, ty)),
+        FieldStub::Weak(inner) => (true, implement_weak_attr(inner.as_deref(), expr, ty)),
+        FieldStub::Counts(inner) => (true, implement_counts_attr(inner.as_deref(), expr, ty)),
+        FieldStub::Lock(lock, inner) => {
+            (true, implement_lock_attr(*lock, inner.as_deref(), expr, ty))
+        }
+    }
+}
+
+/// Generates the `f.field()` Formatter statement for a rendered field
+fn implement_field_stmt(field: &RenderedField) -> Stmt {
+    let value = &field.value;
+    let stmt = match &field.name {
+        Some(name) => quote!(f.field(#name, &#value);),
+        None => quote!(f.field(&#value);),
+    };
+    let stmt = field.unless_skipped(stmt);
+    parse_quote!(#stmt)
+}
+
+/// Makes the rendered fields print their text as rewritten by the `fn(&str, &str) -> Cow<str>` of
+/// `#[debug_stub(redactor = "...")]`, which is called with the field name and the printed text
+fn apply_redactor(fields: &mut [RenderedField], redactor: &Path) {
+    for (index, field) in fields.iter_mut().enumerate() {
+        let label = field.label(index);
+        let value = &field.value;
+        let flags = implement_debug_flags(quote!(f), quote!(::core::option::Option::None::<usize>));
+        let write = implement_write_debug(quote!(&mut text), flags, quote!(#value));
+        field.value = implement_debug_with(quote!({
+            let mut text = ::std::string::String::new();
+            #write?;
+            f.write_str(&#redactor(#label, &text))
+        }));
+    }
+}
+
+/// Generates the statements rendering the fields into strings for `#[debug_stub(max_output = N)]`,
+/// which replace the fields with `…` in the order of their priority until the rendered text fits
+/// into the budget, and makes the fields print their rendered text
+fn implement_output_budget(fields: &mut [RenderedField], max_output: usize) -> Vec<Stmt> {
+    let count = fields.len();
+    let values = fields.iter().map(|field| &field.value).collect::<Vec<_>>();
+
+    // lowest priority first, and the later field first among equal ones, never eliding the fields
+    // with the highest priority
+    let highest = fields.iter().map(|field| field.priority).max().unwrap_or(0);
+    let mut order = (0..count)
+        .filter(|&index| fields[index].priority < highest)
+        .collect::<Vec<_>>();
+    order.sort_by_key(|&index| (fields[index].priority, std::cmp::Reverse(index)));
+    let flags = implement_debug_flags(quote!(f), quote!(::core::option::Option::None::<usize>));
+    let write = implement_write_debug(quote!(&mut text), quote!(flags), quote!(value));
+
+    let stmts = parse_quote! {
+        let (rendered, elided) = {
+            let flags = #flags;
+            let render = |value: &dyn ::core::fmt::Debug| {
+                let mut text = ::std::string::String::new();
+                let _ = #write;
+                text
+            };
+            let rendered: [::std::string::String; #count] = [#(render(&#values)),*];
+            let mut elided = [false; #count];
+            let mut len = rendered
+                .iter()
+                .map(|text| text.chars().count())
+                .sum::<usize>();
+            for &index in &[#(#order),*] as &[usize] {
+                if len <= #max_output {
+                    break;
+                }
+                len = len - rendered[index].chars().count() + 1;
+                elided[index] = true;
+            }
+            (rendered, elided)
+        };
+    };
+
+    for (index, field) in fields.iter_mut().enumerate() {
+        field.value = parse_quote! {
+            format_args!("{}", if elided[#index] { "…" } else { rendered[#index].as_str() })
+        };
+    }
+    vec![stmts]
+}
+
+/// Generates `let _ = &self.field;` statements for the fields whose printed value does not use them,
+/// so that they are not reported as dead code for `#[debug_stub(mark_used)]`
+fn implement_mark_used_stmts(fields: &[RenderedField]) -> Vec<Stmt> {
+    fields
+        .iter()
+        .filter(|field| !field.used)
+        .map(|field| {
+            let access = &field.access;
+            parse_quote!(let _ = #access;)
+        })
+        .collect()
+}
+
+/// Wraps an expression into an `unsafe` block. This is the only place generating `unsafe` code, and
+/// only options whose key starts with `unsafe_` may call it, so that the Debug impl of a type
+/// without such options never contains `unsafe`
+fn implement_unsafe(key: &str, expr: proc_macro2::TokenStream) -> Expr {
+    debug_assert!(
+        key.starts_with("unsafe_"),
+        "`{}` cannot generate unsafe code",
+        key
+    );
+    parse_quote!((unsafe { #expr }))
+}
+
+/// Wraps a Formatter expression (with `f: &mut Formatter` in scope) into an `fmt::Debug` value
+fn implement_debug_with(body: proc_macro2::TokenStream) -> Expr {
+    parse_quote! {{
+        struct DebugWith<F>(F);
+
+        impl<F> ::core::fmt::Debug for DebugWith<F>
+        where
+            F: Fn(&mut ::core::fmt::Formatter) -> ::core::fmt::Result,
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                (self.0)(f)
+            }
+        }
+
+        DebugWith(move |f: &mut ::core::fmt::Formatter| #body)
+    }}
+}
+
+/// Generates the field value for `#[debug_stub = "..."]`, or for `#[debug_stub(const = "...")]`
+/// with the path of the constant
+fn implement_replace_attr(value: &impl quote::ToTokens) -> Expr {
+    parse_quote!(format_args!("{}", #value))
+}
+
+/// Generates the field value for `#[debug_stub = "..."]` with placeholders, which print the length,
+/// type name, or address of the field in place, like `<u8, 3 bytes>`
+fn implement_template_attr(parts: &[TemplatePart], expr: &Expr, ty: &Type) -> (bool, Expr) {
+    let (mut format, mut args, mut used) = (String::new(), vec![], false);
+    for part in parts {
+        match part {
+            TemplatePart::Text(text) => {
+                format.push_str(&text.replace('{', "{{").replace('}', "}}"))
+            }
+            TemplatePart::Len => {
+                format.push_str("{}");
+                args.push(quote!(#expr.len()));
+                used = true;
+            }
+            TemplatePart::Type => {
+                format.push_str("{}");
+                args.push(quote!(::core::any::type_name::<#ty>()));
+            }
+            TemplatePart::Ptr => {
+                format.push_str("{:p}");
+                args.push(quote!(#expr));
+                used = true;
+            }
+        }
+    }
+
+    (used, parse_quote!(format_args!(#format, #(#args),*)))
+}
+
+/// Generates the field value for `#[debug_stub(some = "...", none = "...")]`, printing the
+/// omitted side as is
+fn implement_option_attr(
+    some: Option<&FieldStub>,
+    none: Option<&str>,
+    expr: &Expr,
+    ty: &Type,
+) -> Expr {
+    let some = implement_wrapped_arm(
+        parse_quote!(::core::option::Option::Some),
+        some,
+        generic_type_arg(ty, 0),
+    );
+    let none = none.unwrap_or("None");
+
+    implement_debug_with(quote! {
+        match #expr {
+            #some
+            ::core::option::Option::None => f.write_str(#none),
+        }
+    })
+}
+
+/// Generates the field value for `#[debug_stub(ok = "...", err = "...")]`, printing the omitted
+/// side as is
+fn implement_result_attr(
+    ok: Option<&FieldStub>,
+    err: Option<&FieldStub>,
+    passthrough: &ErrPassthrough,
+    expr: &Expr,
+    ty: &Type,
+) -> Expr {
+    let ok = implement_wrapped_arm(
+        parse_quote!(::core::result::Result::Ok),
+        ok,
+        generic_type_arg(ty, 0),
+    );
+    let err = if passthrough.is_set() {
+        let value = implement_err_passthrough(passthrough);
+        quote!(::core::result::Result::Err(value) => f.debug_tuple("Err").field(&#value).finish(),)
+    } else {
+        implement_wrapped_arm(
+            parse_quote!(::core::result::Result::Err),
+            err,
+            generic_type_arg(ty, 1),
+        )
+    };
+
+    implement_debug_with(quote! {
+        match #expr {
+            #ok
+            #err
+        }
+    })
+}
+
+/// Generates the value printed for the `value` of an `Err(value)` pattern with the
+/// `err_max_len = N` and `err_display` modifiers applied
+fn implement_err_passthrough(passthrough: &ErrPassthrough) -> Expr {
+    let max_len = match passthrough.max_len {
+        Some(max_len) => max_len,
+        None if passthrough.display => return parse_quote!(format_args!("{}", value)),
+        None => return parse_quote!(value),
+    };
+    let truncator = implement_truncator();
+    let flags = implement_debug_flags(quote!(f), quote!(f.precision()));
+    let write = if passthrough.display {
+        quote!(::core::fmt::Write::write_fmt(
+            &mut out,
+            format_args!("{}", value)
+        ))
+    } else {
+        implement_write_debug(quote!(&mut out), quote!(flags), quote!(value))
+    };
+
+    implement_debug_with(quote!({
+        #truncator
+        let flags = #flags;
+        let mut out = Truncator {
+            f,
+            remaining: #max_len,
+            truncated: false,
+        };
+        #write
+    }))
+}
+
+/// Generates the `Truncator` item, a `fmt::Write` adapter which writes at most `remaining`
+/// characters to the wrapped Formatter, followed by `…` if anything was cut
+fn implement_truncator() -> proc_macro2::TokenStream {
+    quote! {
+        struct Truncator<'a, 'b> {
+            f: &'a mut ::core::fmt::Formatter<'b>,
+            remaining: usize,
+            truncated: bool,
+        }
+
+        impl<'a, 'b> ::core::fmt::Write for Truncator<'a, 'b> {
+            fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+                if self.truncated {
+                    return Ok(());
+                }
+                match s.char_indices().nth(self.remaining) {
+                    Some((end, _)) => {
+                        self.f.write_str(&s[..end])?;
+                        self.truncated = true;
+                        self.f.write_str("…")
+                    }
+                    None => {
+                        self.remaining -= s.chars().count();
+                        self.f.write_str(s)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Generates a match arm printing the value wrapped in a `Some`, `Ok`, or `Err` variant through
+/// its nested stub, or as is. `ty` is the type of the wrapped value
+fn implement_wrapped_arm(
+    variant: Path,
+    stub: Option<&FieldStub>,
+    ty: Type,
+) -> proc_macro2::TokenStream {
+    let name = variant
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    let (used, value) = match stub {
+        Some(stub) => implement_stub(stub, &parse_quote!(value), &ty),
+        None => (true, parse_quote!(value)),
+    };
+    let binding = if used { quote!(value) } else { quote!(_) };
+
+    quote!(#variant(#binding) => f.debug_tuple(#name).field(&#value).finish(),)
+}
+
+/// Generates the field value for `#[debug_stub(future_state = "...")]`
+fn implement_future_state_attr(done: &Path, expr: &Expr) -> Expr {
+    parse_quote! {
+        format_args!(
+            "{}",
+            if #expr.is_some() {
+                "Pending"
+            } else if #done(self) {
+                "Done"
+            } else {
+                "NotStarted"
+            },
+        )
+    }
+}
+
+/// Generates the field value for `#[debug_stub(resolve = "...")]`, printing an ID followed by the
+/// name the lookup function returns for it, like `42 "Player"`, or just the ID if there is none
+fn implement_resolve_attr(lookup: &Path, expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        ::core::fmt::Debug::fmt(#expr, f)?;
+        match #lookup(*#expr) {
+            ::core::option::Option::Some(name) => {
+                f.write_str(" ")?;
+                ::core::fmt::Debug::fmt(&name, f)
+            }
+            ::core::option::Option::None => ::core::result::Result::Ok(()),
+        }
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(method = "...")]`, printing what the given method
+/// returns when called on the field
+fn implement_method_attr(method: &Ident, expr: &Expr) -> Expr {
+    implement_debug_with(quote!(::core::fmt::Debug::fmt(&#expr.#method(), f)))
+}
+
+/// Generates the field value for `#[debug_stub(getter = "...")]`, printing what the given function
+/// returns when called with a reference to the field
+fn implement_getter_attr(getter: &Path, expr: &Expr) -> Expr {
+    implement_debug_with(quote!(::core::fmt::Debug::fmt(&#getter(#expr), f)))
+}
+
+/// Generates the field value for `#[debug_stub(expr = "...")]`, printing the given expression,
+/// which is evaluated in the `fmt` body on every call
+fn implement_expr_attr(value: &Expr) -> Expr {
+    implement_debug_with(quote!(::core::fmt::Debug::fmt(&(#value), f)))
+}
+
+/// Generates the field value for `#[debug_stub(with = ...)]`, calling the given closure or function
+/// with the field and the formatter
+fn implement_with_attr(with: &Expr, expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        // passing the closure to a function bounded by its signature lets its parameter types be
+        // inferred, which calling it directly would not
+        fn call<T: ?Sized, F>(value: &T, f: &mut ::core::fmt::Formatter, with: F) -> ::core::fmt::Result
+        where
+            F: Fn(&T, &mut ::core::fmt::Formatter) -> ::core::fmt::Result,
+        {
+            with(value, f)
+        }
+
+        call(#expr, f, #with)
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(len)]`
+fn implement_len_attr(expr: &Expr, ty: &Type) -> Expr {
+    let name = short_type_name(&collection_type(ty));
+    parse_quote!(format_args!("{}(len = {})", #name, #expr.len()))
+}
+
+/// Generates the field value for `#[debug_stub(shallow)]`, summarizing a field by the shape of its
+/// type without printing anything nested in it: the length of collections and strings, the variant
+/// of `Option` and `Result`, and the type name for anything else, like `Some(..)` or `Config(..)`.
+/// Primitive values have nothing nested and are printed as is
+fn implement_shallow_attr(expr: &Expr, ty: &Type) -> (bool, Expr) {
+    let name = short_type_name(ty);
+    let value = match name.as_str() {
+        // smart pointers to collections are summarized like the collections themselves
+        _ if matches!(
+            short_type_name(&collection_type(ty)).as_str(),
+            "Vec"
+                | "VecDeque"
+                | "LinkedList"
+                | "BinaryHeap"
+                | "HashMap"
+                | "BTreeMap"
+                | "IndexMap"
+                | "HashSet"
+                | "BTreeSet"
+                | "IndexSet"
+                | "String"
+                | "str"
+                | "Slice"
+                | "Array"
+        ) =>
+        {
+            implement_len_attr(expr, ty)
+        }
+        "Option" => parse_quote! {
+            format_args!("{}", match #expr {
+                ::core::option::Option::Some(_) => "Some(..)",
+                ::core::option::Option::None => "None",
+            })
+        },
+        "Result" => parse_quote! {
+            format_args!("{}", match #expr {
+                ::core::result::Result::Ok(_) => "Ok(..)",
+                ::core::result::Result::Err(_) => "Err(..)",
+            })
+        },
+        "bool" | "char" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16"
+        | "i32" | "i64" | "i128" | "isize" | "f32" | "f64" => return (true, expr.clone()),
+        _ => {
+            let summary = format!("{}(..)", name);
+            parse_quote!(format_args!(#summary))
+        }
+    };
+    (true, value)
+}
+
+/// Generates the field value for `#[debug_stub(scientific)]`, printing a number in scientific
+/// notation with `{:e}` or `{:E}`, keeping the precision of the formatter if there is one
+fn implement_scientific_attr(upper: bool, expr: &Expr) -> Expr {
+    let (format, precise_format) = if upper {
+        ("{:E}", "{:.*E}")
+    } else {
+        ("{:e}", "{:.*e}")
+    };
+    implement_debug_with(quote! {
+        match f.precision() {
+            ::core::option::Option::Some(precision) => {
+                f.write_fmt(format_args!(#precise_format, precision, #expr))
+            }
+            ::core::option::Option::None => f.write_fmt(format_args!(#format, #expr)),
+        }
+    })
+}
+
+/// Generates the field value for `#[debug_stub(separators)]`, printing an integer with its digits
+/// grouped by three, like `12_345_678`
+fn implement_separators_attr(separator: &str, expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        let digits = ::std::string::ToString::to_string(#expr);
+        let (sign, digits) = match digits.strip_prefix('-') {
+            ::core::option::Option::Some(digits) => ("-", digits),
+            ::core::option::Option::None => ("", digits.as_str()),
+        };
+        let head = match digits.len() % 3 {
+            0 => digits.len().min(3),
+            head => head,
+        };
+        f.write_str(sign)?;
+        f.write_str(&digits[..head])?;
+        let mut rest = &digits[head..];
+        while !rest.is_empty() {
+            f.write_str(#separator)?;
+            f.write_str(&rest[..3])?;
+            rest = &rest[3..];
+        }
+        ::core::result::Result::Ok(())
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(type_name)]`
+fn implement_type_name_attr(ty: &Type) -> Expr {
+    parse_quote!(format_args!("{}", ::core::any::type_name::<#ty>()))
+}
+
+/// Generates the field value for `#[debug_stub(hash)]`, printing the 64 bit FNV-1a hash of the
+/// value folded to 32 bits (`#a1b2c3d4`)
+fn implement_hash_attr(expr: &Expr) -> Expr {
+    let hasher = implement_fnv_hasher();
+    parse_quote! {
+        format_args!("#{:08x}", {
+            #hasher
+            let mut hasher = FnvHasher::default();
+            ::core::hash::Hash::hash(#expr, &mut hasher);
+            let hash = ::core::hash::Hasher::finish(&hasher);
+            (hash ^ (hash >> 32)) as u32
+        })
+    }
+}
+
+/// Generates the `FnvHasher` item, a `Hasher` whose output does not change between Rust versions,
+/// unlike the one of `DefaultHasher`
+fn implement_fnv_hasher() -> proc_macro2::TokenStream {
+    quote! {
+        struct FnvHasher(u64);
+
+        impl ::core::default::Default for FnvHasher {
+            fn default() -> Self {
+                FnvHasher(0xcbf2_9ce4_8422_2325)
+            }
+        }
+
+        impl ::core::hash::Hasher for FnvHasher {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+                }
+            }
+        }
+    }
+}
+
+/// Generates the field value for `#[debug_stub(sha256)]`, printing the first 8 bytes of the
+/// SHA-256 digest of a byte buffer (`sha256:ba7816bf8f01cfea…`)
+fn implement_sha256_attr(expr: &Expr) -> Expr {
+    let sha256 = implement_sha256_fn();
+    parse_quote! {
+        format_args!("sha256:{:016x}…", {
+            #sha256
+            let digest = sha256(::core::convert::AsRef::<[u8]>::as_ref(#expr));
+            let mut prefix = [0u8; 8];
+            prefix.copy_from_slice(&digest[..8]);
+            u64::from_be_bytes(prefix)
+        })
+    }
+}
+
+/// Generates a dependency free `fn sha256(data: &[u8]) -> [u8; 32]`
+fn implement_sha256_fn() -> proc_macro2::TokenStream {
+    quote! {
+        fn sha256(data: &[u8]) -> [u8; 32] {
+            const K: [u32; 64] = [
+                0x428a_2f98, 0x7137_4491, 0xb5c0_fbcf, 0xe9b5_dba5, 0x3956_c25b, 0x59f1_11f1,
+                0x923f_82a4, 0xab1c_5ed5, 0xd807_aa98, 0x1283_5b01, 0x2431_85be, 0x550c_7dc3,
+                0x72be_5d74, 0x80de_b1fe, 0x9bdc_06a7, 0xc19b_f174, 0xe49b_69c1, 0xefbe_4786,
+                0x0fc1_9dc6, 0x240c_a1cc, 0x2de9_2c6f, 0x4a74_84aa, 0x5cb0_a9dc, 0x76f9_88da,
+                0x983e_5152, 0xa831_c66d, 0xb003_27c8, 0xbf59_7fc7, 0xc6e0_0bf3, 0xd5a7_9147,
+                0x06ca_6351, 0x1429_2967, 0x27b7_0a85, 0x2e1b_2138, 0x4d2c_6dfc, 0x5338_0d13,
+                0x650a_7354, 0x766a_0abb, 0x81c2_c92e, 0x9272_2c85, 0xa2bf_e8a1, 0xa81a_664b,
+                0xc24b_8b70, 0xc76c_51a3, 0xd192_e819, 0xd699_0624, 0xf40e_3585, 0x106a_a070,
+                0x19a4_c116, 0x1e37_6c08, 0x2748_774c, 0x34b0_bcb5, 0x391c_0cb3, 0x4ed8_aa4a,
+                0x5b9c_ca4f, 0x682e_6ff3, 0x748f_82ee, 0x78a5_636f, 0x84c8_7814, 0x8cc7_0208,
+                0x90be_fffa, 0xa450_6ceb, 0xbef9_a3f7, 0xc671_78f2,
+            ];
+
+            fn compress(state: &mut [u32; 8], block: &[u8]) {
+                let mut w = [0u32; 64];
+                for i in 0..16 {
+                    w[i] = u32::from_be_bytes([
+                        block[i * 4],
+                        block[i * 4 + 1],
+                        block[i * 4 + 2],
+                        block[i * 4 + 3],
+                    ]);
+                }
+                for i in 16..64 {
+                    let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+                    let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+                    w[i] = w[i - 16]
+                        .wrapping_add(s0)
+                        .wrapping_add(w[i - 7])
+                        .wrapping_add(s1);
+                }
+
+                let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+                for i in 0..64 {
+                    let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+                    let ch = (e & f) ^ (!e & g);
+                    let t1 = h
+                        .wrapping_add(s1)
+                        .wrapping_add(ch)
+                        .wrapping_add(K[i])
+                        .wrapping_add(w[i]);
+                    let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+                    let maj = (a & b) ^ (a & c) ^ (b & c);
+                    let t2 = s0.wrapping_add(maj);
+                    h = g;
+                    g = f;
+                    f = e;
+                    e = d.wrapping_add(t1);
+                    d = c;
+                    c = b;
+                    b = a;
+                    a = t1.wrapping_add(t2);
+                }
+
+                for (word, value) in state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) {
+                    *word = word.wrapping_add(*value);
+                }
+            }
+
+            let mut state: [u32; 8] = [
+                0x6a09_e667, 0xbb67_ae85, 0x3c6e_f372, 0xa54f_f53a, 0x510e_527f, 0x9b05_688c,
+                0x1f83_d9ab, 0x5be0_cd19,
+            ];
+
+            let mut chunks = data.chunks_exact(64);
+            for block in &mut chunks {
+                compress(&mut state, block);
+            }
+
+            let rest = chunks.remainder();
+            let mut block = [0u8; 64];
+            block[..rest.len()].copy_from_slice(rest);
+            block[rest.len()] = 0x80;
+            if rest.len() >= 56 {
+                compress(&mut state, &block);
+                block = [0u8; 64];
+            }
+            block[56..].copy_from_slice(&(data.len() as u64).wrapping_mul(8).to_be_bytes());
+            compress(&mut state, &block);
+
+            let mut digest = [0u8; 32];
+            for (bytes, word) in digest.chunks_exact_mut(4).zip(&state) {
+                bytes.copy_from_slice(&word.to_be_bytes());
+            }
+            digest
+        }
+    }
+}
+
+/// Generates the field value for `#[debug_stub(crc32)]`, printing the CRC-32 (IEEE) checksum of a
+/// byte buffer (`crc32:cbf43926`)
+fn implement_crc32_attr(expr: &Expr) -> Expr {
+    parse_quote! {
+        format_args!("crc32:{:08x}", {
+            let mut crc = !0u32;
+            for byte in ::core::convert::AsRef::<[u8]>::as_ref(#expr) {
+                crc ^= u32::from(*byte);
+                for _ in 0..8 {
+                    crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+                }
+            }
+            !crc
+        })
+    }
+}
+
+/// Generates the field value for `#[debug_stub(bits(...))]`, printing each bit group of an integer
+/// as `name=value`, like `version=3 flags=0b1010`
+fn implement_bits_attr(groups: &[BitGroup], expr: &Expr) -> Expr {
+    let template = groups
+        .iter()
+        .map(|group| {
+            let name = group.name.replace('{', "{{").replace('}', "}}");
+            match group.binary {
+                true => format!("{}={{:#0{}b}}", name, group.width + 2),
+                false => format!("{}={{}}", name),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let values = groups.iter().map(|group| {
+        let shift = group.shift;
+        let mask = u128::MAX >> (128 - group.width);
+        quote!((value >> #shift) & #mask)
+    });
+
+    parse_quote!({
+        // the cast is trivial for `u128` fields, and sign-extends signed ones
+        #[allow(trivial_numeric_casts)]
+        let value = *#expr as u128;
+        format_args!(#template, #(#values),*)
+    })
+}
+
+/// Generates the field value for `#[debug_stub(any_of(...), else = "...")]`, printing the value of
+/// a generic field as is if it is one of the given types, and the fallback text, or the name of
+/// the field type, otherwise
+fn implement_any_of_attr(types: &[Path], fallback: Option<&str>, expr: &Expr, ty: &Type) -> Expr {
+    let fallback = match fallback {
+        Some(fallback) => quote!(f.write_str(#fallback)),
+        None => quote!(f.write_str(::core::any::type_name::<#ty>())),
+    };
+
+    implement_debug_with(quote!({
+        let any: &dyn ::core::any::Any = #expr;
+        #(
+            if let ::core::option::Option::Some(value) = any.downcast_ref::<#types>() {
+                return ::core::fmt::Debug::fmt(value, f);
+            }
+        )*
+        #fallback
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(json(redact(...)))]`, printing a serializable
+/// value as JSON, pretty-printed with `{:#?}`, with the values of the given object keys replaced
+/// by `"<redacted>"` at any depth. Values failing to serialize, like maps with non-string keys,
+/// print as `<json error: ...>`, as returning an error would make `format!()` panic
+fn implement_json_attr(redact: &[String], expr: &Expr) -> Expr {
+    let write = quote! {
+        match text {
+            ::core::result::Result::Ok(text) => f.write_str(&text),
+            ::core::result::Result::Err(err) => {
+                f.write_fmt(format_args!("<json error: {}>", err))
+            }
+        }
+    };
+    if redact.is_empty() {
+        return implement_debug_with(quote!({
+            let text = if f.alternate() {
+                ::serde_json::to_string_pretty(#expr)
+            } else {
+                ::serde_json::to_string(#expr)
+            };
+            #write
+        }));
+    }
 
-            if ident_used {
-                pats.push(parse_quote!(#ident));
-            } else if unnamed {
-                // Skip unused tuple fields to avoid "unused variable" warnings
-                pats.push(parse_quote!(_));
+    implement_debug_with(quote!({
+        fn redact(value: &::serde_json::Value, keys: &[&str]) -> ::serde_json::Value {
+            match value {
+                ::serde_json::Value::Object(map) => ::serde_json::Value::Object(
+                    map.iter()
+                        .map(|(key, value)| {
+                            let value = if keys.contains(&key.as_str()) {
+                                ::serde_json::Value::from("<redacted>")
+                            } else {
+                                redact(value, keys)
+                            };
+                            (key.clone(), value)
+                        })
+                        .collect(),
+                ),
+                ::serde_json::Value::Array(values) => ::serde_json::Value::Array(
+                    values.iter().map(|value| redact(value, keys)).collect(),
+                ),
+                value => value.clone(),
+            }
+        }
+
+        let text = ::serde_json::to_value(#expr).and_then(|value| {
+            let value = redact(&value, &[#(#redact),*]);
+            if f.alternate() {
+                ::serde_json::to_string_pretty(&value)
             } else {
-                // Skip unused struct fields to avoid "unused variable" warnings
-                unused_fields = true;
+                ::serde_json::to_string(&value)
             }
+        });
+        #write
+    }))
+}
 
-            Ok(stmt)
-        })
-        .collect::<syn::Result<Vec<_>>>()?;
+/// Generates the field value for `#[debug_stub(path)]`, printing a path through `Path::display`
+fn implement_path_attr(expr: &Expr) -> Expr {
+    parse_quote! {
+        format_args!(
+            "{}",
+            ::std::path::Path::display(::core::convert::AsRef::<::std::path::Path>::as_ref(#expr)),
+        )
+    }
+}
 
-    if unused_fields {
-        pats.push(parse_quote!(..));
+/// Generates the field value for `#[debug_stub(c_str)]`, printing a C string as a string if it is
+/// valid UTF-8 (`"text"`), and as an escaped byte string otherwise (`b"\xfftext"`)
+fn implement_c_str_attr(expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        let c_str = ::core::convert::AsRef::<::std::ffi::CStr>::as_ref(#expr);
+        match c_str.to_str() {
+            ::core::result::Result::Ok(text) => ::core::fmt::Debug::fmt(text, f),
+            ::core::result::Result::Err(_) => {
+                f.write_str("b\"")?;
+                for &byte in c_str.to_bytes() {
+                    for c in ::core::ascii::escape_default(byte) {
+                        f.write_fmt(format_args!("{}", c as char))?;
+                    }
+                }
+                f.write_str("\"")
+            }
+        }
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(variant_counts)]`, printing how many elements of a
+/// collection of an enum deriving `DebugStub` there are of each variant, like `{A: 120, B: 3, C: 0}`
+fn implement_variant_counts_attr(expr: &Expr, ty: &Type) -> Expr {
+    let elem = element_type(ty);
+
+    implement_debug_with(quote!({
+        let variants = <#elem>::DEBUG_STUB_VARIANTS;
+        let mut counts = ::std::vec![0_usize; variants.len()];
+        for value in #expr.iter() {
+            counts[value.debug_stub_variant_index()] += 1;
+        }
+        let mut builder = f.debug_map();
+        for (name, count) in variants.iter().zip(counts) {
+            builder.entry(&format_args!("{}", name), &count);
+        }
+        builder.finish()
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(bytes)]`, printing an integer size in bytes in the
+/// largest binary unit it has a whole amount of, like `512 B` or `1.44 MiB`, with a leading `-` for
+/// negative sizes
+fn implement_bytes_attr(expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+        // values of unsigned types beyond `i128::MAX` fail the conversion and are not negative; the
+        // cast is trivial for `u128` fields
+        #[allow(trivial_numeric_casts)]
+        let (sign, bytes) = match <i128 as ::core::convert::TryFrom<_>>::try_from(*#expr) {
+            ::core::result::Result::Ok(bytes) if bytes < 0 => ("-", bytes.unsigned_abs()),
+            _ => ("", *#expr as u128),
+        };
+        if bytes < 1024 {
+            return f.write_fmt(format_args!("{}{} B", sign, bytes));
+        }
+        let (mut size, mut unit) = (bytes as f64 / 1024.0, 0);
+        while size >= 1024.0 && unit + 1 < UNITS.len() {
+            size /= 1024.0;
+            unit += 1;
+        }
+        f.write_fmt(format_args!("{}{:.2} {}", sign, size, UNITS[unit]))
+    }))
+}
+
+/// Generates a Debug body printing a `Duration` in the largest unit it has a whole amount of, with
+/// up to three decimals, like `1.5s`, `320ms`, or `12µs`, followed by `suffix`
+fn implement_human_duration(duration: &Expr, suffix: &str) -> proc_macro2::TokenStream {
+    quote!({
+        let nanos = #duration.as_nanos();
+        let (unit, scale) = match nanos {
+            1_000_000_000..=u128::MAX => ("s", 1_000_000_000),
+            1_000_000..=999_999_999 => ("ms", 1_000_000),
+            1_000..=999_999 => ("µs", 1_000),
+            _ => ("ns", 1),
+        };
+        let (mut decimals, mut fraction) = (3, nanos % scale * 1_000 / scale);
+        while decimals > 0 && fraction % 10 == 0 {
+            decimals -= 1;
+            fraction /= 10;
+        }
+        if decimals == 0 {
+            f.write_fmt(format_args!("{}{}{}", nanos / scale, unit, #suffix))
+        } else {
+            f.write_fmt(format_args!(
+                "{}.{:0decimals$}{}{}",
+                nanos / scale,
+                fraction,
+                unit,
+                #suffix,
+                decimals = decimals,
+            ))
+        }
+    })
+}
+
+/// Generates the field value for `#[debug_stub(ptr)]`, printing the address a pointer-like field
+/// (`Box`, `Rc`, `Arc`, references, and raw pointers) points to
+fn implement_ptr_attr(style: AddrStyle, expr: &Expr) -> Expr {
+    match style {
+        AddrStyle::Short => implement_debug_with(quote!(::core::fmt::Pointer::fmt(#expr, f))),
+        // `{:#p}` zero-pads to the pointer width, which needs a fresh set of formatting flags
+        AddrStyle::Full => implement_debug_with(quote!({
+            struct Addr<'a, P: ?Sized>(&'a P);
+
+            impl<P: ?Sized + ::core::fmt::Pointer> ::core::fmt::Pointer for Addr<'_, P> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    ::core::fmt::Pointer::fmt(self.0, f)
+                }
+            }
+
+            f.write_fmt(format_args!("{:#p}", Addr(#expr)))
+        })),
     }
+}
+
+/// Generates the field value for `#[debug_stub(each = "...")]` and `#[debug_stub(set = "...")]`,
+/// printing a sequence or set with each element printed through the nested stub
+fn implement_each_attr(kind: CollectionKind, each: &FieldStub, expr: &Expr, ty: &Type) -> Expr {
+    let (used, entry) = implement_stub(each, &parse_quote!(value), &element_type(ty));
+    let binding = if used { quote!(value) } else { quote!(_) };
+    let builder = match kind {
+        CollectionKind::List => quote!(f.debug_list()),
+        CollectionKind::Set | CollectionKind::Map => quote!(f.debug_set()),
+    };
 
-    Ok((pats, stmts))
+    implement_debug_with(quote!({
+        let mut builder = #builder;
+        for #binding in #expr.iter() {
+            builder.entry(&#entry);
+        }
+        builder.finish()
+    }))
 }
 
-/// Generates a single Formatter statement from given field and attributes. Also returns whether the
-/// field value is actually being used in the statement
-fn extract_value_attr(
+/// Generates the field value for `#[debug_stub(key = "...", value = "...")]`, printing a map with
+/// its keys and/or values printed through the nested stubs, and the omitted side as is. With a key
+/// filter, only the matching entries are printed, followed by an `… and N more entries` marker
+/// counting the others
+fn implement_map_attr(
+    key: Option<&FieldStub>,
+    value: Option<&FieldStub>,
+    filter: Option<&KeyFilter>,
     expr: &Expr,
-    attrs: &[Attribute],
-    name: Option<String>,
-) -> syn::Result<(bool, Stmt)> {
-    for attr in attrs {
-        let meta = match attr.parse_meta() {
-            Ok(meta) if meta.path().is_ident("debug_stub") => meta,
-            _ => continue,
+    ty: &Type,
+) -> Expr {
+    let entry = |name: &str, stub: Option<&FieldStub>, ty: Type| {
+        let ident = Ident::new(name, Span::call_site());
+        let (used, entry) = match stub {
+            Some(stub) => implement_stub(stub, &parse_quote!(#ident), &ty),
+            None => (true, parse_quote!(#ident)),
         };
+        let binding = if used { quote!(#ident) } else { quote!(_) };
+        (binding, entry)
+    };
+    let ty = &collection_type(ty);
+    let (key_binding, key) = entry("key", key, generic_type_arg(ty, 0));
+    let (value_binding, value) = entry("value", value, generic_type_arg(ty, 1));
 
-        match meta {
-            // `#[debug_stub]`
-            Meta::Path(path) => {
-                return Err(syn::Error::new_spanned(
-                    path,
-                    "expected `List` or `NameValue`",
-                ));
+    if let Some(filter) = filter {
+        let matches = match filter {
+            KeyFilter::Prefix(prefix) => {
+                quote!(::core::convert::AsRef::<str>::as_ref(key).starts_with(#prefix))
             }
-            // `#[debug_stub(key1 = val1, key2 = val2)]`
-            Meta::List(MetaList { nested, .. }) => {
-                return match extract_named_value_attrs(nested.iter()) {
-                    (None, None, Some(some)) => Ok((true, implement_some_attr(&some, name, expr))),
-                    (Some(ok), Some(err), None) => {
-                        Ok((true, implement_result_attr(&ok, &err, name, expr)))
-                    }
-                    (Some(ok), None, None) => Ok((true, implement_ok_attr(&ok, name, expr))),
-                    (None, Some(err), None) => Ok((true, implement_err_attr(&err, name, expr))),
-                    _ => Err(syn::Error::new_spanned(
-                        nested,
-                        "expected `some = _`, `ok = _`, `err = _`, or `ok = _, err = _`",
-                    )),
-                };
+            KeyFilter::With(predicate) => quote!(#predicate(key)),
+        };
+        // printed as a set of `key: value` entries for the marker, like `implement_elided_collection`
+        let entry = implement_debug_with(quote!({
+            ::core::fmt::Debug::fmt(&#key, f)?;
+            f.write_str(": ")?;
+            ::core::fmt::Debug::fmt(&#value, f)
+        }));
+
+        return implement_debug_with(quote!({
+            let mut builder = f.debug_set();
+            let mut more = 0;
+            for (key, value) in #expr.iter() {
+                if !#matches {
+                    more += 1;
+                    continue;
+                }
+                let (#key_binding, #value_binding) = (key, value);
+                builder.entry(&#entry);
             }
-            // `#[debug_stub = "literal"]`
-            Meta::NameValue(MetaNameValue { lit, .. }) => {
-                let lit = syn::parse2::<LitStr>(lit.to_token_stream())?;
-                return Ok((false, implement_replace_attr(name, &lit.value())));
+            if more > 0 {
+                builder.entry(&format_args!("… and {} more entries", more));
             }
+            builder.finish()
+        }));
+    }
+
+    implement_debug_with(quote!({
+        let mut builder = f.debug_map();
+        for (#key_binding, #value_binding) in #expr.iter() {
+            builder.entry(&#key, &#value);
         }
+        builder.finish()
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(boxed = "...")]` and its `rc` and `arc`
+/// counterparts, printing the pointee through the nested stub, like `Box(ReplacementValue)`. Also
+/// returns whether the field value is used, like `implement_stub`
+fn implement_smart_pointer_attr(
+    pointer: SmartPointer,
+    inner: &FieldStub,
+    expr: &Expr,
+    ty: &Type,
+) -> (bool, Expr) {
+    let name = match pointer {
+        SmartPointer::Box => "Box",
+        SmartPointer::Rc => "Rc",
+        SmartPointer::Arc => "Arc",
+    };
+    let (used, value) = implement_stub(inner, &parse_quote!((&**#expr)), &generic_type_arg(ty, 0));
+
+    (
+        used,
+        implement_debug_with(quote!(f.debug_tuple(#name).field(&#value).finish())),
+    )
+}
+
+/// Generates the field value for `#[debug_stub(cell)]`, copying the value out of a `Cell` with
+/// `Cell::get` and printing it as is or through the nested stub, like `Cell(42)`. Also returns
+/// whether the field value is used, like `implement_stub`
+fn implement_cell_attr(inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> (bool, Expr) {
+    let (used, value) = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!((&value)), &generic_type_arg(ty, 0)),
+        None => (true, parse_quote!((&value))),
+    };
+    if !used {
+        return (
+            false,
+            implement_debug_with(quote!(f.debug_tuple("Cell").field(&#value).finish())),
+        );
     }
 
-    Ok(match name {
-        Some(name) => (true, parse_quote!(f.field(#name, &#expr);)),
-        None => (true, parse_quote!(f.field(&#expr);)),
-    })
+    (
+        true,
+        implement_debug_with(quote!({
+            let value = #expr.get();
+            f.debug_tuple("Cell").field(&#value).finish()
+        })),
+    )
 }
 
-/// Extracts the `ok = "..."`, `err = "..."`, and `some = "..."` attributes, if present
-fn extract_named_value_attrs<'a>(
-    nested: impl Iterator<Item = &'a NestedMeta>,
-) -> (Option<String>, Option<String>, Option<String>) {
-    let (mut ok, mut err, mut some) = (None, None, None);
+/// Generates the field value for `#[debug_stub(counts)]`, printing the reference counts of an `Rc`
+/// or `Arc` next to the pointee, which is printed as is or through the nested stub, like
+/// `Arc(strong = 3, weak = 1, 42)`
+fn implement_counts_attr(inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> Expr {
+    let value = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!((&**#expr)), &generic_type_arg(ty, 0)).1,
+        None => parse_quote!((&**#expr)),
+    };
 
-    for nested in nested {
-        if let NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-            path,
-            lit: Lit::Str(lit),
-            ..
-        })) = nested
-        {
-            if path.is_ident("some") {
-                some = Some(lit.value());
-            } else if path.is_ident("ok") {
-                ok = Some(lit.value());
-            } else if path.is_ident("err") {
-                err = Some(lit.value());
+    implement_debug_with(quote!({
+        // both pointer types expose the same associated functions, but no common trait
+        trait Counts {
+            fn counts(&self) -> (&'static str, usize, usize);
+        }
+
+        impl<T: ?Sized> Counts for ::std::rc::Rc<T> {
+            fn counts(&self) -> (&'static str, usize, usize) {
+                ("Rc", ::std::rc::Rc::strong_count(self), ::std::rc::Rc::weak_count(self))
             }
         }
+
+        impl<T: ?Sized> Counts for ::std::sync::Arc<T> {
+            fn counts(&self) -> (&'static str, usize, usize) {
+                ("Arc", ::std::sync::Arc::strong_count(self), ::std::sync::Arc::weak_count(self))
+            }
+        }
+
+        let (name, strong, weak) = #expr.counts();
+        f.debug_tuple(name)
+            .field(&format_args!("strong = {}", strong))
+            .field(&format_args!("weak = {}", weak))
+            .field(&#value)
+            .finish()
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(cow)]`, printing whether a `Cow` is borrowed or
+/// owned around its value, which is printed as is or through the nested stub, like `Borrowed("a")`
+fn implement_cow_attr(inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> Expr {
+    let value = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!((&**#expr)), &generic_type_arg(ty, 0)).1,
+        None => parse_quote!((&**#expr)),
+    };
+
+    implement_debug_with(quote!({
+        let name = match #expr {
+            ::std::borrow::Cow::Borrowed(_) => "Borrowed",
+            ::std::borrow::Cow::Owned(_) => "Owned",
+        };
+        f.debug_tuple(name).field(&#value).finish()
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(once)]`, printing a `OnceCell` or `OnceLock` as
+/// `Uninit`, or as `Init(42)` with the value printed as is or through the nested stub
+fn implement_once_attr(inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> Expr {
+    let (used, value) = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!(value), &generic_type_arg(ty, 0)),
+        None => (true, parse_quote!(value)),
+    };
+    let binding = if used { quote!(value) } else { quote!(_) };
+
+    implement_debug_with(quote! {
+        match #expr.get() {
+            ::core::option::Option::Some(#binding) => f.debug_tuple("Init").field(&#value).finish(),
+            ::core::option::Option::None => f.write_str("Uninit"),
+        }
+    })
+}
+
+/// Generates the field value for `#[debug_stub(atomic)]`, loading the value of an atomic with
+/// `Ordering::Relaxed` and printing it as is or through the nested stub, like `42`. Also returns
+/// whether the field value is used, like `implement_stub`
+fn implement_atomic_attr(inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> (bool, Expr) {
+    let (used, value) = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!((&value)), &atomic_value_type(ty)),
+        None => (true, parse_quote!((&value))),
+    };
+    if !used {
+        return (false, value);
     }
 
-    (ok, err, some)
+    (
+        true,
+        implement_debug_with(quote!({
+            let value = #expr.load(::core::sync::atomic::Ordering::Relaxed);
+            ::core::fmt::Debug::fmt(&#value, f)
+        })),
+    )
+}
+
+/// Generates the field value for `#[debug_stub(counter)]`, incrementing a `Cell` or atomic integer
+/// field and printing the new count, like `formatted 14 times`
+fn implement_counter_attr(expr: &Expr, ty: &Type) -> Expr {
+    let count = match short_type_name(ty).starts_with("Atomic") {
+        true => quote! {
+            #expr.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed).wrapping_add(1)
+        },
+        false => quote!({
+            let count = #expr.get().wrapping_add(1);
+            #expr.set(count);
+            count
+        }),
+    };
+
+    implement_debug_with(quote!({
+        let count = #count;
+        let plural = if count == 1 { "" } else { "s" };
+        f.write_fmt(format_args!("formatted {} time{}", count, plural))
+    }))
 }
 
-/// Generates `f.field()` Formatter statement for `#[debug_stub = "..."]`
-fn implement_replace_attr(name: Option<String>, value: &str) -> Stmt {
-    if let Some(name) = name {
-        parse_quote!(f.field(#name, &format_args!("{}", #value));)
-    } else {
-        parse_quote!(f.field(&format_args!("{}", #value));)
+/// Returns the type of the value held by an atomic field type, like `u64` for `AtomicU64` or
+/// `*mut T` for `AtomicPtr<T>`, or `_` if it is not known
+fn atomic_value_type(ty: &Type) -> Type {
+    let name = short_type_name(ty);
+    match name.strip_prefix("Atomic") {
+        Some("Ptr") => {
+            let pointee = generic_type_arg(ty, 0);
+            parse_quote!(*mut #pointee)
+        }
+        Some(value)
+            if matches!(
+                value,
+                "Bool"
+                    | "I8"
+                    | "I16"
+                    | "I32"
+                    | "I64"
+                    | "Isize"
+                    | "U8"
+                    | "U16"
+                    | "U32"
+                    | "U64"
+                    | "Usize"
+            ) =>
+        {
+            let ident = Ident::new(&value.to_lowercase(), Span::call_site());
+            parse_quote!(#ident)
+        }
+        _ => parse_quote!(_),
     }
 }
 
-/// Generates `f.field()` Formatter statement for `#[debug_stub(some = "...")]`
-fn implement_some_attr(some: &str, name: Option<String>, expr: &Expr) -> Stmt {
-    if let Some(name) = name {
-        parse_quote! {
-            if #expr.is_some() {
-                f.field(#name, &Some::<_>(format_args!("{}", #some)));
-            } else {
-                f.field(#name, &format_args!("None"));
+/// Generates the field value for `#[debug_stub(weak)]`, printing whether an `rc::Weak` or
+/// `sync::Weak` can be upgraded, like `Weak(alive)` and `Weak(dangling)`. With a nested stub, the
+/// upgraded value is printed through it instead of `alive`
+fn implement_weak_attr(inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> Expr {
+    let (used, value) = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!((&*strong)), &generic_type_arg(ty, 0)),
+        None => (false, parse_quote!(format_args!("alive"))),
+    };
+    let strong = if used { quote!(strong) } else { quote!(_) };
+
+    implement_debug_with(quote! {
+        match #expr.upgrade() {
+            ::core::option::Option::Some(#strong) => f.debug_tuple("Weak").field(&#value).finish(),
+            ::core::option::Option::None => f
+                .debug_tuple("Weak")
+                .field(&format_args!("dangling"))
+                .finish(),
+        }
+    })
+}
+
+/// Generates the field value for `#[debug_stub(mutex)]`, `#[debug_stub(rwlock)]`, and
+/// `#[debug_stub(refcell)]`, printing the value behind a lock as is or through the nested stub if
+/// it can be locked without blocking (`Mutex(42)`), and `Mutex(<locked>)`, `RwLock(<write-locked>)`,
+/// or `RefCell(<mutably borrowed>)` otherwise. Poisoned values are printed as
+/// `Mutex(42, <poisoned>)`
+fn implement_lock_attr(lock: Lock, inner: Option<&FieldStub>, expr: &Expr, ty: &Type) -> Expr {
+    let (name, try_lock, locked) = match lock {
+        Lock::Mutex => ("Mutex", quote!(try_lock), "<locked>"),
+        Lock::RwLock => ("RwLock", quote!(try_read), "<write-locked>"),
+        Lock::RefCell => ("RefCell", quote!(try_borrow), "<mutably borrowed>"),
+    };
+    let (used, value) = match inner {
+        Some(inner) => implement_stub(inner, &parse_quote!((&*guard)), &generic_type_arg(ty, 0)),
+        None => (true, parse_quote!((&*guard))),
+    };
+    let guard = if used { quote!(guard) } else { quote!(_) };
+
+    // a failed borrow of a RefCell can't be poisoned
+    if let Lock::RefCell = lock {
+        return implement_debug_with(quote! {
+            match #expr.#try_lock() {
+                ::core::result::Result::Ok(#guard) => f.debug_tuple(#name).field(&#value).finish(),
+                ::core::result::Result::Err(_) => f
+                    .debug_tuple(#name)
+                    .field(&format_args!(#locked))
+                    .finish(),
+            }
+        });
+    }
+
+    implement_debug_with(quote! {
+        match #expr.#try_lock() {
+            ::core::result::Result::Ok(#guard) => f.debug_tuple(#name).field(&#value).finish(),
+            ::core::result::Result::Err(::std::sync::TryLockError::Poisoned(err)) => {
+                let #guard = err.into_inner();
+                f.debug_tuple(#name)
+                    .field(&#value)
+                    .field(&format_args!("<poisoned>"))
+                    .finish()
             }
+            ::core::result::Result::Err(::std::sync::TryLockError::WouldBlock) => f
+                .debug_tuple(#name)
+                .field(&format_args!(#locked))
+                .finish(),
         }
-    } else {
-        parse_quote! {
-            if #expr.is_some() {
-                f.field(&Some::<_>(format_args!("{}", #some)));
+    })
+}
+
+/// Generates the field value for `#[debug_stub(precision = N)]`, which prints `value` with the
+/// given precision, which the Debug impls of floats use as their number of decimals and the ones of
+/// std types and derived impls pass on to their fields
+fn implement_precision(value: &Expr, precision: usize) -> Expr {
+    let flags = implement_debug_flags(quote!(f), quote!(::core::option::Option::Some(#precision)));
+    let write = implement_write_debug(quote!(&mut *f), flags, quote!(#value));
+    implement_debug_with(write)
+}
+
+/// For keys which render `value` into text with a new formatter, like `max_len`, returns a
+/// statement capturing the precision of the call site and `value` printed with it, if it is to be
+/// passed on for `#[debug_stub(inherit_precision)]`
+fn implement_inherited_precision(
+    value: &Expr,
+    inherit_precision: bool,
+) -> (proc_macro2::TokenStream, Expr) {
+    if !inherit_precision {
+        return (quote!(), value.clone());
+    }
+
+    let flags = implement_debug_flags(quote!(f), quote!(precision));
+    let write = implement_write_debug(quote!(&mut *f), flags, quote!(#value));
+    let value = implement_debug_with(quote! {
+        match precision {
+            ::core::option::Option::Some(_) => #write,
+            ::core::option::Option::None => ::core::fmt::Debug::fmt(&#value, f),
+        }
+    });
+    (quote!(let precision = f.precision();), value)
+}
+
+/// Generates an expression capturing the flags of the formatter `f` which std passes on to the
+/// values it prints, as a tuple of whether alternate mode is on, the hex mode of `{:x?}` (1) or
+/// `{:X?}` (2), or 0 otherwise, the width, or 0 without one, and the given `Option<usize>`
+/// precision. The `+` and `0` flags are not captured, as every combination of flags needs its own
+/// format string in the generated code
+fn implement_debug_flags(
+    f: proc_macro2::TokenStream,
+    precision: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote!((
+        #f.alternate(),
+        {
+            // the hex flags have no stable accessor besides the raw flags
+            #[allow(deprecated)]
+            let flags = #f.flags();
+            if flags & 1 << 4 != 0 {
+                1_u8
+            } else if flags & 1 << 5 != 0 {
+                2_u8
             } else {
-                f.field(&format_args!("None"));
+                0_u8
             }
+        },
+        #f.width().unwrap_or(0),
+        #precision,
+    ))
+}
+
+/// Generates a `fmt::Write::write_fmt()` call on `writer` printing the Debug output of `value`
+/// with the flags captured by `implement_debug_flags`
+fn implement_write_debug(
+    writer: proc_macro2::TokenStream,
+    flags: proc_macro2::TokenStream,
+    value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mut arms = Vec::new();
+    for &alternate in &[false, true] {
+        for (hex, spec) in [(quote!(0), ""), (quote!(1), "x"), (quote!(_), "X")] {
+            // a width of 0 pads nothing, like none
+            let sign = if alternate { "#" } else { "" };
+            let format = format!("{{:{}1${}?}}", sign, spec);
+            let precise_format = format!("{{:{}1$.2${}?}}", sign, spec);
+            arms.push(quote! {
+                (#alternate, #hex, flags_width, ::core::option::Option::None) => {
+                    ::core::fmt::Write::write_fmt(
+                        #writer,
+                        format_args!(#format, #value, flags_width),
+                    )
+                }
+                (
+                    #alternate,
+                    #hex,
+                    flags_width,
+                    ::core::option::Option::Some(flags_precision),
+                ) => ::core::fmt::Write::write_fmt(
+                    #writer,
+                    format_args!(#precise_format, #value, flags_width, flags_precision),
+                ),
+            });
         }
     }
+    quote!(match #flags { #(#arms)* })
 }
 
-/// Generates `f.field()` Formatter statement for `#[debug_stub(ok = "...", err = "...")]`
-fn implement_result_attr(ok: &str, err: &str, name: Option<String>, expr: &Expr) -> Stmt {
-    if let Some(name) = name {
-        parse_quote! {
-            if #expr.is_ok() {
-                f.field(#name, &Ok::<_, ()>(format_args!("{}", #ok)));
-            } else {
-                f.field(#name, &Err::<(), _>(format_args!("{}", #err)));
+/// Generates the field value for `#[debug_stub(on_error = "...")]`, which renders the Debug output
+/// of `value` into a String, and writes the marker instead if that panics or returns an error.
+/// `catch_unwind` does not keep the panic hook from running, so caught panics are still reported
+/// on stderr; swapping the hook is left to the user, as it is process-wide state
+fn implement_on_error(value: &Expr, marker: &str, inherit_precision: bool) -> Expr {
+    let (precision, value) = implement_inherited_precision(value, inherit_precision);
+    let flags = implement_debug_flags(quote!(f), quote!(::core::option::Option::None::<usize>));
+    let write = implement_write_debug(quote!(&mut text), quote!(flags), quote!(#value));
+    implement_debug_with(quote!({
+        #precision
+        let flags = #flags;
+        let text = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            let mut text = ::std::string::String::new();
+            #write.map(|()| text)
+        }));
+        match text {
+            ::core::result::Result::Ok(::core::result::Result::Ok(text)) => f.write_str(&text),
+            _ => f.write_str(#marker),
+        }
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(spill(...))]`, which renders the Debug output of
+/// `value` into a String, and writes it to a new file instead if it is too long
+fn implement_spill(value: &Expr, spill: &Spill, inherit_precision: bool) -> Expr {
+    let (precision, value) = implement_inherited_precision(value, inherit_precision);
+    let flags = implement_debug_flags(quote!(f), quote!(::core::option::Option::None::<usize>));
+    let write = implement_write_debug(quote!(&mut text), flags, quote!(#value));
+    let dir = match &spill.dir {
+        Some(dir) => quote!(::std::path::PathBuf::from(#dir)),
+        None => quote!(::std::env::temp_dir()),
+    };
+    let over = spill.over;
+    implement_debug_with(quote!({
+        static SPILLED: ::core::sync::atomic::AtomicUsize = ::core::sync::atomic::AtomicUsize::new(0);
+
+        #precision
+        let mut text = ::std::string::String::new();
+        #write?;
+        if text.len() > #over {
+            // the counter is per field, so names taken by other fields or processes are skipped
+            let dir = #dir;
+            let start = SPILLED.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed);
+            for index in start.. {
+                let path = dir.join(::std::format!("dbg-{}-{}.txt", ::std::process::id(), index));
+                let file = ::std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&path);
+                match file {
+                    ::core::result::Result::Ok(mut file) => {
+                        if ::std::io::Write::write_all(&mut file, text.as_bytes()).is_ok() {
+                            return ::core::write!(f, "<spilled to {}>", path.display());
+                        }
+                        break;
+                    }
+                    ::core::result::Result::Err(error)
+                        if error.kind() == ::std::io::ErrorKind::AlreadyExists => {}
+                    ::core::result::Result::Err(_) => break,
+                }
             }
         }
+        f.write_str(&text)
+    }))
+}
+
+/// Generates the field value for a stub followed by text transforms, which renders the Debug output
+/// of `value` into a String and applies the transforms to it from left to right
+fn implement_pipeline(value: &Expr, transforms: &[Transform], inherit_precision: bool) -> Expr {
+    let (precision, value) = implement_inherited_precision(value, inherit_precision);
+    let flags = implement_debug_flags(quote!(f), quote!(::core::option::Option::None::<usize>));
+    let write = implement_write_debug(quote!(&mut text), flags, quote!(#value));
+    let transforms = transforms.iter().map(|transform| match transform {
+        Transform::MaxLen(max_len) => quote! {
+            if let ::core::option::Option::Some((end, _)) = text.char_indices().nth(#max_len) {
+                text.truncate(end);
+                text.push('…');
+            }
+        },
+        Transform::Mask {
+            keep_start,
+            keep_end,
+        } => quote! {
+            let count = text.chars().count();
+            text = text
+                .chars()
+                .enumerate()
+                .map(|(index, c)| {
+                    if index < #keep_start || index + #keep_end >= count {
+                        c
+                    } else {
+                        '*'
+                    }
+                })
+                .collect();
+        },
+    });
+
+    implement_debug_with(quote!({
+        #precision
+        let mut text = ::std::string::String::new();
+        #write?;
+        #(#transforms)*
+        f.write_str(&text)
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(display)]`, optionally escaping control characters
+/// for `#[debug_stub(display, sanitize)]`
+fn implement_display_attr(sanitize: bool, expr: &Expr) -> Expr {
+    if sanitize {
+        let sanitizer = implement_sanitizer();
+        implement_debug_with(quote!({
+            #sanitizer
+            ::core::fmt::Write::write_fmt(&mut Sanitizer(f), format_args!("{}", #expr))
+        }))
     } else {
-        parse_quote! {
-            if #expr.is_ok() {
-                f.field(&Ok::<_, ()>(format_args!("{}", #ok)));
-            } else {
-                f.field(&Err::<(), _>(format_args!("{}", #err)));
+        parse_quote!(format_args!("{}", #expr))
+    }
+}
+
+/// Generates the `Sanitizer` item, a `fmt::Write` adapter which escapes control characters
+/// (including the `ESC` starting ANSI sequences) before writing to the wrapped Formatter, so that
+/// untrusted text cannot inject fake log lines or terminal commands
+fn implement_sanitizer() -> proc_macro2::TokenStream {
+    quote! {
+        struct Sanitizer<'a, 'b>(&'a mut ::core::fmt::Formatter<'b>);
+
+        impl<'a, 'b> ::core::fmt::Write for Sanitizer<'a, 'b> {
+            fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+                for c in s.chars() {
+                    if c.is_control() {
+                        ::core::fmt::Display::fmt(&c.escape_default(), self.0)?;
+                    } else {
+                        ::core::fmt::Write::write_char(self.0, c)?;
+                    }
+                }
+                Ok(())
             }
         }
     }
 }
 
-/// Generates `f.field()` Formatter statement for `#[debug_stub(ok = "...")]`
-fn implement_ok_attr(ok: &str, name: Option<String>, expr: &Expr) -> Stmt {
-    if let Some(name) = name {
-        parse_quote! {
-            if #expr.is_err() {
-                f.field(#name, &Err::<(), _>(#expr.as_ref().err().unwrap()));
-            } else {
-                f.field(#name, &Ok::<_, ()>(format_args!("{}", #ok)));
+/// Generates the field value for `#[debug_stub(truncate = N)]`, printing strings longer than `N`
+/// characters as `"SELECT * FROM us…" (1234 chars)`
+fn implement_truncate_attr(chars: usize, expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        let s: &str = ::core::convert::AsRef::<str>::as_ref(#expr);
+        match s.char_indices().nth(#chars) {
+            Some((end, _)) => ::core::write!(
+                f,
+                "\"{}…\" ({} chars)",
+                s[..end].escape_debug(),
+                s.chars().count(),
+            ),
+            None => ::core::fmt::Debug::fmt(s, f),
+        }
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(peek = N)]`, printing strings longer than `2 * N`
+/// characters as `"AKIA…Z9Q2" (40 chars)`
+fn implement_peek_attr(chars: usize, expr: &Expr) -> Expr {
+    implement_debug_with(quote!({
+        let s: &str = ::core::convert::AsRef::<str>::as_ref(#expr);
+        let count = s.chars().count();
+        if count > 2 * #chars {
+            let start = s.char_indices().nth(#chars).map_or(s.len(), |(index, _)| index);
+            let end = s.char_indices().nth(count - #chars).map_or(s.len(), |(index, _)| index);
+            ::core::write!(
+                f,
+                "\"{}…{}\" ({} chars)",
+                s[..start].escape_debug(),
+                s[end..].escape_debug(),
+                count,
+            )
+        } else {
+            ::core::fmt::Debug::fmt(s, f)
+        }
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(utc)]`, converting a date time to UTC
+fn implement_utc_attr(krate: DateTimeCrate, expr: &Expr) -> Expr {
+    match krate {
+        DateTimeCrate::Chrono => {
+            parse_quote!(::chrono::DateTime::with_timezone(#expr, &::chrono::Utc))
+        }
+        DateTimeCrate::Time => {
+            parse_quote!(::time::OffsetDateTime::to_offset(*#expr, ::time::UtcOffset::UTC))
+        }
+    }
+}
+
+/// Generates the field value for `#[debug_stub(rfc3339)]`, printing a `SystemTime` as an RFC 3339
+/// timestamp in UTC, like `2024-05-01T12:34:56Z`, with as many fractional digits as needed. Times
+/// out of the range of the date time crate are printed as is
+fn implement_rfc3339_attr(krate: DateTimeCrate, expr: &Expr) -> Expr {
+    let parts = match krate {
+        DateTimeCrate::Chrono => quote! {
+            ::chrono::DateTime::from_timestamp(
+                nanos.div_euclid(1_000_000_000) as i64,
+                nanos.rem_euclid(1_000_000_000) as u32,
+            )
+            .map(|date_time| {
+                use ::chrono::{Datelike, Timelike};
+                (
+                    date_time.year(),
+                    date_time.month(),
+                    date_time.day(),
+                    date_time.hour(),
+                    date_time.minute(),
+                    date_time.second(),
+                    date_time.nanosecond(),
+                )
+            })
+        },
+        DateTimeCrate::Time => quote! {
+            ::time::OffsetDateTime::from_unix_timestamp_nanos(nanos)
+                .ok()
+                .map(|date_time| {
+                    (
+                        date_time.year(),
+                        u8::from(date_time.month()),
+                        date_time.day(),
+                        date_time.hour(),
+                        date_time.minute(),
+                        date_time.second(),
+                        date_time.nanosecond(),
+                    )
+                })
+        },
+    };
+
+    implement_debug_with(quote!({
+        let nanos = match #expr.duration_since(::std::time::UNIX_EPOCH) {
+            ::core::result::Result::Ok(since) => since.as_nanos() as i128,
+            ::core::result::Result::Err(err) => -(err.duration().as_nanos() as i128),
+        };
+        let (year, month, day, hour, minute, second, mut fraction) = match #parts {
+            ::core::option::Option::Some(parts) => parts,
+            ::core::option::Option::None => return ::core::fmt::Debug::fmt(#expr, f),
+        };
+        f.write_fmt(format_args!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, hour, minute, second,
+        ))?;
+        if fraction > 0 {
+            let mut digits = 9;
+            while fraction % 10 == 0 {
+                digits -= 1;
+                fraction /= 10;
             }
+            f.write_fmt(format_args!(".{:0digits$}", fraction, digits = digits))?;
         }
-    } else {
-        parse_quote! {
-            if #expr.is_err() {
-                f.field(&Err::<(), _>(#expr.as_ref().err().unwrap()));
-            } else {
-                f.field(&Ok::<_, ()>(format_args!("{}", #ok)));
+        f.write_str("Z")
+    }))
+}
+
+/// Generates the field value for `#[debug_stub(be)]` and `#[debug_stub(le)]`, printing both the
+/// raw integer and its value in the given byte order (`0x0100 (be: 1)`)
+fn implement_byte_order_attr(order: ByteOrder, expr: &Expr, ty: &Type) -> Expr {
+    let (label, convert) = match order {
+        ByteOrder::Big => ("be", quote!(from_be)),
+        ByteOrder::Little => ("le", quote!(from_le)),
+    };
+    let template = format!("{{:#0width$x}} ({}: {{}})", label);
+
+    parse_quote! {
+        format_args!(
+            #template,
+            *#expr,
+            <#ty>::#convert(*#expr),
+            width = ::core::mem::size_of::<#ty>() * 2 + 2,
+        )
+    }
+}
+
+/// Kind of collection held by a field, which determines how its elements are printed
+#[derive(Clone, Copy)]
+enum CollectionKind {
+    /// Sequences like `Vec` or slices, printed as `[a, b]`
+    List,
+    /// Sets like `HashSet`, printed as `{a, b}`
+    Set,
+    /// Maps like `HashMap`, printed as `{k: v}`
+    Map,
+}
+
+/// Guesses the collection kind of a field from the name of its type
+fn collection_kind(ty: &Type) -> CollectionKind {
+    match short_type_name(&collection_type(ty)).as_str() {
+        "HashMap" | "BTreeMap" | "IndexMap" => CollectionKind::Map,
+        "HashSet" | "BTreeSet" | "IndexSet" => CollectionKind::Set,
+        _ => CollectionKind::List,
+    }
+}
+
+/// Generates a Debug value printing only the first `head` and the last `tail` elements of a
+/// collection, with an `… and N more entries` marker counting the elided ones in between
+fn implement_elided_collection(
+    kind: CollectionKind,
+    expr: &Expr,
+    head: usize,
+    tail: usize,
+) -> Expr {
+    let builder = match kind {
+        CollectionKind::List => quote!(f.debug_list()),
+        // maps are printed as sets of `key: value` entries, which renders identically to
+        // `debug_map()` but allows the marker to be a single entry
+        CollectionKind::Set | CollectionKind::Map => quote!(f.debug_set()),
+    };
+    let entries = |iter: proc_macro2::TokenStream| match kind {
+        CollectionKind::List | CollectionKind::Set => quote!(builder.entries(#iter);),
+        CollectionKind::Map => {
+            let entry = implement_debug_with(quote!({
+                ::core::fmt::Debug::fmt(key, f)?;
+                f.write_str(": ")?;
+                ::core::fmt::Debug::fmt(value, f)
+            }));
+            quote! {
+                for (key, value) in #iter {
+                    builder.entry(&#entry);
+                }
             }
         }
+    };
+    let head_entries = entries(quote!(iter.by_ref().take(#head)));
+    let tail_entries = entries(quote!(iter));
+
+    implement_debug_with(quote!({
+        let more = #expr.iter().count().saturating_sub(#head + #tail);
+        let mut iter = #expr.iter();
+        let mut builder = #builder;
+        #head_entries
+        if more > 0 {
+            builder.entry(&format_args!("… and {} more entries", more));
+            iter.by_ref().nth(more - 1);
+        }
+        #tail_entries
+        builder.finish()
+    }))
+}
+
+/// Returns the `index`th generic type argument of a field type, like `u8` for the first one of
+/// `Option<u8>`, or `_` if it is not written out
+fn generic_type_arg(ty: &Type, index: usize) -> Type {
+    match ty {
+        Type::Path(TypePath { path, .. }) => path
+            .segments
+            .last()
+            .and_then(|segment| match &segment.arguments {
+                PathArguments::AngleBracketed(arguments) => arguments
+                    .args
+                    .iter()
+                    .filter_map(|argument| match argument {
+                        GenericArgument::Type(ty) => Some(ty.clone()),
+                        _ => None,
+                    })
+                    .nth(index),
+                _ => None,
+            })
+            .unwrap_or_else(|| parse_quote!(_)),
+        Type::Reference(TypeReference { elem, .. })
+        | Type::Paren(TypeParen { elem, .. })
+        | Type::Group(TypeGroup { elem, .. }) => generic_type_arg(elem, index),
+        _ => parse_quote!(_),
     }
 }
 
-/// Generates `f.field()` Formatter statement for `#[debug_stub(err = "...")]`
-fn implement_err_attr(err: &str, name: Option<String>, expr: &Expr) -> Stmt {
-    if let Some(name) = name {
-        parse_quote! {
-            if #expr.is_ok() {
-                f.field(#name, &Ok::<_, ()>(#expr.as_ref().ok().unwrap()));
-            } else {
-                f.field(#name, &Err::<(), _>(format_args!("{}", #err)));
+/// Returns the element type of a sequence or set field type, like `u8` for `Vec<u8>`, `[u8; 4]`,
+/// `&[u8]`, or `Box<[u8]>`, or `_` if it is not written out
+fn element_type(ty: &Type) -> Type {
+    match ty {
+        Type::Slice(TypeSlice { elem, .. }) | Type::Array(TypeArray { elem, .. }) => {
+            (**elem).clone()
+        }
+        Type::Reference(TypeReference { elem, .. })
+        | Type::Paren(TypeParen { elem, .. })
+        | Type::Group(TypeGroup { elem, .. }) => element_type(elem),
+        _ => match short_type_name(ty).as_str() {
+            "Box" | "Rc" | "Arc" => element_type(&generic_type_arg(ty, 0)),
+            _ => generic_type_arg(ty, 0),
+        },
+    }
+}
+
+/// Returns the collection type behind a `Box`, `Rc`, or `Arc` field type, like `[u8]` for
+/// `Box<[u8]>` or `Vec<u8>` for `Arc<Vec<u8>>`, or the field type itself otherwise
+fn collection_type(ty: &Type) -> Type {
+    match short_type_name(ty).as_str() {
+        "Box" | "Rc" | "Arc" => collection_type(&generic_type_arg(ty, 0)),
+        _ => ty.clone(),
+    }
+}
+
+/// Returns the type a field type dereferences to, like `str` for `String` or `T` for `Box<T>`, or
+/// `_` if it is not known
+fn deref_target(ty: &Type) -> Type {
+    match short_type_name(ty).as_str() {
+        "String" => parse_quote!(str),
+        "PathBuf" => parse_quote!(::std::path::Path),
+        "OsString" => parse_quote!(::std::ffi::OsStr),
+        "CString" => parse_quote!(::std::ffi::CStr),
+        "Vec" => {
+            let elem = generic_type_arg(ty, 0);
+            parse_quote!([#elem])
+        }
+        "Box" | "Rc" | "Arc" => generic_type_arg(ty, 0),
+        _ => parse_quote!(_),
+    }
+}
+
+/// Returns the short name of a field type, like `Vec` for `alloc::vec::Vec<u8>`
+fn short_type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(TypePath { path, .. }) => path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        Type::Reference(TypeReference { elem, .. })
+        | Type::Paren(TypeParen { elem, .. })
+        | Type::Group(TypeGroup { elem, .. }) => short_type_name(elem),
+        Type::Slice(_) => "Slice".to_string(),
+        Type::Array(_) => "Array".to_string(),
+        _ => ty.to_token_stream().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::{TokenStream, TokenTree};
+
+    /// Returns whether a token stream contains the `unsafe` keyword at any depth
+    fn contains_unsafe(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "unsafe",
+            TokenTree::Group(group) => contains_unsafe(group.stream()),
+            _ => false,
+        })
+    }
+
+    /// Expands `#[derive(DebugStub)]` for a struct and an enum with a single field of the given
+    /// type and attribute
+    fn expand_field(attr: &str, ty: &str) -> Vec<TokenStream> {
+        let inputs = [
+            format!("struct S {{ #[debug_stub({})] a: {}, }}", attr, ty),
+            format!("enum E {{ A(#[debug_stub({})] {}), }}", attr, ty),
+        ];
+        inputs
+            .iter()
+            .map(|input| {
+                let ast = syn::parse_str::<DeriveInput>(input).unwrap();
+                expand_derive_serialize(&ast).unwrap_or_else(|err| panic!("{}: {}", input, err))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expansions_without_unsafe() {
+        let mut fields = vec![
+            ("ok = \"Ok\", err = \"Err\"", "Result<u8, u8>"),
+            ("some = \"Some\", none = \"None\"", "Option<u8>"),
+            ("key = \"K\", value(len)", "HashMap<u8, Vec<u8>>"),
+            ("each = \"x\"", "Vec<u8>"),
+            ("set(hash)", "HashSet<u8>"),
+            ("mutex", "Mutex<u8>"),
+            ("rwlock(len)", "RwLock<Vec<u8>>"),
+            ("refcell", "RefCell<u8>"),
+            ("cell", "Cell<u8>"),
+            ("uninit", "MaybeUninit<u8>"),
+            ("atomic", "AtomicU8"),
+            ("once", "OnceCell<u8>"),
+            ("cow", "Cow<'static, str>"),
+            ("weak", "Weak<u8>"),
+            ("counts", "Rc<u8>"),
+            ("boxed(len)", "Box<Vec<u8>>"),
+            ("mask(keep_end = 4)", "String"),
+            ("bits(format = \"vvvv\")", "u8"),
+            ("any_of(u8), else = \"other\"", "T"),
+            ("display, sanitize, max_len = 3", "String"),
+            ("head = 1, tail = 1", "Vec<u8>"),
+            ("ptr, addr_style = \"full\"", "*const u8"),
+            ("precision = 2, on_error = \"?\"", "f64"),
+            ("spill(over = 2)", "String"),
+            ("release_only = \"<secret>\"", "String"),
+            ("if = \"is_external\", then(truncate = 2)", "String"),
+            ("separators", "u64"),
+            ("future_state = \"is_done\"", "Fut"),
+            ("resolve = \"lookup\"", "u64"),
+            ("method = \"id\"", "Conn"),
+            ("getter = \"stats\"", "Pool"),
+            ("deref_to = \"str\"", "String"),
+            ("as_deref, some(len)", "Option<Vec<u8>>"),
+            ("len", "Vec<u8>"),
+            ("type_name", "u8"),
+            ("shallow", "Option<u8>"),
+            ("scientific", "f64"),
+            ("hash", "u8"),
+            ("limit = 2", "Vec<u8>"),
+            ("display", "String"),
+            ("truncate = 2", "String"),
+            ("peek = 2", "String"),
+            ("first = 2", "Vec<u8>"),
+            ("be", "u32"),
+            ("crc32", "Vec<u8>"),
+            ("duration", "Duration"),
+            ("bytes", "u64"),
+            ("variant_counts", "Vec<E>"),
+            ("path", "PathBuf"),
+            ("c_str", "CString"),
+            ("elapsed", "Instant"),
+            ("expr = \"self.a.len()\"", "Vec<u8>"),
+            ("with = |v, f| write!(f, \"{}\", v)", "u8"),
+            ("with = \"fmt_flag\", max_len = 3", "bool"),
+            ("wrap = \"Adapter\"", "Foreign"),
+            ("trait", "Foreign"),
+            ("trait = \"path::to::StubDebug\"", "Foreign"),
+            ("via = \"Summary\"", "Handle"),
+            ("const = \"MARKER\"", "u8"),
+            ("len, alternate(limit = 2)", "Vec<u8>"),
+            ("as_inner", "Wrapper"),
+            ("as_inner, inner = \"u64\"", "Wrapper"),
+            ("counter", "Cell<u32>"),
+            ("scientific = \"e\"", "f64"),
+            ("inherit_precision, max_len = 3", "f64"),
+            ("if = \"is_external\", then = \"x\", else_debug", "String"),
+            (
+                "ok = \"Ok\", err_max_len = 4, err_display",
+                "Result<u8, String>",
+            ),
+            ("filter_keys = \"a\"", "HashMap<String, u8>"),
+            ("filter_keys_with = \"keep\"", "HashMap<String, u8>"),
+            ("le", "u32"),
+            ("opaque", "Conn"),
+            ("rc(len)", "Rc<Vec<u8>>"),
+            ("arc = \"x\"", "Arc<u8>"),
+        ];
+        if cfg!(feature = "serde_json") {
+            fields.push(("json(redact(\"password\"))", "Value"));
+        }
+        if cfg!(feature = "sha256") {
+            fields.push(("sha256", "Vec<u8>"));
+        }
+        if cfg!(feature = "chrono") {
+            fields.push(("utc = \"chrono\"", "DateTime<Local>"));
+            fields.push(("rfc3339 = \"chrono\"", "SystemTime"));
+        }
+
+        for (attr, ty) in fields.iter() {
+            for expanded in expand_field(attr, ty) {
+                assert!(
+                    !contains_unsafe(expanded),
+                    "`{}` generated unsafe code",
+                    attr
+                );
             }
         }
-    } else {
-        parse_quote! {
-            if #expr.is_ok() {
-                f.field(&Ok::<_, ()>(#expr.as_ref().ok().unwrap()));
-            } else {
-                f.field(&Err::<(), _>(format_args!("{}", #err)));
+    }
+
+    #[test]
+    fn test_container_expansions_without_unsafe() {
+        let mut options = vec![
+            "stable_layout",
+            "dedup_key",
+            "fingerprint",
+            "explain",
+            "debug_fields",
+            "mark_used",
+            "skip_none",
+            "max_output = 20",
+            "redactor = \"redact\"",
+            "trait = \"AltDebug\"",
+            "ignore_generics, bound = \"T: Debug\"",
+            "types(\"Handle\" = \"<handle>\")",
+            "pure",
+            "strict",
+        ];
+        if cfg!(feature = "dot") {
+            options.push("dot");
+        }
+
+        for options in options {
+            let inputs = [
+                format!(
+                    "#[debug_stub({})] struct S<T> {{ #[debug_stub(brief)] a: T, b: Option<u8>, \
+                     c: Handle, }}",
+                    options
+                ),
+                format!(
+                    "#[debug_stub({}, variant_index)] enum E<T> {{ A(#[debug_stub(brief)] T), \
+                     B {{ b: Option<u8> }}, C, }}",
+                    options
+                ),
+            ];
+            for input in inputs.iter() {
+                let ast = syn::parse_str::<DeriveInput>(input).unwrap();
+                let expanded = expand_derive_serialize(&ast)
+                    .unwrap_or_else(|err| panic!("{}: {}", input, err));
+                assert!(
+                    !contains_unsafe(expanded),
+                    "`{}` generated unsafe code",
+                    options
+                );
             }
         }
     }
+
+    #[test]
+    fn test_expansions_with_unsafe_options() {
+        for expanded in expand_field("unsafe_assume_init(len)", "MaybeUninit<Vec<u8>>") {
+            assert!(contains_unsafe(expanded));
+        }
+    }
+
+    #[test]
+    fn test_replacement_placeholders() {
+        let describe =
+            |text: &str| parse_replacement(&LitStr::new(text, Span::call_site())).describe();
+
+        assert_eq!(describe("plain"), "replace = \"plain\"");
+        assert_eq!(describe("{{x}}"), "replace = \"{{x}}\"");
+        assert_eq!(
+            describe("<{type}, {len} at {ptr}>"),
+            "replace = \"<{type}, {len} at {ptr}>\""
+        );
+        assert_eq!(describe("{{len}}"), "replace = \"{{len}}\"");
+        assert_eq!(describe("{size}"), "replace = \"{size}\"");
+        assert_eq!(describe("{len"), "replace = \"{len\"");
+        assert_eq!(describe("len}"), "replace = \"len}\"");
+    }
+
+    #[test]
+    fn test_inline_with_rejects_unknown_keys() {
+        let expand = |input: &str| {
+            let ast = syn::parse_str::<DeriveInput>(input).unwrap();
+            expand_derive_serialize(&ast).map_err(|err| err.to_string())
+        };
+
+        assert!(
+            expand("struct S { #[debug_stub(with = |v, f| Ok(()), priority = 1)] a: u8 }").is_ok()
+        );
+        assert_eq!(
+            expand("struct S { #[debug_stub(with = |v, f| Ok(()), bogus = 1)] a: u8 }")
+                .unwrap_err(),
+            "unexpected `debug_stub` key"
+        );
+        assert_eq!(
+            expand("struct S { #[debug_stub(with = |v, f| Ok(()), len)] a: Vec<u8> }").unwrap_err(),
+            "conflicting `debug_stub` keys"
+        );
+    }
+
+    #[test]
+    fn test_clone_lint() {
+        let expand = |input: &str| {
+            let ast = syn::parse_str::<DeriveInput>(input).unwrap();
+            expand_derive_serialize(&ast)
+                .map(|tokens| tokens.to_string())
+                .map_err(|err| err.to_string())
+        };
+
+        let expanded = expand("struct S { #[debug_stub(expr = \"self.a.len()\")] a: Vec<u8> }");
+        assert!(!expanded.unwrap().contains("debug_stub_clone"));
+        let expanded = expand(
+            "struct S { #[debug_stub(with = |v, f| write!(f, \"{:?}\", v.clone()))] a: u8 }",
+        );
+        assert!(expanded.unwrap().contains("debug_stub_clone"));
+        let expanded = expand(
+            "struct S { #[debug_stub(some(with = \"|v, f| g(v.clone(), f)\"))] a: Option<u8> }",
+        );
+        assert!(expanded.unwrap().contains("debug_stub_clone"));
+        assert_eq!(
+            expand(
+                "#[debug_stub(strict)] struct S { #[debug_stub(expr = \"self.a.clone()\")] a: u8 }"
+            )
+            .unwrap_err(),
+            "`expr` clones the field on every format, which `strict` does not allow"
+        );
+    }
+
+    #[test]
+    fn test_pure_rejects_side_effects() {
+        let expand = |input: &str| {
+            let ast = syn::parse_str::<DeriveInput>(input).unwrap();
+            expand_derive_serialize(&ast).map_err(|err| err.to_string())
+        };
+
+        assert!(expand("#[debug_stub(pure)] struct S { #[debug_stub(len)] a: Vec<u8> }").is_ok());
+        assert_eq!(
+            expand("#[debug_stub(pure, redactor = \"redact\")] struct S { a: u8 }").unwrap_err(),
+            "`redactor` has side effects, which `pure` does not allow"
+        );
+        assert_eq!(
+            expand(
+                "#[debug_stub(pure)] struct S { #[debug_stub(if = \"p\", then = \"x\")] a: u8 }"
+            )
+            .unwrap_err(),
+            "`if` has side effects, which `pure` does not allow"
+        );
+        assert!(
+            expand("#[debug_stub(pure)] struct S { #[debug_stub(skip_none)] a: Option<u8> }")
+                .is_ok()
+        );
+        assert_eq!(
+            expand("#[debug_stub(pure)] struct S { #[debug_stub(skip_if = \"p\")] a: u8 }")
+                .unwrap_err(),
+            "`skip_if` has side effects, which `pure` does not allow"
+        );
+    }
 }
@@ -0,0 +1,7 @@
+//! Runs the edition tests in a crate on the 2021 edition.
+
+extern crate debug_stub_derive;
+
+#[cfg(test)]
+#[path = "../../tests.rs"]
+mod tests;
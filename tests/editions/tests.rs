@@ -0,0 +1,109 @@
+//! Tests shared by the crates on each edition, which check that the generated code compiles and
+//! behaves the same regardless of the edition of the deriving crate, whose paths, keywords, and
+//! macro resolution differ.
+
+use debug_stub_derive::DebugStub;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub struct StructWithoutDebug;
+
+fn fmt_flag(value: &bool, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(if *value { "on" } else { "off" })
+}
+
+#[test]
+fn test_struct_edition() {
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used, explain, fingerprint, dedup_key, debug_fields)]
+    struct TestStruct<T> {
+        a: T,
+        #[debug_stub = "Replaced"]
+        b: StructWithoutDebug,
+        #[debug_stub(some = "Some", none = "None")]
+        c: Option<u8>,
+        #[debug_stub(max_len = 3, on_error = "?")]
+        d: String,
+        #[debug_stub(key = "K", value(len))]
+        e: HashMap<u8, Vec<u8>>,
+        #[debug_stub(any_of(u8), else = "other")]
+        f: u8,
+        #[debug_stub(mutex, precision = 1)]
+        g: Mutex<f64>,
+        #[debug_stub(refcell(each = "x"))]
+        h: RefCell<Vec<u8>>,
+        #[debug_stub(counts)]
+        i: Rc<u8>,
+        #[debug_stub(duration)]
+        j: Duration,
+        #[debug_stub(with = "fmt_flag")]
+        k: bool,
+        #[debug_stub(with = |v, f| write!(f, "{} items", v.len()))]
+        l: Vec<u8>,
+        #[allow(dead_code)]
+        #[debug_stub(expr = "self.l.len() * 2")]
+        m: (),
+        #[debug_stub(display, sanitize)]
+        n: String,
+        #[debug_stub(separators, inherit_precision, mask(keep_start = 2))]
+        o: u32,
+    }
+
+    let s = TestStruct {
+        a: 1,
+        b: StructWithoutDebug,
+        c: None,
+        d: "abcdef".to_string(),
+        e: HashMap::new(),
+        f: 3,
+        g: Mutex::new(0.25),
+        h: RefCell::new(vec![1]),
+        i: Rc::new(4),
+        j: Duration::from_millis(1500),
+        k: true,
+        l: vec![1, 2],
+        m: (),
+        n: "a\nb".to_string(),
+        o: 12345,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 1, b: Replaced, c: None, d: \"ab…, e: {}, f: 3, g: Mutex(0.2), \
+         h: RefCell([x]), i: Rc(strong = 1, weak = 0, 4), j: 1.5s, k: on, l: 2 items, m: 4, \
+         n: a\\nb, o: 12**** }"
+    );
+    assert_eq!(s.debug_dedup_key(), s.debug_dedup_key());
+}
+
+#[test]
+fn test_enum_edition() {
+    #[derive(DebugStub)]
+    #[debug_stub(stable_layout, variant_index)]
+    enum TestEnum {
+        VariantA(#[debug_stub(ok = "Ok")] Result<u8, u8>),
+        VariantB {
+            #[debug_stub(expr = "a + 1")]
+            a: u8,
+        },
+        VariantC,
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Err(2))),
+        "VariantA(Err(2))"
+    );
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantB { a: 1 }),
+        "VariantB {\n    a: 2,\n}"
+    );
+    assert_eq!(format!("{:?}", TestEnum::VariantC), "VariantC");
+    assert_eq!(
+        TestEnum::DEBUG_STUB_VARIANTS,
+        &["VariantA", "VariantB", "VariantC"]
+    );
+}
@@ -1,6 +1,6 @@
 // Crate Dependencies ---------------------------------------------------------
 use debug_stub_derive::DebugStub;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
 struct StructWithoutDebug;
 
@@ -43,6 +43,7 @@ fn test_struct_compare_std_empty() {
 
 #[test]
 fn test_struct() {
+    #[allow(dead_code)]
     #[derive(Debug)]
     struct StructWithDebug {
         number: u64,
@@ -117,6 +118,7 @@ fn test_struct_compare_std() {
     mod a {
         use debug_stub_derive::DebugStub;
 
+        #[allow(dead_code)]
         #[derive(Debug)]
         pub struct InternalStruct {
             pub a: bool,
@@ -130,11 +132,13 @@ fn test_struct_compare_std() {
     }
 
     mod b {
+        #[allow(dead_code)]
         #[derive(Debug)]
         pub struct InternalStruct {
             pub a: bool,
         }
 
+        #[allow(dead_code)]
         #[derive(Debug)]
         pub struct TestStruct {
             pub a: InternalStruct,
@@ -397,6 +401,7 @@ fn test_struct_optional_compare_std() {
         #[derive(Debug)]
         pub struct Internal;
 
+        #[allow(dead_code)]
         #[derive(Debug)]
         pub struct TestStruct {
             pub a: Option<String>,
@@ -444,6 +449,7 @@ fn test_struct_result_compare_std() {
         #[derive(Debug)]
         pub struct Internal;
 
+        #[allow(dead_code)]
         #[derive(Debug)]
         pub struct TestStruct {
             pub a: Result<String, bool>,
@@ -515,532 +521,5628 @@ fn test_struct_generic() {
     assert_eq!(format!("{:?}", A(5)), "A(5)");
     assert_eq!(
         format!("{:?}", B(PhantomData::<StructWithoutDebug>)),
-        "B(PhantomData)"
+        format!("B({:?})", PhantomData::<StructWithoutDebug>)
     );
 }
 
-// Enum Tests -----------------------------------------------------------------
-
-#[test]
-fn test_enum_empty() {
-    #[derive(DebugStub)]
-    enum TestEnum {
-        VariantC {},
-        VariantD,
-    }
-
-    assert_eq!(format!("{:?}", TestEnum::VariantC {}), "VariantC");
-
-    assert_eq!(format!("{:?}", TestEnum::VariantC {}), "VariantC");
-
-    assert_eq!(format!("{:#?}", TestEnum::VariantD), "VariantD");
-
-    assert_eq!(format!("{:#?}", TestEnum::VariantD), "VariantD");
-}
-
 #[test]
-fn test_enum_compare_std_empty() {
-    mod a {
-        use debug_stub_derive::DebugStub;
+fn test_struct_future_state() {
+    struct FutureWithoutDebug;
 
-        #[derive(DebugStub)]
-        pub enum TestEnum {
-            VariantA,
-            VariantB {},
-        }
+    #[derive(DebugStub)]
+    struct TestStruct {
+        done: bool,
+        #[debug_stub(future_state = "TestStruct::is_done")]
+        future: Option<FutureWithoutDebug>,
     }
 
-    mod b {
-        #[derive(Debug)]
-        pub enum TestEnum {
-            VariantA,
-            VariantB {},
+    impl TestStruct {
+        fn is_done(&self) -> bool {
+            self.done
         }
     }
 
     assert_eq!(
-        format!("{:?}", a::TestEnum::VariantA),
-        format!("{:?}", b::TestEnum::VariantA)
-    );
-
-    assert_eq!(
-        format!("{:#?}", a::TestEnum::VariantA),
-        format!("{:#?}", b::TestEnum::VariantA)
+        format!(
+            "{:?}",
+            TestStruct {
+                done: false,
+                future: None
+            }
+        ),
+        "TestStruct { done: false, future: NotStarted }"
     );
 
     assert_eq!(
-        format!("{:?}", a::TestEnum::VariantB {}),
-        format!("{:?}", b::TestEnum::VariantB {})
+        format!(
+            "{:?}",
+            TestStruct {
+                done: false,
+                future: Some(FutureWithoutDebug)
+            }
+        ),
+        "TestStruct { done: false, future: Pending }"
     );
 
     assert_eq!(
-        format!("{:#?}", a::TestEnum::VariantB {}),
-        format!("{:#?}", b::TestEnum::VariantB {})
+        format!(
+            "{:?}",
+            TestStruct {
+                done: true,
+                future: None
+            }
+        ),
+        "TestStruct { done: true, future: Done }"
     );
 }
 
+#[cfg(feature = "dot")]
 #[test]
-fn test_enum() {
-    #[derive(Debug)]
-    struct StructWithDebug {
-        number: u64,
-    }
-
+fn test_struct_dot() {
     #[derive(DebugStub)]
-    enum TestEnum {
-        VariantA(
-            StructWithDebug,
-            #[debug_stub = "StructWithoutDebugReplaceValue"] StructWithoutDebug,
-            bool,
-        ),
-        VariantB {
-            a: StructWithDebug,
-            #[allow(dead_code)]
-            #[debug_stub = "StructWithoutDebugReplaceValue"]
-            b: StructWithoutDebug,
-            c: bool,
-        },
+    #[debug_stub(dot)]
+    struct TestStruct {
+        value: bool,
+        #[allow(dead_code)]
+        #[debug_stub = "StructWithoutDebugReplaceValue"]
+        b: StructWithoutDebug,
+        s: &'static str,
     }
 
     assert_eq!(
-        format!(
-            "{:?}",
-            TestEnum::VariantA(StructWithDebug { number: 42 }, StructWithoutDebug, true)
-        ),
-        "VariantA(StructWithDebug { number: 42 }, StructWithoutDebugReplaceValue, true)"
-    );
-
-    assert_eq!(
-        format!("{:?}", TestEnum::VariantB {
-            a: StructWithDebug {
-                number: 42
-            },
+        TestStruct {
+            value: true,
             b: StructWithoutDebug,
-            c: true
-
-        }), "VariantB { a: StructWithDebug { number: 42 }, b: StructWithoutDebugReplaceValue, c: true }"
+            s: "\"quoted\"",
+        }
+        .to_dot(),
+        r#"digraph {
+    node0 [label="TestStruct"];
+    node1 [label="true"];
+    node0 -> node1 [label="value"];
+    node2 [label="StructWithoutDebugReplaceValue"];
+    node0 -> node2 [label="b"];
+    node3 [label="\"\\\"quoted\\\"\""];
+    node0 -> node3 [label="s"];
+}
+"#
     );
 }
 
 #[test]
-fn test_enum_dyn_fields() {
-    trait Trait: Debug {}
-
-    #[derive(Debug)]
-    struct TraitImpl;
-
-    impl Trait for TraitImpl {}
+fn test_struct_len() {
+    use std::collections::HashMap;
 
     #[derive(DebugStub)]
-    enum Enum<'a> {
-        A(
-            Box<dyn Trait>,
-            #[allow(dead_code)]
-            #[debug_stub = "_"]
-            Box<dyn Trait>,
-            &'a dyn Trait,
-            #[allow(dead_code)]
-            #[debug_stub = "_"]
-            &'a dyn Trait,
-        ),
-        B {
-            a: Box<dyn Trait>,
-            #[allow(dead_code)]
-            #[debug_stub = "_"]
-            b: Box<dyn Trait>,
-            c: &'a dyn Trait,
-            #[allow(dead_code)]
-            #[debug_stub = "_"]
-            d: &'a dyn Trait,
-        },
+    struct TestStruct<'a> {
+        #[debug_stub(len)]
+        a: Vec<StructWithoutDebug>,
+        #[debug_stub(len)]
+        b: String,
+        #[debug_stub(len)]
+        c: &'a [u8],
+        #[debug_stub(len)]
+        d: HashMap<u8, StructWithoutDebug>,
     }
 
-    let trait_impl = TraitImpl;
-
-    assert_eq!(
-        format!(
-            "{:?}",
-            Enum::A(
-                Box::new(TraitImpl),
-                Box::new(TraitImpl),
-                &trait_impl,
-                &trait_impl,
-            ),
-        ),
-        "A(TraitImpl, _, TraitImpl, _)",
-    );
+    let s = TestStruct {
+        a: vec![StructWithoutDebug, StructWithoutDebug],
+        b: "Foo".to_string(),
+        c: &[1, 2, 3, 4],
+        d: HashMap::new(),
+    };
 
     assert_eq!(
-        format!(
-            "{:?}",
-            Enum::B {
-                a: Box::new(TraitImpl),
-                b: Box::new(TraitImpl),
-                c: &trait_impl,
-                d: &trait_impl,
-            },
-        ),
-        "B { a: TraitImpl, b: _, c: TraitImpl, d: _ }",
+        format!("{:?}", s),
+        "TestStruct { a: Vec(len = 2), b: String(len = 3), c: Slice(len = 4), d: HashMap(len = 0) }"
     );
 }
 
 #[test]
-fn test_enum_compare_std() {
-    mod a {
-        use debug_stub_derive::DebugStub;
-
-        #[derive(Debug)]
-        pub struct InternalStruct {
-            pub a: bool,
-        }
-
-        #[derive(DebugStub)]
-        pub enum TestEnum {
-            VariantA(InternalStruct, bool, u64),
-            VariantB { a: InternalStruct, b: bool, c: u64 },
-        }
-    }
-
-    mod b {
-        #[derive(Debug)]
-        pub struct InternalStruct {
-            pub a: bool,
-        }
+fn test_struct_smart_pointer_collections() {
+    use std::collections::{HashMap, HashSet};
+    use std::rc::Rc;
+    use std::sync::Arc;
 
-        #[derive(Debug)]
-        pub enum TestEnum {
-            VariantA(InternalStruct, bool, u64),
-            VariantB { a: InternalStruct, b: bool, c: u64 },
-        }
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(len)]
+        a: Box<[u8]>,
+        #[debug_stub(head = 1, tail = 1)]
+        b: Rc<[u8]>,
+        #[debug_stub(each = "X")]
+        c: Arc<Vec<StructWithoutDebug>>,
+        #[debug_stub(each(some = "Y"))]
+        d: Box<[Option<StructWithoutDebug>]>,
+        #[debug_stub(limit = 1)]
+        e: Arc<HashSet<u8>>,
+        #[debug_stub(value(len))]
+        f: Rc<HashMap<u8, Vec<u8>>>,
+        #[debug_stub(shallow)]
+        g: Box<str>,
     }
 
-    let enum_a_a = a::TestEnum::VariantA(a::InternalStruct { a: true }, false, 42);
-
-    let enum_a_b = a::TestEnum::VariantB {
-        a: a::InternalStruct { a: true },
-        b: false,
-        c: 42,
-    };
-
-    let enum_b_a = b::TestEnum::VariantA(b::InternalStruct { a: true }, false, 42);
-
-    let enum_b_b = b::TestEnum::VariantB {
-        a: b::InternalStruct { a: true },
-        b: false,
-        c: 42,
+    let s = TestStruct {
+        a: vec![1, 2, 3].into_boxed_slice(),
+        b: Rc::from(vec![1, 2, 3, 4]),
+        c: Arc::new(vec![StructWithoutDebug, StructWithoutDebug]),
+        d: vec![Some(StructWithoutDebug), None].into_boxed_slice(),
+        e: Arc::new([7].iter().copied().collect()),
+        f: Rc::new([(1, vec![1, 2])].iter().cloned().collect()),
+        g: "text".into(),
     };
 
-    assert_eq!(format!("{:?}", enum_a_a), format!("{:?}", enum_b_a));
-
-    assert_eq!(format!("{:#?}", enum_a_a), format!("{:#?}", enum_b_a));
-
-    assert_eq!(format!("{:?}", enum_a_b), format!("{:?}", enum_b_b));
-
-    assert_eq!(format!("{:#?}", enum_a_b), format!("{:#?}", enum_b_b));
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Slice(len = 3), b: [1, … and 2 more entries, 4], c: [X, X], \
+         d: [Some(Y), None], e: {7}, f: {1: Vec(len = 2)}, g: str(len = 4) }"
+    );
 }
 
 #[test]
-fn test_enum_with_type_parameters() {
-    use std::fmt::{Debug, Display};
+fn test_struct_shallow() {
+    #[derive(Debug)]
+    struct Config {
+        #[allow(dead_code)]
+        name: String,
+    }
 
     #[derive(DebugStub)]
-    enum TestEnum<T: Display + Debug> {
-        VariantA(T),
+    struct TestStruct {
+        #[debug_stub(shallow)]
+        a: Vec<Config>,
+        #[debug_stub(shallow)]
+        b: Option<Config>,
+        #[debug_stub(shallow)]
+        c: Result<Config, String>,
+        #[allow(dead_code)]
+        #[debug_stub(shallow)]
+        d: Config,
+        #[debug_stub(shallow)]
+        e: u32,
     }
 
-    assert_eq!(format!("{:?}", TestEnum::VariantA(true)), "VariantA(true)");
+    let s = TestStruct {
+        a: vec![Config {
+            name: "a".to_string(),
+        }],
+        b: None,
+        c: Ok(Config {
+            name: "c".to_string(),
+        }),
+        d: Config {
+            name: "d".to_string(),
+        },
+        e: 5,
+    };
 
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(true)),
-        r#"VariantA(
-    true,
-)"#
+        format!("{:?}", s),
+        "TestStruct { a: Vec(len = 1), b: None, c: Ok(..), d: Config(..), e: 5 }"
     );
 }
 
 #[test]
-fn test_enum_with_type_where_clause() {
-    use std::fmt::{Debug, Display};
-
+fn test_struct_scientific() {
     #[derive(DebugStub)]
-    enum TestEnum<T>
-    where
-        T: Display + Debug,
-    {
-        VariantA(T),
+    struct TestStruct {
+        #[debug_stub(scientific)]
+        a: f64,
+        #[debug_stub(scientific = "E")]
+        b: f32,
+        #[debug_stub(scientific, precision = 2)]
+        c: f64,
+        #[debug_stub(scientific)]
+        d: u64,
     }
 
-    assert_eq!(format!("{:?}", TestEnum::VariantA(true)), "VariantA(true)");
+    let s = TestStruct {
+        a: 0.00000015,
+        b: 1234.5,
+        c: 6.02214076e23,
+        d: 1500,
+    };
 
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(true)),
-        r#"VariantA(
-    true,
-)"#
+        format!("{:?}", s),
+        "TestStruct { a: 1.5e-7, b: 1.2345E3, c: 6.02e23, d: 1.5e3 }"
     );
 }
 
 #[test]
-fn test_enum_optional() {
+fn test_struct_stable_layout() {
     #[derive(DebugStub)]
-    enum TestEnum {
-        VariantA(#[debug_stub(some = "StructWithoutDebugReplaceValue")] Option<StructWithoutDebug>),
+    #[debug_stub(stable_layout)]
+    struct TestStruct {
+        a: (u8, &'static str),
+        #[allow(dead_code)]
+        #[debug_stub = "Hidden"]
+        b: StructWithoutDebug,
+        c: Vec<f32>,
     }
 
-    assert_eq!(format!("{:?}", TestEnum::VariantA(None)), "VariantA(None)");
+    #[derive(DebugStub)]
+    #[debug_stub(stable_layout)]
+    struct TestTuple(u8, TestStruct);
 
-    assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(None)),
+    #[derive(DebugStub)]
+    #[debug_stub(stable_layout)]
+    struct TestUnit;
+
+    let s = TestStruct {
+        a: (1, "x"),
+        b: StructWithoutDebug,
+        c: vec![1.5, 2.25],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: (1, "x"), b: Hidden, c: [1.5, 2.25] }"#
+    );
+    assert_eq!(
+        format!("{:.1?}", s),
+        r#"TestStruct { a: (1, "x"), b: Hidden, c: [1.5, 2.2] }"#
+    );
+    assert_eq!(
+        format!("{:#?}", TestTuple(7, s)),
+        r#"TestTuple(
+    7,
+    TestStruct {
+        a: (
+            1,
+            "x",
+        ),
+        b: Hidden,
+        c: [
+            1.5,
+            2.25,
+        ],
+    },
+)"#
+    );
+    assert_eq!(format!("{:#?}", TestUnit), "TestUnit");
+}
+
+#[test]
+fn test_struct_separators() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(separators)]
+        a: u64,
+        #[debug_stub(separators = ",")]
+        b: i32,
+        #[debug_stub(separators)]
+        c: u8,
+        #[debug_stub(separators)]
+        d: i128,
+    }
+
+    let s = TestStruct {
+        a: 12_345_678,
+        b: -123_456,
+        c: 42,
+        d: 0,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 12_345_678, b: -123,456, c: 42, d: 0 }"
+    );
+}
+
+#[test]
+fn test_struct_opaque_generics() {
+    struct Ctx;
+
+    #[allow(clippy::type_complexity)]
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used)]
+    struct TestStruct<C, E, T> {
+        #[debug_stub = "Handler"]
+        handler: Box<dyn Fn(&mut C) -> Result<(), E> + Send + 'static>,
+        #[debug_stub = "Error"]
+        last_error: Option<E>,
+        value: T,
+    }
+
+    let s: TestStruct<Ctx, StructWithoutDebug, u8> = TestStruct {
+        handler: Box::new(|_| Ok(())),
+        last_error: Some(StructWithoutDebug),
+        value: 1,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { handler: Handler, last_error: Error, value: 1 }"
+    );
+}
+
+#[test]
+fn test_struct_method() {
+    struct Connection {
+        id: u32,
+        open: bool,
+    }
+
+    impl Connection {
+        fn id(&self) -> u32 {
+            self.id
+        }
+
+        fn status(&self) -> &'static str {
+            if self.open {
+                "open"
+            } else {
+                "closed"
+            }
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(method = "id")]
+        a: Connection,
+        #[debug_stub(method = "status")]
+        b: Connection,
+        #[debug_stub(some(method = "status"))]
+        c: Option<Connection>,
+    }
+
+    let s = TestStruct {
+        a: Connection { id: 7, open: true },
+        b: Connection { id: 8, open: false },
+        c: Some(Connection { id: 9, open: true }),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: 7, b: "closed", c: Some("open") }"#
+    );
+}
+
+#[test]
+fn test_struct_wrap() {
+    struct Foreign(u8);
+
+    struct ForeignDebug<'a>(&'a Foreign);
+
+    impl fmt::Debug for ForeignDebug<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Foreign#{}", (self.0).0)
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(wrap = "ForeignDebug")]
+        a: Foreign,
+        #[debug_stub(some(wrap = "ForeignDebug"))]
+        b: Option<Foreign>,
+    }
+
+    let s = TestStruct {
+        a: Foreign(1),
+        b: Some(Foreign(2)),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Foreign#1, b: Some(Foreign#2) }"
+    );
+}
+
+#[test]
+fn test_struct_stub_trait() {
+    trait StubDebug {
+        fn stub_fmt(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    }
+
+    impl StubDebug for StructWithoutDebug {
+        fn stub_fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("<foreign>")
+        }
+    }
+
+    mod verbose {
+        use std::fmt;
+
+        pub trait VerboseDebug {
+            fn stub_fmt(&self, f: &mut fmt::Formatter) -> fmt::Result;
+        }
+
+        impl VerboseDebug for super::StructWithoutDebug {
+            fn stub_fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("<foreign struct without Debug>")
+            }
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(trait)]
+        a: StructWithoutDebug,
+        #[debug_stub(some(trait))]
+        b: Option<StructWithoutDebug>,
+        #[debug_stub(trait = "verbose::VerboseDebug")]
+        c: StructWithoutDebug,
+    }
+
+    let s = TestStruct {
+        a: StructWithoutDebug,
+        b: Some(StructWithoutDebug),
+        c: StructWithoutDebug,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <foreign>, b: Some(<foreign>), c: <foreign struct without Debug> }"
+    );
+}
+
+#[test]
+fn test_struct_opaque() {
+    struct Connection;
+
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used)]
+    struct TestStruct {
+        #[debug_stub(opaque)]
+        a: Connection,
+        #[debug_stub(opaque)]
+        b: std::sync::Arc<Connection>,
+        #[debug_stub(some(opaque))]
+        c: Option<StructWithoutDebug>,
+    }
+
+    let s = TestStruct {
+        a: Connection,
+        b: std::sync::Arc::new(Connection),
+        c: Some(StructWithoutDebug),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Connection { .. }, b: Arc { .. }, c: Some(StructWithoutDebug { .. }) }"
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        "TestStruct {\n    a: Connection { .. },\n    b: Arc { .. },\n    c: Some(\n        StructWithoutDebug { .. },\n    ),\n}"
+    );
+}
+
+#[test]
+fn test_struct_as_inner() {
+    #[repr(transparent)]
+    struct UserId(u64);
+
+    mod ids {
+        #[repr(transparent)]
+        pub struct OrderId(u64);
+
+        impl OrderId {
+            pub fn new(id: u64) -> Self {
+                OrderId(id)
+            }
+        }
+
+        impl AsRef<u64> for OrderId {
+            fn as_ref(&self) -> &u64 {
+                &self.0
+            }
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(as_inner)]
+        a: UserId,
+        #[debug_stub(as_inner, inner = "u64")]
+        b: ids::OrderId,
+        #[debug_stub(some(as_inner))]
+        c: Option<UserId>,
+    }
+
+    let s = TestStruct {
+        a: UserId(42),
+        b: ids::OrderId::new(7),
+        c: Some(UserId(1)),
+    };
+
+    assert_eq!(format!("{:?}", s), "TestStruct { a: 42, b: 7, c: Some(1) }");
+}
+
+#[test]
+fn test_struct_getter() {
+    struct Pool {
+        idle: Vec<u8>,
+        busy: Vec<u8>,
+    }
+
+    #[derive(Debug)]
+    struct Stats {
+        #[allow(dead_code)]
+        idle: usize,
+        #[allow(dead_code)]
+        busy: usize,
+    }
+
+    fn pool_stats(pool: &Pool) -> Stats {
+        Stats {
+            idle: pool.idle.len(),
+            busy: pool.busy.len(),
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(getter = "pool_stats")]
+        a: Pool,
+        #[debug_stub(getter = "str::len")]
+        b: &'static str,
+    }
+
+    let s = TestStruct {
+        a: Pool {
+            idle: vec![1, 2],
+            busy: vec![3],
+        },
+        b: "abcd",
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Stats { idle: 2, busy: 1 }, b: 4 }"
+    );
+}
+
+#[test]
+fn test_struct_via() {
+    struct Handle {
+        id: u32,
+        open: bool,
+    }
+
+    #[derive(Debug)]
+    struct HandleInfo {
+        #[allow(dead_code)]
+        id: u32,
+        #[allow(dead_code)]
+        open: bool,
+    }
+
+    impl From<&Handle> for HandleInfo {
+        fn from(handle: &Handle) -> Self {
+            HandleInfo {
+                id: handle.id,
+                open: handle.open,
+            }
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(via = "HandleInfo")]
+        a: Handle,
+        #[debug_stub(some(via = "HandleInfo"))]
+        b: Option<Handle>,
+    }
+
+    let s = TestStruct {
+        a: Handle { id: 1, open: true },
+        b: Some(Handle { id: 2, open: false }),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: HandleInfo { id: 1, open: true }, \
+         b: Some(HandleInfo { id: 2, open: false }) }"
+    );
+}
+
+#[test]
+fn test_struct_debug_flags() {
+    #[derive(Debug)]
+    struct Expected {
+        #[allow(dead_code)]
+        a: Vec<u8>,
+        #[allow(dead_code)]
+        b: Option<u16>,
+        #[allow(dead_code)]
+        c: (u8, f32),
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(stable_layout)]
+    struct TestStruct {
+        #[debug_stub(max_len = 40)]
+        a: Vec<u8>,
+        #[debug_stub(on_error = "?")]
+        b: Option<u16>,
+        #[debug_stub(precision = 1)]
+        c: (u8, f32),
+    }
+
+    let s = TestStruct {
+        a: vec![10, 255],
+        b: Some(4096),
+        c: (171, 0.5),
+    };
+    let expected = Expected {
+        a: vec![10, 255],
+        b: Some(4096),
+        c: (171, 0.5),
+    };
+
+    assert_eq!(
+        format!("{:5?}", s),
+        "TestStruct { a: [   10,   255], b: Some( 4096), c: (  171,   0.5) }"
+    );
+    assert_eq!(
+        format!("{:5?}", s),
+        format!("{:5?}", expected).replace("Expected", "TestStruct")
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        format!("{:#?}", expected).replace("Expected", "TestStruct")
+    );
+}
+
+#[test]
+fn test_struct_debug_hex() {
+    use std::borrow::Cow;
+
+    fn redact<'a>(_field: &str, text: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(text)
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(stable_layout)]
+    struct TestStruct {
+        #[debug_stub(max_len = 40)]
+        a: Vec<u8>,
+        #[debug_stub(on_error = "?")]
+        b: Option<u16>,
+        #[debug_stub(mask(keep_end = 2))]
+        c: u16,
+        d: u8,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(redactor = "redact")]
+    struct Redacted {
+        a: Vec<u8>,
+    }
+
+    let s = TestStruct {
+        a: vec![10, 255],
+        b: Some(4096),
+        c: 0xabcd,
+        d: 42,
+    };
+    assert_eq!(
+        format!("{:x?}", s),
+        "TestStruct { a: [a, ff], b: Some(1000), c: **cd, d: 2a }"
+    );
+    assert_eq!(
+        format!("{:#X?}", s),
+        "TestStruct {\n    a: [\n        0xA,\n        0xFF,\n    ],\n    b: Some(\n        0x1000,\n    ),\n    \
+         c: ****CD,\n    d: 0x2A,\n}"
+    );
+
+    let r = Redacted { a: vec![10, 255] };
+    assert_eq!(format!("{:x?}", r), "Redacted { a: [a, ff] }");
+    assert_eq!(
+        format!("{:#X?}", r),
+        "Redacted {\n    a: [\n        0xA,\n        0xFF,\n    ],\n}"
+    );
+}
+
+#[test]
+fn test_struct_expr() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(expr = "format_args!(\"<len {}>\", self.queue.len())")]
+        queue: Vec<StructWithoutDebug>,
+        #[allow(dead_code)]
+        #[debug_stub(expr = "self.total - self.done")]
+        remaining: (),
+        total: u32,
+        done: u32,
+    }
+
+    let mut s = TestStruct {
+        queue: vec![StructWithoutDebug, StructWithoutDebug],
+        remaining: (),
+        total: 10,
+        done: 4,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { queue: <len 2>, remaining: 6, total: 10, done: 4 }"
+    );
+
+    s.queue.pop();
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { queue: <len 1>, remaining: 6, total: 10, done: 4 }"
+    );
+}
+
+#[test]
+fn test_struct_inherit_precision() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(inherit_precision, max_len = 20)]
+        a: f64,
+        #[debug_stub(max_len = 20)]
+        b: f64,
+        #[debug_stub(none = "-", inherit_precision, on_error = "?")]
+        c: Option<(f32, f32)>,
+        #[debug_stub(inherit_precision)]
+        d: f64,
+    }
+
+    let s = TestStruct {
+        a: 1.0 / 3.0,
+        b: 1.0 / 3.0,
+        c: Some((0.5, 2.0)),
+        d: 2.0 / 3.0,
+    };
+
+    assert_eq!(
+        format!("{:.2?}", s),
+        "TestStruct { a: 0.33, b: 0.3333333333333333, c: Some((0.50, 2.00)), d: 0.67 }"
+    );
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 0.3333333333333333, b: 0.3333333333333333, c: Some((0.5, 2.0)), d: 0.6666666666666666 }"
+    );
+
+    let s = TestStruct { c: None, ..s };
+    assert_eq!(
+        format!("{:.1?}", s),
+        "TestStruct { a: 0.3, b: 0.3333333333333333, c: -, d: 0.7 }"
+    );
+}
+
+#[test]
+fn test_struct_with() {
+    fn fmt_flag(value: &bool, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(if *value { "on" } else { "off" })
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(with = |v, f| write!(f, "{} items", v.len()))]
+        a: Vec<StructWithoutDebug>,
+        #[debug_stub(with = "fmt_flag")]
+        b: bool,
+        #[debug_stub(some(with = "|v, f| write!(f, \"<{}>\", v)"))]
+        c: Option<u8>,
+    }
+
+    let s = TestStruct {
+        a: vec![StructWithoutDebug, StructWithoutDebug],
+        b: true,
+        c: Some(4),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 2 items, b: on, c: Some(<4>) }"
+    );
+}
+
+#[test]
+fn test_struct_with_and_markers() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(with = |v, f| write!(f, "{} items", v.len()), skip_if = "Vec::is_empty")]
+        a: Vec<StructWithoutDebug>,
+        #[debug_stub(brief, with = |v, f| write!(f, "<{}>", v), max_len = 3)]
+        b: String,
+    }
+
+    let s = TestStruct {
+        a: vec![],
+        b: "abc".to_owned(),
+    };
+    assert_eq!(format!("{:?}", s), "TestStruct { b: <ab… }");
+    assert_eq!(s.brief().to_string(), "TestStruct b=<ab…");
+
+    let s = TestStruct {
+        a: vec![StructWithoutDebug],
+        b: "a".to_owned(),
+    };
+    assert_eq!(format!("{:?}", s), "TestStruct { a: 1 items, b: <a> }");
+}
+
+#[test]
+fn test_struct_template() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub = "<{type}, {len} bytes>"]
+        a: Vec<u8>,
+        #[debug_stub(some = "{len} items")]
+        b: Option<Vec<StructWithoutDebug>>,
+        #[allow(dead_code)]
+        #[debug_stub = "{{type}}"]
+        c: StructWithoutDebug,
+        #[debug_stub = "{ptr}"]
+        d: Box<u8>,
+    }
+
+    let s = TestStruct {
+        a: vec![1, 2, 3],
+        b: Some(vec![StructWithoutDebug]),
+        c: StructWithoutDebug,
+        d: Box::new(1),
+    };
+
+    let output = format!("{:?}", s);
+    assert!(
+        output.starts_with(
+            "TestStruct { a: <alloc::vec::Vec<u8>, 3 bytes>, b: Some(1 items), \
+             c: {lib::StructWithoutDebug}, d: 0x"
+        ),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_struct_replace_braces() {
+    #[derive(DebugStub)]
+    #[allow(dead_code)]
+    struct TestStruct {
+        #[debug_stub = "{...}"]
+        a: StructWithoutDebug,
+        #[debug_stub = "<fn() {}>"]
+        b: StructWithoutDebug,
+        #[debug_stub = "}"]
+        c: StructWithoutDebug,
+        #[debug_stub(some = "{{x}} {size}")]
+        d: Option<StructWithoutDebug>,
+    }
+
+    let s = TestStruct {
+        a: StructWithoutDebug,
+        b: StructWithoutDebug,
+        c: StructWithoutDebug,
+        d: Some(StructWithoutDebug),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: {...}, b: <fn() {}>, c: }, d: Some({{x}} {size}) }"
+    );
+}
+
+#[test]
+fn test_struct_if() {
+    fn is_external(email: &str) -> bool {
+        !email.ends_with("@example.com")
+    }
+
+    fn is_long(value: &[u8]) -> bool {
+        value.len() > 2
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(explain)]
+    struct TestStruct {
+        #[debug_stub(if = "is_external", then = "<external>", else_debug)]
+        a: String,
+        #[debug_stub(if = "is_long", then(len))]
+        b: Vec<u8>,
+    }
+
+    let s = TestStruct {
+        a: "admin@example.com".to_string(),
+        b: vec![1, 2],
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: "admin@example.com", b: [1, 2] }"#
+    );
+
+    let s = TestStruct {
+        a: "user@mail.test".to_string(),
+        b: vec![1, 2, 3],
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <external>, b: Vec(len = 3) }"
+    );
+    assert_eq!(
+        TestStruct::DEBUG_STUB_EXPLAIN,
+        "TestStruct\n  bounds: none\n  \
+         a: if = \"is_external\", then = \"<external>\", else_debug\n  \
+         b: if = \"is_long\", then(len), else_debug"
+    );
+}
+
+#[test]
+fn test_struct_release_only() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(release_only = "<secret>")]
+        a: String,
+        #[debug_stub(release_only(len))]
+        b: Vec<u8>,
+    }
+
+    let s = TestStruct {
+        a: "hunter2".to_string(),
+        b: vec![1, 2],
+    };
+
+    let expected = if cfg!(debug_assertions) {
+        r#"TestStruct { a: "hunter2", b: [1, 2] }"#
+    } else {
+        "TestStruct { a: <secret>, b: Vec(len = 2) }"
+    };
+    assert_eq!(format!("{:?}", s), expected);
+}
+
+#[test]
+fn test_struct_alternate() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(len, alternate(first = 2))]
+        a: Vec<u8>,
+        #[debug_stub(alternate = "<secret>")]
+        b: u8,
+    }
+
+    let s = TestStruct {
+        a: vec![1, 2, 3],
+        b: 7,
+    };
+
+    assert_eq!(format!("{:?}", s), "TestStruct { a: Vec(len = 3), b: 7 }");
+    assert_eq!(
+        format!("{:#?}", s),
+        "TestStruct {\n    a: [\n        1,\n        2,\n        … and 1 more entries,\n    ],\n    b: <secret>,\n}"
+    );
+}
+
+#[test]
+fn test_struct_const() {
+    mod markers {
+        pub const REDACTED: &str = "<redacted>";
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[allow(dead_code)]
+        #[debug_stub(const = "markers::REDACTED")]
+        a: StructWithoutDebug,
+        #[debug_stub(some(const = "markers::REDACTED"), none = "-")]
+        b: Option<StructWithoutDebug>,
+        c: u8,
+    }
+
+    let s = TestStruct {
+        a: StructWithoutDebug,
+        b: Some(StructWithoutDebug),
+        c: 1,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <redacted>, b: Some(<redacted>), c: 1 }"
+    );
+}
+
+#[test]
+fn test_struct_debug_fields() {
+    #[derive(DebugStub)]
+    #[debug_stub(debug_fields)]
+    struct TestStruct {
+        value: bool,
+        #[allow(dead_code)]
+        #[debug_stub = "StructWithoutDebugReplaceValue"]
+        b: StructWithoutDebug,
+        #[debug_stub(some = "Replaced")]
+        c: Option<StructWithoutDebug>,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(debug_fields)]
+    struct TestTuple(u64, #[debug_stub = "_"] StructWithoutDebug);
+
+    assert_eq!(
+        TestStruct {
+            value: true,
+            b: StructWithoutDebug,
+            c: Some(StructWithoutDebug),
+        }
+        .debug_fields(),
+        vec![
+            ("value", "true".to_string()),
+            ("b", "StructWithoutDebugReplaceValue".to_string()),
+            ("c", "Some(Replaced)".to_string()),
+        ]
+    );
+
+    assert_eq!(
+        TestTuple(42, StructWithoutDebug).debug_fields(),
+        vec![("0", "42".to_string()), ("1", "_".to_string())]
+    );
+}
+
+#[test]
+fn test_struct_brief() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(brief)]
+        id: u64,
+        #[debug_stub(brief, max_len = 4)]
+        name: String,
+        #[allow(dead_code)]
+        #[debug_stub = "ReplacementValue"]
+        payload: StructWithoutDebug,
+        retries: u8,
+    }
+
+    let s = TestStruct {
+        id: 42,
+        name: "build".to_string(),
+        payload: StructWithoutDebug,
+        retries: 3,
+    };
+
+    assert_eq!(s.brief().to_string(), r#"TestStruct id=42 name="bui…"#);
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { id: 42, name: "bui…, payload: ReplacementValue, retries: 3 }"#
+    );
+}
+
+#[test]
+fn test_struct_skip_if() {
+    fn is_zero(value: &u8) -> bool {
+        *value == 0
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(explain)]
+    struct TestStruct {
+        #[debug_stub(skip_if = "Vec::is_empty")]
+        a: Vec<u8>,
+        #[debug_stub(skip_if = "is_zero", brief)]
+        b: u8,
+        #[debug_stub(skip_if = "Option::is_none", some = "Set")]
+        c: Option<u8>,
+        d: bool,
+    }
+
+    let s = TestStruct {
+        a: vec![],
+        b: 0,
+        c: None,
+        d: true,
+    };
+    assert_eq!(format!("{:?}", s), "TestStruct { d: true }");
+    assert_eq!(s.brief().to_string(), "TestStruct");
+
+    let s = TestStruct {
+        a: vec![1],
+        b: 2,
+        c: Some(3),
+        d: false,
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: [1], b: 2, c: Some(Set), d: false }"
+    );
+    assert_eq!(s.brief().to_string(), "TestStruct b=2");
+    assert_eq!(
+        TestStruct::DEBUG_STUB_EXPLAIN,
+        "TestStruct\n  bounds: none\n  a: debug, skip_if = \"Vec::is_empty\"\n  \
+         b: debug, skip_if = \"is_zero\"\n  c: some = \"Set\", skip_if = \"Option::is_none\"\n  \
+         d: debug"
+    );
+}
+
+#[test]
+fn test_struct_skip_other_outputs() {
+    #[derive(DebugStub)]
+    #[debug_stub(debug_fields)]
+    #[cfg_attr(feature = "dot", debug_stub(dot))]
+    struct TestStruct {
+        #[debug_stub(skip_if = "Vec::is_empty")]
+        a: Vec<u8>,
+        #[debug_stub(skip_none)]
+        b: Option<u8>,
+        c: bool,
+    }
+
+    let s = TestStruct {
+        a: vec![],
+        b: None,
+        c: true,
+    };
+    assert_eq!(s.debug_fields(), vec![("c", "true".to_string())]);
+    #[cfg(feature = "dot")]
+    assert_eq!(
+        s.to_dot(),
+        "digraph {\n    node0 [label=\"TestStruct\"];\n    node3 [label=\"true\"];\n    node0 -> node3 [label=\"c\"];\n}\n"
+    );
+
+    let s = TestStruct {
+        a: vec![1],
+        b: Some(2),
+        c: false,
+    };
+    assert_eq!(
+        s.debug_fields(),
+        vec![
+            ("a", "[1]".to_string()),
+            ("b", "Some(2)".to_string()),
+            ("c", "false".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_struct_skip_none() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(skip_none)]
+        a: Option<u8>,
+        #[debug_stub(skip_none, some = "Set")]
+        b: Option<String>,
+        c: Option<u8>,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(skip_none)]
+    struct TestConfig {
+        host: Option<String>,
+        #[debug_stub(skip_if = "Option::is_some")]
+        port: Option<u16>,
+        #[debug_stub(some(len))]
+        paths: Option<Vec<u8>>,
+        verbose: bool,
+    }
+
+    let s = TestStruct {
+        a: None,
+        b: None,
+        c: None,
+    };
+    assert_eq!(format!("{:?}", s), "TestStruct { c: None }");
+
+    let s = TestStruct {
+        a: Some(1),
+        b: Some("x".to_string()),
+        c: Some(2),
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Some(1), b: Some(Set), c: Some(2) }"
+    );
+
+    let config = TestConfig {
+        host: None,
+        port: None,
+        paths: None,
+        verbose: true,
+    };
+    assert_eq!(
+        format!("{:?}", config),
+        "TestConfig { port: None, verbose: true }"
+    );
+
+    let config = TestConfig {
+        host: Some("localhost".to_string()),
+        port: Some(80),
+        paths: Some(vec![1]),
+        verbose: false,
+    };
+    assert_eq!(
+        format!("{:?}", config),
+        r#"TestConfig { host: Some("localhost"), paths: Some(Vec(len = 1)), verbose: false }"#
+    );
+}
+
+#[test]
+fn test_struct_skip_empty() {
+    use std::collections::HashMap;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(skip_empty)]
+        a: String,
+        #[debug_stub(skip_empty, len)]
+        b: Vec<u8>,
+        #[debug_stub(skip_empty)]
+        c: HashMap<u8, u8>,
+        #[debug_stub(skip_empty)]
+        d: &'static str,
+        e: Vec<u8>,
+    }
+
+    let s = TestStruct {
+        a: String::new(),
+        b: vec![],
+        c: HashMap::new(),
+        d: "",
+        e: vec![],
+    };
+    assert_eq!(format!("{:?}", s), "TestStruct { e: [] }");
+
+    let s = TestStruct {
+        a: "a".to_string(),
+        b: vec![1],
+        c: vec![(1, 2)].into_iter().collect(),
+        d: "d",
+        e: vec![],
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: "a", b: Vec(len = 1), c: {1: 2}, d: "d", e: [] }"#
+    );
+}
+
+#[test]
+fn test_struct_skip_default() {
+    #[derive(Default, PartialEq)]
+    enum Mode {
+        #[default]
+        Fast,
+        Safe,
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(skip_default)]
+        retries: u8,
+        #[debug_stub(skip_default)]
+        name: String,
+        #[debug_stub = "Custom"]
+        #[debug_stub(skip_default)]
+        mode: Mode,
+        #[debug_stub(skip_default)]
+        ratio: Option<f32>,
+    }
+
+    let s = TestStruct {
+        retries: 0,
+        name: String::new(),
+        mode: Mode::Fast,
+        ratio: None,
+    };
+    assert_eq!(format!("{:?}", s), "TestStruct");
+
+    let s = TestStruct {
+        retries: 3,
+        name: "job".to_string(),
+        mode: Mode::Safe,
+        ratio: Some(0.5),
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { retries: 3, name: "job", mode: Custom, ratio: Some(0.5) }"#
+    );
+}
+
+#[test]
+fn test_struct_marker_attribute_with_stub() {
+    #[derive(DebugStub)]
+    #[debug_stub(types("*Debug" = "<no debug>"))]
+    struct TestStruct {
+        #[allow(dead_code)]
+        #[debug_stub(brief)]
+        #[debug_stub = "<redacted>"]
+        password: String,
+        #[debug_stub(priority = 1)]
+        #[debug_stub(len)]
+        data: Vec<u8>,
+        #[allow(dead_code)]
+        #[debug_stub(brief)]
+        handle: StructWithoutDebug,
+    }
+
+    let s = TestStruct {
+        password: "hunter2".to_string(),
+        data: vec![1, 2],
+        handle: StructWithoutDebug,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { password: <redacted>, data: Vec(len = 2), handle: <no debug> }"
+    );
+    assert_eq!(
+        s.brief().to_string(),
+        "TestStruct password=<redacted> handle=<no debug>"
+    );
+}
+
+#[test]
+fn test_struct_type_name() {
+    use std::marker::PhantomData;
+
+    #[derive(DebugStub)]
+    #[debug_stub(ignore_generics)]
+    struct TestStruct<T> {
+        #[allow(dead_code)]
+        #[debug_stub(type_name)]
+        a: T,
+        #[allow(dead_code)]
+        #[debug_stub(type_name)]
+        b: PhantomData<u8>,
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestStruct {
+                a: StructWithoutDebug,
+                b: PhantomData,
+            }
+        ),
+        format!(
+            "TestStruct {{ a: {}, b: {} }}",
+            std::any::type_name::<StructWithoutDebug>(),
+            std::any::type_name::<PhantomData<u8>>(),
+        )
+    );
+}
+
+#[test]
+fn test_struct_hash() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(hash)]
+        email: String,
+        #[debug_stub(hash)]
+        id: u64,
+    }
+
+    let a = format!(
+        "{:?}",
+        TestStruct {
+            email: "user@example.com".to_string(),
+            id: 42,
+        }
+    );
+    let b = format!(
+        "{:?}",
+        TestStruct {
+            email: "user@example.com".to_string(),
+            id: 43,
+        }
+    );
+
+    assert_eq!(a, "TestStruct { email: #13756aeb, id: #c8b30784 }");
+    assert_eq!(a.split(',').next(), b.split(',').next());
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_struct_limit() {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(limit = 2)]
+        a: Vec<u32>,
+        #[debug_stub(limit = 2)]
+        b: BTreeSet<u32>,
+        #[debug_stub(limit = 1)]
+        c: BTreeMap<u32, &'static str>,
+    }
+
+    let s = TestStruct {
+        a: vec![1, 2, 3, 4, 5],
+        b: (1..=3).collect(),
+        c: vec![(1, "a"), (2, "b")].into_iter().collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: [1, 2, … and 3 more entries], b: {1, 2, … and 1 more entries}, \
+         c: {1: \"a\", … and 1 more entries} }"
+    );
+
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: [
+        1,
+        2,
+        … and 3 more entries,
+    ],
+    b: {
+        1,
+        2,
+        … and 1 more entries,
+    },
+    c: {
+        1: "a",
+        … and 1 more entries,
+    },
+}"#
+    );
+}
+
+#[test]
+fn test_struct_limit_compare_std() {
+    use std::collections::BTreeMap;
+
+    mod a {
+        use debug_stub_derive::DebugStub;
+        use std::collections::BTreeMap;
+
+        #[derive(DebugStub)]
+        pub struct TestStruct {
+            #[debug_stub(limit = 10)]
+            pub a: Vec<(u32, bool)>,
+            #[debug_stub(limit = 10)]
+            pub b: BTreeMap<u32, Vec<u32>>,
+        }
+    }
+
+    mod b {
+        use std::collections::BTreeMap;
+
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub struct TestStruct {
+            pub a: Vec<(u32, bool)>,
+            pub b: BTreeMap<u32, Vec<u32>>,
+        }
+    }
+
+    let map: BTreeMap<_, _> = vec![(1, vec![2, 3]), (4, vec![])].into_iter().collect();
+    let struct_a = a::TestStruct {
+        a: vec![(1, true), (2, false)],
+        b: map.clone(),
+    };
+    let struct_b = b::TestStruct {
+        a: vec![(1, true), (2, false)],
+        b: map,
+    };
+
+    assert_eq!(format!("{:?}", struct_a), format!("{:?}", struct_b));
+
+    assert_eq!(format!("{:#?}", struct_a), format!("{:#?}", struct_b));
+}
+
+#[cfg(feature = "sha256")]
+#[test]
+fn test_struct_sha256() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(sha256)]
+        a: Vec<u8>,
+        #[debug_stub(sha256)]
+        b: &'static [u8],
+        #[debug_stub(sha256)]
+        c: String,
+    }
+
+    let s = TestStruct {
+        a: vec![],
+        b: b"abc",
+        c: "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_string(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: sha256:e3b0c44298fc1c14…, b: sha256:ba7816bf8f01cfea…, \
+         c: sha256:248d6a61d20638b8… }"
+    );
+}
+
+#[test]
+fn test_struct_explain() {
+    #[derive(DebugStub)]
+    #[debug_stub(explain)]
+    #[allow(dead_code)]
+    struct TestStruct<T, U: Clone> {
+        a: T,
+        #[debug_stub = "Replaced"]
+        b: StructWithoutDebug,
+        #[debug_stub(ok = "Replaced")]
+        c: Result<StructWithoutDebug, U>,
+    }
+
+    assert_eq!(
+        TestStruct::<(), ()>::DEBUG_STUB_EXPLAIN,
+        r#"TestStruct
+  bounds: T: Debug, U: Debug
+  a: debug
+  b: replace = "Replaced"
+  c: ok = "Replaced""#
+    );
+}
+
+#[test]
+fn test_struct_container_options() {
+    #[derive(DebugStub)]
+    #[debug_stub(ignore_generics)]
+    #[debug_stub(explain, bound = "T: Debug")]
+    #[debug_stub(stable_layout, mark_used)]
+    struct TestStruct<T, U> {
+        a: T,
+        #[debug_stub = "Replaced"]
+        b: U,
+    }
+
+    assert_eq!(
+        format!(
+            "{:#?}",
+            TestStruct {
+                a: 1,
+                b: StructWithoutDebug,
+            }
+        ),
+        "TestStruct {\n    a: 1,\n    b: Replaced,\n}"
+    );
+    assert_eq!(
+        TestStruct::<u8, StructWithoutDebug>::DEBUG_STUB_EXPLAIN,
+        r#"TestStruct
+  bounds: T: Debug
+  a: debug
+  b: replace = "Replaced""#
+    );
+}
+
+#[test]
+fn test_struct_display() {
+    use std::net::Ipv4Addr;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(display)]
+        a: Ipv4Addr,
+        #[debug_stub(display)]
+        b: String,
+        #[debug_stub(display, sanitize)]
+        c: String,
+    }
+
+    let s = TestStruct {
+        a: Ipv4Addr::LOCALHOST,
+        b: "plain".to_string(),
+        c: "user\n[ERROR] forged\u{1b}[31m".to_string(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: 127.0.0.1, b: plain, c: user\n[ERROR] forged\u{1b}[31m }"#
+    );
+}
+
+#[test]
+fn test_struct_truncate() {
+    use std::borrow::Cow;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(truncate = 16)]
+        a: String,
+        #[debug_stub(truncate = 3)]
+        b: &'static str,
+        #[debug_stub(truncate = 2)]
+        c: Cow<'static, str>,
+    }
+
+    let s = TestStruct {
+        a: "SELECT * FROM users WHERE id = \"42\"".to_string(),
+        b: "abc",
+        c: Cow::Borrowed("äöü"),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: "SELECT * FROM us…" (35 chars), b: "abc", c: "äö…" (3 chars) }"#
+    );
+}
+
+#[test]
+fn test_struct_peek() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(peek = 4)]
+        a: String,
+        #[debug_stub(peek = 2)]
+        b: &'static str,
+        #[debug_stub(some(peek = 1))]
+        c: Option<String>,
+        #[debug_stub(peek = 0)]
+        d: String,
+    }
+
+    let s = TestStruct {
+        a: "AKIAIOSFODNN7EXAMPLEZ9Q2".to_string(),
+        b: "abcd",
+        c: Some("ä\"ü".to_string()),
+        d: "secret".to_string(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: "AKIA…Z9Q2" (24 chars), b: "abcd", c: Some("ä…ü" (3 chars)), d: "…" (6 chars) }"#
+    );
+}
+
+#[test]
+fn test_struct_first() {
+    use std::collections::VecDeque;
+
+    #[derive(DebugStub)]
+    struct TestStruct<'a> {
+        #[debug_stub(first = 2)]
+        a: Vec<u32>,
+        #[debug_stub(first = 1)]
+        b: &'a [&'static str],
+        #[debug_stub(first = 3)]
+        c: VecDeque<u8>,
+    }
+
+    let s = TestStruct {
+        a: (0..4994).collect(),
+        b: &["x", "y"],
+        c: vec![1, 2].into_iter().collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: [0, 1, … and 4992 more entries], b: ["x", … and 1 more entries], c: [1, 2] }"#
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_struct_utc_chrono() {
+    use chrono::{DateTime, FixedOffset, TimeZone};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(utc = "chrono")]
+        a: DateTime<FixedOffset>,
+    }
+
+    let a = FixedOffset::east_opt(2 * 3600)
+        .unwrap()
+        .with_ymd_and_hms(2024, 5, 1, 14, 34, 56)
+        .unwrap();
+
+    assert_eq!(
+        format!("{:?}", TestStruct { a }),
+        "TestStruct { a: 2024-05-01T12:34:56Z }"
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_struct_utc_time() {
+    use time::{OffsetDateTime, UtcOffset};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(utc = "time")]
+        a: OffsetDateTime,
+    }
+
+    let a = OffsetDateTime::from_unix_timestamp(1_714_566_896)
+        .unwrap()
+        .to_offset(UtcOffset::from_hms(-7, 0, 0).unwrap());
+
+    assert_eq!(
+        format!("{:?}", TestStruct { a }),
+        format!(
+            "TestStruct {{ a: {:?} }}",
+            OffsetDateTime::from_unix_timestamp(1_714_566_896).unwrap()
+        )
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_struct_rfc3339_chrono() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(rfc3339 = "chrono")]
+        a: SystemTime,
+        #[debug_stub(rfc3339 = "chrono")]
+        b: SystemTime,
+        #[debug_stub(rfc3339 = "chrono")]
+        c: SystemTime,
+    }
+
+    let s = TestStruct {
+        a: UNIX_EPOCH + Duration::from_secs(1_714_566_896),
+        b: UNIX_EPOCH + Duration::from_millis(1_714_566_896_250),
+        c: UNIX_EPOCH - Duration::from_secs(1),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 2024-05-01T12:34:56Z, b: 2024-05-01T12:34:56.25Z, c: 1969-12-31T23:59:59Z }"
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_struct_rfc3339_time() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(rfc3339 = "time")]
+        a: SystemTime,
+        #[debug_stub(rfc3339 = "time")]
+        b: SystemTime,
+    }
+
+    let s = TestStruct {
+        a: UNIX_EPOCH + Duration::from_secs(1_714_566_896),
+        b: UNIX_EPOCH + Duration::from_nanos(1_714_566_896_000_000_001),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 2024-05-01T12:34:56Z, b: 2024-05-01T12:34:56.000000001Z }"
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_struct_json() {
+    use serde_json::{json, Value};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(json(redact("password", "token")))]
+        a: Value,
+        #[debug_stub(json)]
+        b: Value,
+    }
+
+    let s = TestStruct {
+        a: json!({
+            "user": "name",
+            "password": "hunter2",
+            "sessions": [{ "token": "abc", "id": 1 }],
+        }),
+        b: json!([1, "two"]),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: {"password":"<redacted>","sessions":[{"id":1,"token":"<redacted>"}],"user":"name"}, b: [1,"two"] }"#
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: {
+      "password": "<redacted>",
+      "sessions": [
+        {
+          "id": 1,
+          "token": "<redacted>"
+        }
+      ],
+      "user": "name"
+    },
+    b: [
+      1,
+      "two"
+    ],
+}"#
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_struct_json_serialize() {
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Config {
+        host: String,
+        port: u16,
+        password: String,
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(json(redact("password")))]
+        a: Config,
+        #[debug_stub(json)]
+        b: BTreeMap<&'static str, Vec<u8>>,
+        #[debug_stub(some(json))]
+        c: Option<(u8, bool)>,
+    }
+
+    let s = TestStruct {
+        a: Config {
+            host: "localhost".to_string(),
+            port: 8080,
+            password: "hunter2".to_string(),
+        },
+        b: vec![("x", vec![1, 2])].into_iter().collect(),
+        c: Some((3, true)),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: {"host":"localhost","password":"<redacted>","port":8080}, b: {"x":[1,2]}, c: Some([3,true]) }"#
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: {
+      "host": "localhost",
+      "password": "<redacted>",
+      "port": 8080
+    },
+    b: {
+      "x": [
+        1,
+        2
+      ]
+    },
+    c: Some(
+        [
+          3,
+          true
+        ],
+    ),
+}"#
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_struct_json_error() {
+    use std::collections::HashMap;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(json)]
+        a: HashMap<(u8, u8), bool>,
+        #[debug_stub(json(redact("password")))]
+        b: HashMap<(u8, u8), bool>,
+    }
+
+    let s = TestStruct {
+        a: vec![((1, 2), true)].into_iter().collect(),
+        b: vec![((3, 4), false)].into_iter().collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <json error: key must be a string>, \
+         b: <json error: key must be a string> }"
+    );
+}
+
+#[test]
+fn test_struct_byte_order() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(be)]
+        a: u16,
+        #[debug_stub(le)]
+        b: u32,
+        #[debug_stub(be)]
+        c: i8,
+    }
+
+    let s = TestStruct {
+        a: u16::from_ne_bytes([0x00, 0x01]),
+        b: u32::from_ne_bytes([0x01, 0x02, 0x00, 0x00]),
+        c: -1,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        format!(
+            "TestStruct {{ a: {:#06x} (be: 1), b: {:#010x} (le: 513), c: 0xff (be: -1) }}",
+            s.a, s.b,
+        )
+    );
+}
+
+#[test]
+fn test_struct_head_tail() {
+    use std::collections::BTreeMap;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(head = 2, tail = 1)]
+        a: Vec<u32>,
+        #[debug_stub(tail = 2)]
+        b: Vec<u32>,
+        #[debug_stub(head = 1, tail = 1)]
+        c: BTreeMap<u32, bool>,
+        #[debug_stub(head = 2, tail = 2)]
+        d: Vec<u32>,
+    }
+
+    let s = TestStruct {
+        a: (1..=10).collect(),
+        b: (1..=10).collect(),
+        c: (1..=3).map(|i| (i, i % 2 == 0)).collect(),
+        d: (1..=3).collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: [1, 2, … and 7 more entries, 10], b: [… and 8 more entries, 9, 10], \
+         c: {1: false, … and 1 more entries, 3: false}, d: [1, 2, 3] }"
+    );
+}
+
+#[test]
+fn test_struct_crc32() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(crc32)]
+        a: Vec<u8>,
+        #[debug_stub(crc32)]
+        b: &'static [u8],
+        #[debug_stub(crc32)]
+        c: String,
+    }
+
+    let s = TestStruct {
+        a: vec![],
+        b: b"123456789",
+        c: "The quick brown fox jumps over the lazy dog".to_string(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: crc32:00000000, b: crc32:cbf43926, c: crc32:414fa339 }"
+    );
+}
+
+#[test]
+fn test_struct_ptr() {
+    use std::rc::Rc;
+
+    trait Trait {}
+    impl Trait for u8 {}
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(ptr)]
+        a: Box<dyn Trait>,
+        #[debug_stub(ptr)]
+        b: Rc<StructWithoutDebug>,
+        #[debug_stub(ptr)]
+        c: *const u8,
+    }
+
+    let s = TestStruct {
+        a: Box::new(1u8),
+        b: Rc::new(StructWithoutDebug),
+        c: 0x1000 as *const u8,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        format!("TestStruct {{ a: {:p}, b: {:p}, c: 0x1000 }}", s.a, s.b)
+    );
+}
+
+#[test]
+fn test_struct_addr_style() {
+    use std::rc::Rc;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(ptr, addr_style = "full")]
+        a: *const u8,
+        #[debug_stub(ptr, addr_style = "short")]
+        b: *const u8,
+        #[debug_stub(ptr, addr_style = "full")]
+        c: Rc<StructWithoutDebug>,
+    }
+
+    let s = TestStruct {
+        a: 0x1000 as *const u8,
+        b: 0x1000 as *const u8,
+        c: Rc::new(StructWithoutDebug),
+    };
+
+    let width = std::mem::size_of::<usize>() * 2;
+    assert_eq!(
+        format!("{:?}", s),
+        format!(
+            "TestStruct {{ a: 0x{:0width$x}, b: 0x1000, c: {:#p} }}",
+            0x1000,
+            s.c,
+            width = width
+        )
+    );
+}
+
+#[test]
+fn test_struct_optional_none() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(some = "Loaded", none = "<not yet loaded>")]
+        a: Option<StructWithoutDebug>,
+        #[debug_stub(none = "<unset>")]
+        b: Option<u8>,
+    }
+
+    assert_eq!(
+        format!("{:?}", TestStruct { a: None, b: None }),
+        "TestStruct { a: <not yet loaded>, b: <unset> }"
+    );
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestStruct {
+                a: Some(StructWithoutDebug),
+                b: Some(42)
+            }
+        ),
+        "TestStruct { a: Some(Loaded), b: Some(42) }"
+    );
+}
+
+trait AltDebug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+#[test]
+fn test_struct_trait() {
+    #[derive(DebugStub)]
+    #[debug_stub(trait = "AltDebug")]
+    struct TestStruct<T> {
+        a: T,
+        #[debug_stub = "ReplacementValue"]
+        #[allow(dead_code)]
+        b: StructWithoutDebug,
+    }
+
+    impl<T> fmt::Debug for TestStruct<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("TestStruct { .. }")
+        }
+    }
+
+    let s = TestStruct {
+        a: 42,
+        b: StructWithoutDebug,
+    };
+
+    assert_eq!(format!("{:?}", s), "TestStruct { .. }");
+    assert_eq!(
+        format!("{:?}", s.debug_stub()),
+        "TestStruct { a: 42, b: ReplacementValue }"
+    );
+    assert_eq!(
+        format!("{:#?}", s.debug_stub()),
+        r#"TestStruct {
+    a: 42,
+    b: ReplacementValue,
+}"#
+    );
+}
+
+mod ffi {
+    pub struct Handle;
+    pub struct Buffer<T>(pub T);
+    pub mod sys {
+        pub struct Context;
+    }
+}
+
+#[test]
+fn test_struct_types() {
+    #[derive(DebugStub)]
+    #[debug_stub(types("ffi::sys::*" = "<sys>", "ffi::*" = "<ffi>", "*Debug" = "<no debug>"))]
+    #[allow(dead_code)]
+    struct TestStruct<'a> {
+        a: ffi::Handle,
+        b: &'a ffi::Buffer<u8>,
+        c: ffi::sys::Context,
+        d: StructWithoutDebug,
+        #[debug_stub = "ReplacementValue"]
+        e: ffi::Handle,
+        f: u8,
+    }
+
+    let s = TestStruct {
+        a: ffi::Handle,
+        b: &ffi::Buffer(0),
+        c: ffi::sys::Context,
+        d: StructWithoutDebug,
+        e: ffi::Handle,
+        f: 42,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <ffi>, b: <ffi>, c: <sys>, d: <no debug>, e: ReplacementValue, f: 42 }"
+    );
+}
+
+#[test]
+fn test_struct_nested_optional_result() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(some(ok = "Foreign", err = "ForeignErr"))]
+        a: Option<Result<StructWithoutDebug, StructWithoutDebug>>,
+        #[debug_stub(ok(some = "Foreign", none = "<empty>"))]
+        b: Result<Option<StructWithoutDebug>, u8>,
+        #[debug_stub(some(len))]
+        c: Option<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: Some(Err(StructWithoutDebug)),
+        b: Ok(None),
+        c: Some(vec![1, 2, 3]),
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Some(Err(ForeignErr)), b: Ok(<empty>), c: Some(Vec(len = 3)) }"
+    );
+
+    let s = TestStruct {
+        a: Some(Ok(StructWithoutDebug)),
+        b: Ok(Some(StructWithoutDebug)),
+        c: None,
+    };
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: Some(
+        Ok(
+            Foreign,
+        ),
+    ),
+    b: Ok(
+        Some(
+            Foreign,
+        ),
+    ),
+    c: None,
+}"#
+    );
+}
+
+#[test]
+fn test_struct_each() {
+    #[derive(DebugStub)]
+    struct TestStruct<'a> {
+        #[debug_stub(each = "X")]
+        a: Vec<StructWithoutDebug>,
+        #[debug_stub(each = "X")]
+        b: [StructWithoutDebug; 2],
+        #[debug_stub(each = "X")]
+        c: &'a [StructWithoutDebug],
+        #[debug_stub(each(some = "X"))]
+        d: Vec<Option<StructWithoutDebug>>,
+    }
+
+    let s = TestStruct {
+        a: vec![StructWithoutDebug, StructWithoutDebug, StructWithoutDebug],
+        b: [StructWithoutDebug, StructWithoutDebug],
+        c: &[],
+        d: vec![Some(StructWithoutDebug), None],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: [X, X, X], b: [X, X], c: [], d: [Some(X), None] }"
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: [
+        X,
+        X,
+        X,
+    ],
+    b: [
+        X,
+        X,
+    ],
+    c: [],
+    d: [
+        Some(
+            X,
+        ),
+        None,
+    ],
+}"#
+    );
+}
+
+#[test]
+fn test_struct_redactor() {
+    use std::borrow::Cow;
+
+    fn redact<'a>(field: &str, text: &'a str) -> Cow<'a, str> {
+        if field.contains("email") || text.contains('@') {
+            Cow::Owned("<pii>".to_string())
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(redactor = "redact")]
+    struct TestStruct {
+        id: u32,
+        email: String,
+        #[debug_stub(each = "x")]
+        notes: Vec<&'static str>,
+        contact: Option<String>,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(redactor = "redact")]
+    enum TestEnum {
+        Login(u32, String),
+    }
+
+    let s = TestStruct {
+        id: 1,
+        email: "a@example.com".to_string(),
+        notes: vec!["n"],
+        contact: Some("b@example.com".to_string()),
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { id: 1, email: <pii>, notes: [x], contact: <pii> }"
+    );
+
+    let e = TestEnum::Login(2, "c@example.com".to_string());
+    assert_eq!(format!("{:?}", e), "Login(2, <pii>)");
+}
+
+#[test]
+fn test_struct_redactor_other_outputs() {
+    use std::borrow::Cow;
+
+    fn redact<'a>(field: &str, text: &'a str) -> Cow<'a, str> {
+        match field {
+            "pw" | "1" => Cow::Borrowed("***"),
+            _ => Cow::Borrowed(text),
+        }
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(redactor = "redact", debug_fields)]
+    #[cfg_attr(feature = "dot", debug_stub(dot))]
+    struct TestStruct {
+        #[debug_stub(brief)]
+        user: &'static str,
+        #[debug_stub(brief)]
+        pw: String,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(redactor = "redact", debug_fields)]
+    enum TestEnum {
+        Login(#[debug_stub(brief)] u32, #[debug_stub(brief)] String),
+    }
+
+    let s = TestStruct {
+        user: "root",
+        pw: "hunter2".to_string(),
+    };
+    assert_eq!(s.brief().to_string(), r#"TestStruct user="root" pw=***"#);
+    assert_eq!(
+        s.debug_fields(),
+        vec![("user", "\"root\"".to_string()), ("pw", "***".to_string())]
+    );
+    #[cfg(feature = "dot")]
+    assert!(
+        s.to_dot().contains("node2 [label=\"***\"]"),
+        "{}",
+        s.to_dot()
+    );
+
+    let e = TestEnum::Login(2, "hunter2".to_string());
+    assert_eq!(e.brief().to_string(), "Login 0=2 1=***");
+    assert_eq!(
+        e.debug_fields(),
+        vec![("0", "2".to_string()), ("1", "***".to_string())]
+    );
+}
+
+#[test]
+fn test_struct_max_output() {
+    #[derive(DebugStub)]
+    #[debug_stub(max_output = 20)]
+    struct TestStruct {
+        #[debug_stub(priority = 2)]
+        id: u32,
+        #[debug_stub(priority = 1)]
+        items: Vec<u8>,
+        payload: String,
+        trace: String,
+    }
+
+    let s = TestStruct {
+        id: 42,
+        items: vec![1, 2, 3],
+        payload: "short".to_string(),
+        trace: "t".to_string(),
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { id: 42, items: [1, 2, 3], payload: "short", trace: … }"#
+    );
+
+    let s = TestStruct {
+        payload: "a much longer payload".to_string(),
+        ..s
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { id: 42, items: [1, 2, 3], payload: …, trace: … }"
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        "TestStruct {\n    id: 42,\n    items: …,\n    payload: …,\n    trace: …,\n}"
+    );
+
+    let s = TestStruct {
+        items: vec![0; 1000],
+        ..s
+    };
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { id: 42, items: …, payload: …, trace: … }"
+    );
+}
+
+#[test]
+fn test_struct_mark_used() {
+    // no `#[allow(dead_code)]` needed for `b` and `1`
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used)]
+    struct TestStruct {
+        a: u8,
+        #[debug_stub = "ReplacementValue"]
+        b: StructWithoutDebug,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used)]
+    struct TestTupleStruct(u8, #[debug_stub(type_name)] StructWithoutDebug);
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestStruct {
+                a: 42,
+                b: StructWithoutDebug
+            }
+        ),
+        "TestStruct { a: 42, b: ReplacementValue }"
+    );
+    assert_eq!(
+        format!("{:?}", TestTupleStruct(42, StructWithoutDebug)),
+        "TestTupleStruct(42, lib::StructWithoutDebug)"
+    );
+}
+
+#[test]
+fn test_struct_map() {
+    use std::collections::BTreeMap;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(value = "X")]
+        a: BTreeMap<u8, StructWithoutDebug>,
+        #[debug_stub(key = "K", value = "V")]
+        b: BTreeMap<u8, StructWithoutDebug>,
+        #[debug_stub(key(len))]
+        c: BTreeMap<Vec<u8>, bool>,
+    }
+
+    let s = TestStruct {
+        a: vec![(1, StructWithoutDebug), (2, StructWithoutDebug)]
+            .into_iter()
+            .collect(),
+        b: vec![(1, StructWithoutDebug)].into_iter().collect(),
+        c: vec![(vec![1, 2], true)].into_iter().collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: {1: X, 2: X}, b: {K: V}, c: {Vec(len = 2): true} }"
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: {
+        1: X,
+        2: X,
+    },
+    b: {
+        K: V,
+    },
+    c: {
+        Vec(len = 2): true,
+    },
+}"#
+    );
+}
+
+#[test]
+fn test_struct_set() {
+    use std::collections::BTreeSet;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(set = "X")]
+        a: BTreeSet<u8>,
+        #[debug_stub(set(hash))]
+        b: BTreeSet<u64>,
+    }
+
+    let s = TestStruct {
+        a: vec![1, 2, 3].into_iter().collect(),
+        b: vec![42].into_iter().collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: {X, X, X}, b: {#c8b30784} }"
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: {
+        X,
+        X,
+        X,
+    },
+    b: {
+        #c8b30784,
+    },
+}"#
+    );
+}
+
+#[test]
+fn test_struct_fingerprint() {
+    mod v1 {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub struct TestStruct {
+            a: u8,
+            #[debug_stub = "ReplacementValue"]
+            b: StructWithoutDebug,
+        }
+    }
+
+    mod v1_again {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub struct TestStruct {
+            a: u8,
+            #[debug_stub = "ReplacementValue"]
+            b: StructWithoutDebug,
+        }
+    }
+
+    mod v2 {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub struct TestStruct {
+            a: u8,
+            #[debug_stub = "OtherReplacementValue"]
+            b: StructWithoutDebug,
+        }
+    }
+
+    mod v3 {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub struct TestStruct(u8, #[debug_stub = "ReplacementValue"] StructWithoutDebug);
+    }
+
+    assert_eq!(
+        v1::TestStruct::DEBUG_FORMAT_FINGERPRINT,
+        v1_again::TestStruct::DEBUG_FORMAT_FINGERPRINT
+    );
+    assert_ne!(
+        v1::TestStruct::DEBUG_FORMAT_FINGERPRINT,
+        v2::TestStruct::DEBUG_FORMAT_FINGERPRINT
+    );
+    assert_ne!(
+        v1::TestStruct::DEBUG_FORMAT_FINGERPRINT,
+        v3::TestStruct::DEBUG_FORMAT_FINGERPRINT
+    );
+    // downstream crates pin fingerprints, so they must not change between releases
+    assert_eq!(
+        v1::TestStruct::DEBUG_FORMAT_FINGERPRINT,
+        11_265_180_512_530_196_388
+    );
+}
+
+#[test]
+fn test_struct_err_passthrough() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct HugeError {
+        code: u32,
+        details: &'static str,
+    }
+
+    impl fmt::Display for HugeError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "error {}", self.code)
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(ok = "Foreign", err_max_len = 20)]
+        a: Result<StructWithoutDebug, HugeError>,
+        #[debug_stub(ok = "Foreign", err_display)]
+        b: Result<StructWithoutDebug, HugeError>,
+        #[debug_stub(ok = "Foreign", err_max_len = 7, err_display)]
+        c: Result<StructWithoutDebug, HugeError>,
+        #[debug_stub(ok = "Foreign", err_max_len = 3)]
+        d: Result<StructWithoutDebug, &'static str>,
+    }
+
+    let error = || HugeError {
+        code: 1234,
+        details: "many lines",
+    };
+    let s = TestStruct {
+        a: Err(error()),
+        b: Err(error()),
+        c: Err(error()),
+        d: Err("ab"),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: Err(HugeError { code: 12…), b: Err(error 1234), c: Err(error 1…), d: Err("ab…) }"#
+    );
+    assert_eq!(
+        format!(
+            "{:#?}",
+            TestStruct {
+                a: Err(error()),
+                ..s
+            }
+        ),
+        r#"TestStruct {
+    a: Err(
+        HugeError {
+            code…,
+    ),
+    b: Err(
+        error 1234,
+    ),
+    c: Err(
+        error 1…,
+    ),
+    d: Err(
+        "ab…,
+    ),
+}"#
+    );
+}
+
+#[test]
+fn test_struct_smart_pointer() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(DebugStub)]
+    #[allow(dead_code)]
+    struct TestStruct {
+        #[debug_stub(boxed = "ReplacementValue")]
+        a: Box<StructWithoutDebug>,
+        #[debug_stub(rc(some = "ReplacementValue"))]
+        b: Rc<Option<StructWithoutDebug>>,
+        #[debug_stub(arc(len))]
+        c: Arc<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: Box::new(StructWithoutDebug),
+        b: Rc::new(Some(StructWithoutDebug)),
+        c: Arc::new(vec![1, 2, 3]),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Box(ReplacementValue), b: Rc(Some(ReplacementValue)), c: Arc(Vec(len = 3)) }"
+    );
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: Box(
+        ReplacementValue,
+    ),
+    b: Rc(
+        Some(
+            ReplacementValue,
+        ),
+    ),
+    c: Arc(
+        Vec(len = 3),
+    ),
+}"#
+    );
+}
+
+#[test]
+fn test_struct_mutex() {
+    use std::sync::Mutex;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(mutex)]
+        a: Mutex<u8>,
+        #[debug_stub(mutex = "ReplacementValue")]
+        b: Mutex<StructWithoutDebug>,
+        #[debug_stub(mutex(len))]
+        c: Mutex<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: Mutex::new(42),
+        b: Mutex::new(StructWithoutDebug),
+        c: Mutex::new(vec![1, 2, 3]),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Mutex(42), b: Mutex(ReplacementValue), c: Mutex(Vec(len = 3)) }"
+    );
+
+    let guard = s.a.lock().unwrap();
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: Mutex(
+        <locked>,
+    ),
+    b: Mutex(
+        ReplacementValue,
+    ),
+    c: Mutex(
+        Vec(len = 3),
+    ),
+}"#
+    );
+    drop(guard);
+
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = s.c.lock().unwrap();
+        panic!("poison");
+    });
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Mutex(42), b: Mutex(ReplacementValue), c: Mutex(Vec(len = 3), <poisoned>) }"
+    );
+}
+
+#[test]
+fn test_struct_const_generic_array() {
+    const LEN: usize = 3;
+
+    #[derive(DebugStub)]
+    #[debug_stub(explain, fingerprint, debug_fields)]
+    struct TestStruct<T, const N: usize> {
+        #[debug_stub(len)]
+        a: [u8; N],
+        #[debug_stub(first = 2)]
+        b: [T; N],
+        #[debug_stub(each = "X")]
+        c: [StructWithoutDebug; 2 * LEN],
+        #[debug_stub(hash)]
+        d: [u64; LEN - 2],
+        e: [T; LEN],
+    }
+
+    let s = TestStruct {
+        a: [0; 4],
+        b: [1, 2, 3, 4],
+        c: [
+            StructWithoutDebug,
+            StructWithoutDebug,
+            StructWithoutDebug,
+            StructWithoutDebug,
+            StructWithoutDebug,
+            StructWithoutDebug,
+        ],
+        d: [42],
+        e: [5, 6, 7],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Array(len = 4), b: [1, 2, … and 2 more entries], \
+         c: [X, X, X, X, X, X], d: #d2cd0264, e: [5, 6, 7] }"
+    );
+    assert_eq!(
+        TestStruct::<u8, 4>::DEBUG_STUB_EXPLAIN,
+        "TestStruct\n  bounds: T: Debug\n  a: len\n  b: first = 2\n  c: each = \"X\"\n  d: hash\n  e: debug"
+    );
+    assert_eq!(
+        TestStruct::<u8, 4>::DEBUG_FORMAT_FINGERPRINT,
+        TestStruct::<u16, 8>::DEBUG_FORMAT_FINGERPRINT
+    );
+    assert_eq!(s.debug_fields()[4], ("e", "[5, 6, 7]".to_string()));
+}
+
+#[test]
+fn test_struct_bound() {
+    #[derive(DebugStub)]
+    #[debug_stub(ignore_generics, bound = "K: Debug", explain)]
+    #[allow(dead_code)]
+    struct TestStruct<K, V> {
+        a: K,
+        #[debug_stub = "ReplacementValue"]
+        b: V,
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(bound = "Vec<T>: Clone")]
+    #[debug_stub(bound = "T: PartialEq")]
+    struct TestTupleStruct<T>(Vec<T>);
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestStruct {
+                a: 42,
+                b: StructWithoutDebug
+            }
+        ),
+        "TestStruct { a: 42, b: ReplacementValue }"
+    );
+    assert_eq!(
+        TestStruct::<u8, StructWithoutDebug>::DEBUG_STUB_EXPLAIN,
+        "TestStruct\n  bounds: K: Debug\n  a: debug\n  b: replace = \"ReplacementValue\""
+    );
+    assert_eq!(
+        format!("{:?}", TestTupleStruct(vec![1])),
+        "TestTupleStruct([1])"
+    );
+}
+
+#[test]
+fn test_struct_rwlock() {
+    use std::sync::RwLock;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(rwlock)]
+        a: RwLock<u8>,
+        #[debug_stub(rwlock = "ReplacementValue")]
+        b: RwLock<StructWithoutDebug>,
+    }
+
+    let s = TestStruct {
+        a: RwLock::new(42),
+        b: RwLock::new(StructWithoutDebug),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: RwLock(42), b: RwLock(ReplacementValue) }"
+    );
+
+    let read_guard = s.a.read().unwrap();
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: RwLock(42), b: RwLock(ReplacementValue) }"
+    );
+    drop(read_guard);
+
+    let write_guard = s.b.write().unwrap();
+    assert_eq!(
+        format!("{:#?}", s),
+        r#"TestStruct {
+    a: RwLock(
+        42,
+    ),
+    b: RwLock(
+        <write-locked>,
+    ),
+}"#
+    );
+    drop(write_guard);
+}
+
+#[test]
+fn test_struct_pipeline() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(display, max_len = 10, mask(keep_start = 4))]
+        a: String,
+        #[debug_stub(mask(keep_end = 2), max_len = 4)]
+        b: String,
+        #[debug_stub(max_len = 3)]
+        c: Vec<u8>,
+        #[debug_stub(mask)]
+        d: u32,
+    }
+
+    let s = TestStruct {
+        a: "Bearer abcdefghijklmnop".to_string(),
+        b: "secret".to_string(),
+        c: vec![1, 2, 3, 4],
+        d: 1234,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Bear*******, b: ****…, c: [1,…, d: **** }"
+    );
+}
+
+#[test]
+fn test_struct_refcell() {
+    use std::cell::RefCell;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(refcell)]
+        a: RefCell<u8>,
+        #[debug_stub(refcell = "ReplacementValue")]
+        b: RefCell<StructWithoutDebug>,
+    }
+
+    let s = TestStruct {
+        a: RefCell::new(42),
+        b: RefCell::new(StructWithoutDebug),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: RefCell(42), b: RefCell(ReplacementValue) }"
+    );
+
+    let shared = s.a.borrow();
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: RefCell(42), b: RefCell(ReplacementValue) }"
+    );
+    drop(shared);
+
+    let exclusive = s.a.borrow_mut();
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: RefCell(<mutably borrowed>), b: RefCell(ReplacementValue) }"
+    );
+    drop(exclusive);
+}
+
+#[test]
+fn test_struct_on_error() {
+    struct Panicking;
+
+    impl Debug for Panicking {
+        fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
+            panic!("Panicking can't be printed");
+        }
+    }
+
+    struct Failing;
+
+    impl fmt::Display for Failing {
+        fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(on_error = "<panicked>")]
+        a: Panicking,
+        #[debug_stub(display, on_error = "<failed>")]
+        b: Failing,
+        #[debug_stub(len, on_error = "<failed>")]
+        c: Vec<u8>,
+    }
+
+    let s = TestStruct {
+        a: Panicking,
+        b: Failing,
+        c: vec![1, 2],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <panicked>, b: <failed>, c: Vec(len = 2) }"
+    );
+}
+
+#[test]
+fn test_struct_spill() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(spill(dir = "target/spill", over = 9))]
+        a: Vec<u8>,
+        #[debug_stub(spill(dir = "target/spill", over = 9))]
+        b: Vec<u8>,
+        #[debug_stub(spill(dir = "target/spill", over = 9))]
+        c: Vec<u8>,
+        #[debug_stub(len, spill(over = 12))]
+        d: Vec<u8>,
+    }
+
+    std::fs::create_dir_all("target/spill").unwrap();
+    let s = TestStruct {
+        a: vec![1, 2, 3],
+        b: vec![10, 20, 30],
+        c: vec![40, 50, 60],
+        d: vec![1, 2],
+    };
+
+    let output = format!("{:?}", s);
+    let prefix = "TestStruct { a: [1, 2, 3], b: <spilled to ";
+    assert!(output.starts_with(prefix), "{}", output);
+    let rest = &output[prefix.len()..];
+    let (b, rest) = rest.split_at(rest.find(">, c: <spilled to ").unwrap());
+    let c = &rest[">, c: <spilled to ".len()..rest.find(">, d:").unwrap()];
+    assert!(output.ends_with(">, d: Vec(len = 2) }"), "{}", output);
+    assert!(b.starts_with("target/spill/dbg-"), "{}", b);
+    assert_ne!(b, c);
+    assert_eq!(std::fs::read_to_string(b).unwrap(), "[10, 20, 30]");
+    assert_eq!(std::fs::read_to_string(c).unwrap(), "[40, 50, 60]");
+    std::fs::remove_file(b).unwrap();
+    std::fs::remove_file(c).unwrap();
+}
+
+#[test]
+fn test_struct_cell() {
+    use std::cell::Cell;
+
+    #[derive(Clone, Copy)]
+    struct CopyWithoutDebug;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(cell)]
+        a: Cell<u8>,
+        #[allow(dead_code)]
+        #[debug_stub(cell = "ReplacementValue")]
+        b: Cell<CopyWithoutDebug>,
+        #[debug_stub(cell(some = "ReplacementValue"))]
+        c: Cell<Option<CopyWithoutDebug>>,
+    }
+
+    let s = TestStruct {
+        a: Cell::new(42),
+        b: Cell::new(CopyWithoutDebug),
+        c: Cell::new(Some(CopyWithoutDebug)),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Cell(42), b: Cell(ReplacementValue), c: Cell(Some(ReplacementValue)) }"
+    );
+
+    s.a.set(7);
+    s.c.set(None);
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Cell(7), b: Cell(ReplacementValue), c: Cell(None) }"
+    );
+}
+
+#[test]
+fn test_struct_filter_keys() {
+    use std::collections::BTreeMap;
+
+    fn is_two(key: &u8) -> bool {
+        *key == 2
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(filter_keys = "user_")]
+        a: BTreeMap<String, u8>,
+        #[debug_stub(filter_keys_with = "is_two", value = "ReplacementValue")]
+        b: BTreeMap<u8, StructWithoutDebug>,
+        #[debug_stub(filter_keys = "user_")]
+        c: BTreeMap<&'static str, u8>,
+    }
+
+    let s = TestStruct {
+        a: vec![("db_url", 1), ("user_id", 2), ("user_name", 3), ("port", 4)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+        b: (1..=3).map(|key| (key, StructWithoutDebug)).collect(),
+        c: vec![("user_id", 1)].into_iter().collect(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { \
+            a: {\"user_id\": 2, \"user_name\": 3, … and 2 more entries}, \
+            b: {2: ReplacementValue, … and 2 more entries}, \
+            c: {\"user_id\": 1} \
+        }"
+    );
+}
+
+#[test]
+fn test_struct_weak() {
+    use std::rc::{self, Rc};
+    use std::sync::{self, Arc};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(weak)]
+        a: rc::Weak<StructWithoutDebug>,
+        #[debug_stub(weak = "ReplacementValue")]
+        b: sync::Weak<StructWithoutDebug>,
+        #[debug_stub(weak(len))]
+        c: rc::Weak<Vec<u8>>,
+    }
+
+    let a = Rc::new(StructWithoutDebug);
+    let b = Arc::new(StructWithoutDebug);
+    let c = Rc::new(vec![1, 2]);
+    let s = TestStruct {
+        a: Rc::downgrade(&a),
+        b: Arc::downgrade(&b),
+        c: Rc::downgrade(&c),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Weak(alive), b: Weak(ReplacementValue), c: Weak(Vec(len = 2)) }"
+    );
+
+    drop((a, b, c));
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Weak(dangling), b: Weak(dangling), c: Weak(dangling) }"
+    );
+}
+
+#[test]
+fn test_struct_counts() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(counts)]
+        a: Arc<u8>,
+        #[debug_stub(counts = "ReplacementValue")]
+        b: Rc<StructWithoutDebug>,
+        #[debug_stub(counts(len))]
+        c: Rc<[u8]>,
+    }
+
+    let s = TestStruct {
+        a: Arc::new(42),
+        b: Rc::new(StructWithoutDebug),
+        c: Rc::from(&[1, 2, 3][..]),
+    };
+    let (_a, _b, _weak) = (s.a.clone(), s.b.clone(), Rc::downgrade(&s.b));
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { \
+            a: Arc(strong = 2, weak = 0, 42), \
+            b: Rc(strong = 2, weak = 1, ReplacementValue), \
+            c: Rc(strong = 1, weak = 0, Slice(len = 3)) \
+        }"
+    );
+}
+
+#[test]
+fn test_struct_resolve() {
+    fn lookup(id: u64) -> Option<&'static str> {
+        match id {
+            42 => Some("Player"),
+            _ => None,
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct Entity(#[debug_stub(resolve = "lookup")] u64);
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(resolve = "lookup")]
+        a: u64,
+        b: Entity,
+    }
+
+    let s = TestStruct {
+        a: 7,
+        b: Entity(42),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: 7, b: Entity(42 "Player") }"#
+    );
+}
+
+#[test]
+fn test_struct_atomic() {
+    use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(atomic)]
+        a: AtomicU64,
+        #[debug_stub(atomic)]
+        b: AtomicBool,
+        #[debug_stub(atomic(be))]
+        c: AtomicU16,
+    }
+
+    let s = TestStruct {
+        a: AtomicU64::new(42),
+        b: AtomicBool::new(true),
+        c: AtomicU16::new(0x0100),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 42, b: true, c: 0x0100 (be: 1) }"
+    );
+
+    s.a.store(7, Ordering::SeqCst);
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 7, b: true, c: 0x0100 (be: 1) }"
+    );
+}
+
+#[test]
+fn test_struct_uninit() {
+    use std::mem::MaybeUninit;
+
+    #[derive(DebugStub)]
+    #[debug_stub(explain)]
+    #[allow(dead_code)]
+    struct TestStruct {
+        #[debug_stub(uninit)]
+        a: MaybeUninit<u8>,
+        #[debug_stub(uninit = "ReplacementValue")]
+        b: MaybeUninit<StructWithoutDebug>,
+        #[debug_stub(unsafe_assume_init)]
+        c: MaybeUninit<u8>,
+        #[debug_stub(unsafe_assume_init(len))]
+        d: MaybeUninit<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: MaybeUninit::uninit(),
+        b: MaybeUninit::uninit(),
+        c: MaybeUninit::new(42),
+        d: MaybeUninit::new(vec![1, 2]),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: <uninit>, b: ReplacementValue, c: 42, d: Vec(len = 2) }"
+    );
+    assert_eq!(
+        TestStruct::DEBUG_STUB_EXPLAIN,
+        "TestStruct\n  bounds: none\n  a: uninit\n  b: uninit = \"ReplacementValue\"\n  \
+         c: unsafe_assume_init\n  d: unsafe_assume_init(len)"
+    );
+
+    let TestStruct { mut d, .. } = s;
+    unsafe { d.assume_init_drop() };
+}
+
+#[test]
+fn test_struct_deref_to() {
+    use std::ops::Deref;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    struct Name(String);
+
+    impl Deref for Name {
+        type Target = String;
+
+        fn deref(&self) -> &String {
+            &self.0
+        }
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(deref_to = "str")]
+        a: Name,
+        #[debug_stub(deref_to = "Path")]
+        b: Arc<PathBuf>,
+        #[debug_stub(deref_to = "[u8]")]
+        c: Rc<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: Name("name".to_string()),
+        b: Arc::new(Path::new("/tmp").to_path_buf()),
+        c: Rc::new(vec![1, 2]),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: "name", b: "/tmp", c: [1, 2] }"#
+    );
+}
+
+#[test]
+fn test_struct_as_deref() {
+    use std::path::PathBuf;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(as_deref)]
+        a: Option<Box<u8>>,
+        #[debug_stub(as_deref, some(truncate = 3))]
+        b: Option<String>,
+        #[debug_stub(as_deref, some(len), none = "-")]
+        c: Option<Vec<u8>>,
+        #[debug_stub(as_deref, some(path))]
+        d: Option<PathBuf>,
+        #[debug_stub(as_deref, none = "-")]
+        e: Option<String>,
+    }
+
+    let s = TestStruct {
+        a: Some(Box::new(1)),
+        b: Some("abcdef".to_string()),
+        c: Some(vec![1, 2]),
+        d: Some(PathBuf::from("/tmp")),
+        e: None,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: Some(1), b: Some("abc…" (6 chars)), c: Some(Slice(len = 2)), d: Some(/tmp), e: - }"#
+    );
+}
+
+#[test]
+fn test_struct_once() {
+    use std::cell::OnceCell;
+    use std::sync::OnceLock;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(once)]
+        a: OnceCell<u8>,
+        #[debug_stub(once = "ReplacementValue")]
+        b: OnceLock<StructWithoutDebug>,
+        #[debug_stub(once(len))]
+        c: OnceLock<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: OnceCell::new(),
+        b: OnceLock::new(),
+        c: OnceLock::new(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Uninit, b: Uninit, c: Uninit }"
+    );
+
+    let _ = s.a.set(42);
+    let _ = s.b.set(StructWithoutDebug);
+    let _ = s.c.set(vec![1, 2]);
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: Init(42), b: Init(ReplacementValue), c: Init(Vec(len = 2)) }"
+    );
+}
+
+#[test]
+fn test_struct_cow() {
+    use std::borrow::Cow;
+
+    #[derive(Clone)]
+    struct CloneWithoutDebug;
+
+    #[derive(DebugStub)]
+    struct TestStruct<'a> {
+        #[debug_stub(cow)]
+        a: Cow<'a, str>,
+        #[debug_stub(cow)]
+        b: Cow<'a, str>,
+        #[debug_stub(cow = "ReplacementValue")]
+        c: Cow<'a, CloneWithoutDebug>,
+        #[debug_stub(cow(len))]
+        d: Cow<'a, [u8]>,
+    }
+
+    let s = TestStruct {
+        a: Cow::Borrowed("a"),
+        b: Cow::Owned("b".to_string()),
+        c: Cow::Owned(CloneWithoutDebug),
+        d: Cow::Borrowed(&[1, 2]),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: Borrowed("a"), b: Owned("b"), c: Owned(ReplacementValue), d: Borrowed(Slice(len = 2)) }"#
+    );
+}
+
+#[test]
+fn test_struct_dedup_key() {
+    #[derive(DebugStub)]
+    #[debug_stub(dedup_key)]
+    struct TestStruct {
+        a: u8,
+        #[debug_stub = "<timestamp>"]
+        #[allow(dead_code)]
+        b: u64,
+    }
+
+    let key = TestStruct { a: 1, b: 100 }.debug_dedup_key();
+    assert_eq!(key, TestStruct { a: 1, b: 200 }.debug_dedup_key());
+    assert_ne!(key, TestStruct { a: 2, b: 100 }.debug_dedup_key());
+
+    // FNV-1a of the output, which does not depend on the Rust version
+    let output = format!("{:?}", TestStruct { a: 1, b: 100 });
+    let expected = output
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    assert_eq!(key, expected);
+}
+
+#[test]
+fn test_struct_duration() {
+    use std::time::Duration;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(duration)]
+        a: Duration,
+        #[debug_stub(duration)]
+        b: Duration,
+        #[debug_stub(duration)]
+        c: Duration,
+        #[debug_stub(duration)]
+        d: Duration,
+        #[debug_stub(duration)]
+        e: Duration,
+        #[debug_stub(duration, max_len = 3)]
+        f: Duration,
+    }
+
+    let s = TestStruct {
+        a: Duration::from_millis(1500),
+        b: Duration::from_millis(320),
+        c: Duration::from_nanos(12_345),
+        d: Duration::from_nanos(7),
+        e: Duration::from_secs(3600),
+        f: Duration::from_micros(1_234_567),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 1.5s, b: 320ms, c: 12.345µs, d: 7ns, e: 3600s, f: 1.2… }"
+    );
+}
+
+#[test]
+fn test_struct_elapsed() {
+    use std::time::{Duration, Instant};
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(elapsed)]
+        started: Instant,
+    }
+
+    let s = TestStruct {
+        started: Instant::now() - Duration::from_secs(42),
+    };
+
+    let output = format!("{:?}", s);
+    assert!(output.starts_with("TestStruct { started: 42"), "{}", output);
+    assert!(output.ends_with("s ago }"), "{}", output);
+}
+
+#[test]
+fn test_struct_counter() {
+    use std::cell::Cell;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        a: u8,
+        #[debug_stub(counter)]
+        b: Cell<u32>,
+        #[debug_stub(counter)]
+        c: AtomicUsize,
+    }
+
+    let s = TestStruct {
+        a: 1,
+        b: Cell::new(0),
+        c: AtomicUsize::new(13),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 1, b: formatted 1 time, c: formatted 14 times }"
+    );
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 1, b: formatted 2 times, c: formatted 15 times }"
+    );
+}
+
+#[test]
+#[deny(trivial_numeric_casts)]
+fn test_struct_bits() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(bits(format = "vvvv_ffff", v = "version", f = "flags:b"))]
+        a: u8,
+        #[debug_stub(bits(format = "ab--_cccc_cccc_cccc"))]
+        b: u16,
+        #[debug_stub(bits(format = "hhhh_llll", h = "high", l = "low"))]
+        c: u128,
+    }
+
+    let s = TestStruct {
+        a: 0b0011_1010,
+        b: 0b1000_0000_0000_0101,
+        c: 0x2f,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: version=3 flags=0b1010, b: a=1 b=0 c=5, c: high=2 low=15 }"
+    );
+}
+
+#[test]
+fn test_struct_variant_counts() {
+    #[derive(DebugStub)]
+    #[debug_stub(variant_index)]
+    #[allow(dead_code)]
+    enum Event {
+        Click { x: u8, y: u8 },
+        Key(char),
+        Quit,
+    }
+
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(variant_counts)]
+        a: Vec<Event>,
+        #[debug_stub(variant_counts)]
+        b: [Event; 2],
+    }
+
+    let s = TestStruct {
+        a: vec![
+            Event::Key('a'),
+            Event::Click { x: 1, y: 2 },
+            Event::Key('b'),
+        ],
+        b: [Event::Quit, Event::Quit],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: {Click: 1, Key: 2, Quit: 0}, b: {Click: 0, Key: 0, Quit: 2} }"
+    );
+}
+
+#[test]
+fn test_struct_path() {
+    use std::ffi::{OsStr, OsString};
+    use std::path::{Path, PathBuf};
+
+    #[derive(DebugStub)]
+    struct TestStruct<'a> {
+        #[debug_stub(path)]
+        a: PathBuf,
+        #[debug_stub(path)]
+        b: &'a Path,
+        #[debug_stub(path)]
+        c: OsString,
+        #[debug_stub(path)]
+        d: &'a OsStr,
+    }
+
+    let s = TestStruct {
+        a: PathBuf::from(r#"/var/log/"quoted".log"#),
+        b: Path::new("relative/dir"),
+        c: OsString::from("file.txt"),
+        d: OsStr::new("tab\there"),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: /var/log/\"quoted\".log, b: relative/dir, c: file.txt, d: tab\there }"
+    );
+}
+
+#[test]
+fn test_struct_pure() {
+    #[derive(DebugStub)]
+    #[debug_stub(pure)]
+    struct TestStruct {
+        a: u8,
+        #[debug_stub(some = "ReplacementValue")]
+        b: Option<StructWithoutDebug>,
+        #[debug_stub(each(len))]
+        c: Vec<Vec<u8>>,
+    }
+
+    let s = TestStruct {
+        a: 42,
+        b: None,
+        c: vec![vec![1]],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 42, b: None, c: [Vec(len = 1)] }"
+    );
+}
+
+#[test]
+fn test_struct_c_str() {
+    use std::ffi::{CStr, CString};
+
+    #[derive(DebugStub)]
+    struct TestStruct<'a> {
+        #[debug_stub(c_str)]
+        a: CString,
+        #[debug_stub(c_str)]
+        b: &'a CStr,
+        #[debug_stub(c_str)]
+        c: CString,
+    }
+
+    let s = TestStruct {
+        a: CString::new("text").unwrap(),
+        b: CStr::from_bytes_with_nul(b"quote\"\0").unwrap(),
+        c: CString::new(vec![0xff, b'a', b'\n']).unwrap(),
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: "text", b: "quote\"", c: b"\xffa\n" }"#
+    );
+}
+
+#[test]
+#[deny(trivial_numeric_casts)]
+fn test_struct_bytes() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(bytes)]
+        a: u64,
+        #[debug_stub(bytes)]
+        b: usize,
+        #[debug_stub(bytes)]
+        c: u32,
+        #[debug_stub(bytes)]
+        d: u64,
+        #[debug_stub(bytes)]
+        e: i32,
+        #[debug_stub(bytes)]
+        f: i64,
+        #[debug_stub(bytes)]
+        g: u128,
+    }
+
+    let s = TestStruct {
+        a: 1_509_949,
+        b: 512,
+        c: 1024,
+        d: 3 << 40,
+        e: -1,
+        f: -3 << 20,
+        g: u128::MAX,
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        "TestStruct { a: 1.44 MiB, b: 512 B, c: 1.00 KiB, d: 3.00 TiB, e: -1 B, f: -3.00 MiB, \
+         g: 295147905179352825856.00 EiB }"
+    );
+}
+
+#[test]
+fn test_struct_any_of() {
+    #[derive(DebugStub)]
+    #[debug_stub(ignore_generics, bound = "T: 'static")]
+    struct TestStruct<T> {
+        #[debug_stub(any_of(u32, String), else = "ReplacementValue")]
+        a: T,
+    }
+
+    assert_eq!(
+        format!("{:?}", TestStruct { a: 42_u32 }),
+        "TestStruct { a: 42 }"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestStruct {
+                a: "text".to_string()
+            }
+        ),
+        r#"TestStruct { a: "text" }"#
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestStruct {
+                a: StructWithoutDebug
+            }
+        ),
+        "TestStruct { a: ReplacementValue }"
+    );
+}
+
+#[test]
+fn test_struct_precision() {
+    #[derive(DebugStub)]
+    struct TestStruct {
+        #[debug_stub(precision = 3)]
+        a: f64,
+        #[debug_stub(precision = 3)]
+        b: Option<(f32, f32, &'static str)>,
+        #[debug_stub(some(each = "ReplacementValue"), precision = 1)]
+        c: Option<Vec<StructWithoutDebug>>,
+        #[debug_stub(precision = 1, max_len = 6)]
+        d: Vec<f64>,
+    }
+
+    let s = TestStruct {
+        a: 1.0 / 3.0,
+        b: Some((1.0, 2.0 / 3.0, "text")),
+        c: Some(vec![StructWithoutDebug]),
+        d: vec![0.25, 1.75],
+    };
+
+    assert_eq!(
+        format!("{:?}", s),
+        r#"TestStruct { a: 0.333, b: Some((1.000, 0.667, "text")), c: Some([ReplacementValue]), d: [0.2, … }"#
+    );
+}
+
+// Enum Tests -----------------------------------------------------------------
+
+#[test]
+fn test_enum_empty() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantC {},
+        VariantD,
+    }
+
+    assert_eq!(format!("{:?}", TestEnum::VariantC {}), "VariantC");
+
+    assert_eq!(format!("{:?}", TestEnum::VariantC {}), "VariantC");
+
+    assert_eq!(format!("{:#?}", TestEnum::VariantD), "VariantD");
+
+    assert_eq!(format!("{:#?}", TestEnum::VariantD), "VariantD");
+}
+
+#[test]
+fn test_enum_compare_std_empty() {
+    mod a {
+        use debug_stub_derive::DebugStub;
+
+        #[derive(DebugStub)]
+        pub enum TestEnum {
+            VariantA,
+            VariantB {},
+        }
+    }
+
+    mod b {
+        #[derive(Debug)]
+        pub enum TestEnum {
+            VariantA,
+            VariantB {},
+        }
+    }
+
+    assert_eq!(
+        format!("{:?}", a::TestEnum::VariantA),
+        format!("{:?}", b::TestEnum::VariantA)
+    );
+
+    assert_eq!(
+        format!("{:#?}", a::TestEnum::VariantA),
+        format!("{:#?}", b::TestEnum::VariantA)
+    );
+
+    assert_eq!(
+        format!("{:?}", a::TestEnum::VariantB {}),
+        format!("{:?}", b::TestEnum::VariantB {})
+    );
+
+    assert_eq!(
+        format!("{:#?}", a::TestEnum::VariantB {}),
+        format!("{:#?}", b::TestEnum::VariantB {})
+    );
+}
+
+#[test]
+fn test_enum() {
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct StructWithDebug {
+        number: u64,
+    }
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(
+            StructWithDebug,
+            #[debug_stub = "StructWithoutDebugReplaceValue"] StructWithoutDebug,
+            bool,
+        ),
+        VariantB {
+            a: StructWithDebug,
+            #[allow(dead_code)]
+            #[debug_stub = "StructWithoutDebugReplaceValue"]
+            b: StructWithoutDebug,
+            c: bool,
+        },
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantA(StructWithDebug { number: 42 }, StructWithoutDebug, true)
+        ),
+        "VariantA(StructWithDebug { number: 42 }, StructWithoutDebugReplaceValue, true)"
+    );
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantB {
+            a: StructWithDebug {
+                number: 42
+            },
+            b: StructWithoutDebug,
+            c: true
+
+        }), "VariantB { a: StructWithDebug { number: 42 }, b: StructWithoutDebugReplaceValue, c: true }"
+    );
+}
+
+#[test]
+fn test_enum_dyn_fields() {
+    trait Trait: Debug {}
+
+    #[derive(Debug)]
+    struct TraitImpl;
+
+    impl Trait for TraitImpl {}
+
+    #[derive(DebugStub)]
+    enum Enum<'a> {
+        A(
+            Box<dyn Trait>,
+            #[allow(dead_code)]
+            #[debug_stub = "_"]
+            Box<dyn Trait>,
+            &'a dyn Trait,
+            #[allow(dead_code)]
+            #[debug_stub = "_"]
+            &'a dyn Trait,
+        ),
+        B {
+            a: Box<dyn Trait>,
+            #[allow(dead_code)]
+            #[debug_stub = "_"]
+            b: Box<dyn Trait>,
+            c: &'a dyn Trait,
+            #[allow(dead_code)]
+            #[debug_stub = "_"]
+            d: &'a dyn Trait,
+        },
+    }
+
+    let trait_impl = TraitImpl;
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            Enum::A(
+                Box::new(TraitImpl),
+                Box::new(TraitImpl),
+                &trait_impl,
+                &trait_impl,
+            ),
+        ),
+        "A(TraitImpl, _, TraitImpl, _)",
+    );
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            Enum::B {
+                a: Box::new(TraitImpl),
+                b: Box::new(TraitImpl),
+                c: &trait_impl,
+                d: &trait_impl,
+            },
+        ),
+        "B { a: TraitImpl, b: _, c: TraitImpl, d: _ }",
+    );
+}
+
+#[test]
+fn test_enum_compare_std() {
+    mod a {
+        use debug_stub_derive::DebugStub;
+
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub struct InternalStruct {
+            pub a: bool,
+        }
+
+        #[derive(DebugStub)]
+        pub enum TestEnum {
+            VariantA(InternalStruct, bool, u64),
+            VariantB { a: InternalStruct, b: bool, c: u64 },
+        }
+    }
+
+    mod b {
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub struct InternalStruct {
+            pub a: bool,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub enum TestEnum {
+            VariantA(InternalStruct, bool, u64),
+            VariantB { a: InternalStruct, b: bool, c: u64 },
+        }
+    }
+
+    let enum_a_a = a::TestEnum::VariantA(a::InternalStruct { a: true }, false, 42);
+
+    let enum_a_b = a::TestEnum::VariantB {
+        a: a::InternalStruct { a: true },
+        b: false,
+        c: 42,
+    };
+
+    let enum_b_a = b::TestEnum::VariantA(b::InternalStruct { a: true }, false, 42);
+
+    let enum_b_b = b::TestEnum::VariantB {
+        a: b::InternalStruct { a: true },
+        b: false,
+        c: 42,
+    };
+
+    assert_eq!(format!("{:?}", enum_a_a), format!("{:?}", enum_b_a));
+
+    assert_eq!(format!("{:#?}", enum_a_a), format!("{:#?}", enum_b_a));
+
+    assert_eq!(format!("{:?}", enum_a_b), format!("{:?}", enum_b_b));
+
+    assert_eq!(format!("{:#?}", enum_a_b), format!("{:#?}", enum_b_b));
+}
+
+#[test]
+fn test_enum_with_type_parameters() {
+    use std::fmt::{Debug, Display};
+
+    #[derive(DebugStub)]
+    enum TestEnum<T: Display + Debug> {
+        VariantA(T),
+    }
+
+    assert_eq!(format!("{:?}", TestEnum::VariantA(true)), "VariantA(true)");
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(true)),
+        r#"VariantA(
+    true,
+)"#
+    );
+}
+
+#[test]
+fn test_enum_with_type_where_clause() {
+    use std::fmt::{Debug, Display};
+
+    #[derive(DebugStub)]
+    enum TestEnum<T>
+    where
+        T: Display + Debug,
+    {
+        VariantA(T),
+    }
+
+    assert_eq!(format!("{:?}", TestEnum::VariantA(true)), "VariantA(true)");
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(true)),
+        r#"VariantA(
+    true,
+)"#
+    );
+}
+
+#[test]
+fn test_enum_optional() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(some = "StructWithoutDebugReplaceValue")] Option<StructWithoutDebug>),
+    }
+
+    assert_eq!(format!("{:?}", TestEnum::VariantA(None)), "VariantA(None)");
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(None)),
+        r#"VariantA(
+    None,
+)"#
+    );
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Some(StructWithoutDebug))),
+        "VariantA(Some(StructWithoutDebugReplaceValue))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Some(StructWithoutDebug))),
+        r#"VariantA(
+    Some(
+        StructWithoutDebugReplaceValue,
+    ),
+)"#
+    );
+}
+
+#[test]
+fn test_enum_result_both() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(
+            #[debug_stub(
+                ok = "StructWithoutDebugReplaceValue",
+                err = "ErrorWithoutDebugReplaceValue"
+            )]
+            Result<StructWithoutDebug, StructWithoutDebug>,
+        ),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Err(StructWithoutDebug))),
+        "VariantA(Err(ErrorWithoutDebugReplaceValue))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Err(StructWithoutDebug))),
+        r#"VariantA(
+    Err(
+        ErrorWithoutDebugReplaceValue,
+    ),
+)"#
+    );
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
+        "VariantA(Ok(StructWithoutDebugReplaceValue))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
+        r#"VariantA(
+    Ok(
+        StructWithoutDebugReplaceValue,
+    ),
+)"#
+    );
+}
+
+#[test]
+fn test_enum_result_ok() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(
+            #[debug_stub(ok = "StructWithoutDebugReplaceValue")] Result<StructWithoutDebug, String>,
+        ),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
+        "VariantA(Ok(StructWithoutDebugReplaceValue))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
+        r#"VariantA(
+    Ok(
+        StructWithoutDebugReplaceValue,
+    ),
+)"#
+    );
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Err("Foo".to_string()))),
+        "VariantA(Err(\"Foo\"))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Err("Foo".to_string()))),
+        r#"VariantA(
+    Err(
+        "Foo",
+    ),
+)"#
+    );
+}
+
+#[test]
+fn test_enum_result_err() {
+    struct ErrorWithoutDebug;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(
+            #[debug_stub(err = "ErrorWithoutDebugReplaceValue")] Result<String, ErrorWithoutDebug>,
+        ),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Err(ErrorWithoutDebug))),
+        "VariantA(Err(ErrorWithoutDebugReplaceValue))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Err(ErrorWithoutDebug))),
+        r#"VariantA(
+    Err(
+        ErrorWithoutDebugReplaceValue,
+    ),
+)"#
+    );
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Ok("Foo".to_string()))),
+        "VariantA(Ok(\"Foo\"))"
+    );
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(Ok("Foo".to_string()))),
+        r#"VariantA(
+    Ok(
+        "Foo",
+    ),
+)"#
+    );
+}
+
+#[test]
+fn test_enum_optional_compare_std() {
+    mod a {
+        use debug_stub_derive::DebugStub;
+
+        #[derive(DebugStub)]
+        pub enum TestEnum {
+            VariantA(Option<String>, Option<String>),
+            VariantB {
+                a: Option<String>,
+                b: Option<String>,
+            },
+        }
+    }
+
+    mod b {
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub enum TestEnum {
+            VariantA(Option<String>, Option<String>),
+            VariantB {
+                a: Option<String>,
+                b: Option<String>,
+            },
+        }
+    }
+
+    let enum_a_a = a::TestEnum::VariantA(None, Some("Foo".to_string()));
+    let enum_a_b = a::TestEnum::VariantB {
+        a: Some("Foo".to_string()),
+        b: None,
+    };
+
+    let enum_b_a = b::TestEnum::VariantA(None, Some("Foo".to_string()));
+    let enum_b_b = b::TestEnum::VariantB {
+        a: Some("Foo".to_string()),
+        b: None,
+    };
+
+    assert_eq!(format!("{:?}", enum_a_a), format!("{:?}", enum_b_a));
+
+    assert_eq!(format!("{:#?}", enum_a_a), format!("{:#?}", enum_b_a));
+
+    assert_eq!(format!("{:?}", enum_a_b), format!("{:?}", enum_b_b));
+
+    assert_eq!(format!("{:#?}", enum_a_b), format!("{:#?}", enum_b_b));
+}
+
+#[test]
+fn test_enum_result_compare_std() {
+    mod a {
+        use debug_stub_derive::DebugStub;
+
+        #[derive(DebugStub)]
+        pub enum TestEnum {
+            VariantA(Result<String, bool>, Result<String, bool>),
+            VariantB {
+                a: Result<String, bool>,
+                b: Result<String, bool>,
+            },
+        }
+    }
+
+    mod b {
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub enum TestEnum {
+            VariantA(Result<String, bool>, Result<String, bool>),
+            VariantB {
+                a: Result<String, bool>,
+                b: Result<String, bool>,
+            },
+        }
+    }
+
+    let enum_a_a = a::TestEnum::VariantA(Err(true), Ok("Foo".to_string()));
+    let enum_a_b = a::TestEnum::VariantB {
+        a: Ok("Foo".to_string()),
+        b: Err(true),
+    };
+
+    let enum_b_a = b::TestEnum::VariantA(Err(true), Ok("Foo".to_string()));
+    let enum_b_b = b::TestEnum::VariantB {
+        a: Ok("Foo".to_string()),
+        b: Err(true),
+    };
+
+    assert_eq!(format!("{:?}", enum_a_a), format!("{:?}", enum_b_a));
+
+    assert_eq!(format!("{:#?}", enum_a_a), format!("{:#?}", enum_b_a));
+
+    assert_eq!(format!("{:?}", enum_a_b), format!("{:?}", enum_b_b));
+
+    assert_eq!(format!("{:#?}", enum_a_b), format!("{:#?}", enum_b_b));
+}
+
+#[test]
+fn test_enum_generic() {
+    use std::marker::PhantomData;
+
+    #[derive(DebugStub)]
+    enum Enum<T> {
+        A(T),
+    }
+
+    #[derive(DebugStub)]
+    #[debug_stub(ignore_generics)]
+    struct B<T>(PhantomData<T>);
+
+    assert_eq!(format!("{:?}", Enum::A(5)), "A(5)");
+    assert_eq!(
+        format!("{:?}", B(PhantomData::<StructWithoutDebug>)),
+        format!("B({:?})", PhantomData::<StructWithoutDebug>)
+    );
+}
+
+#[test]
+fn test_enum_future_state() {
+    struct FutureWithoutDebug;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        Running(#[debug_stub(future_state = "TestEnum::is_done")] Option<FutureWithoutDebug>),
+        Finished {
+            #[debug_stub(future_state = "TestEnum::is_done")]
+            future: Option<FutureWithoutDebug>,
+        },
+    }
+
+    impl TestEnum {
+        fn is_done(&self) -> bool {
+            matches!(self, TestEnum::Finished { .. })
+        }
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::Running(None)),
+        "Running(NotStarted)"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::Running(Some(FutureWithoutDebug))),
+        "Running(Pending)"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::Finished { future: None }),
+        "Finished { future: Done }"
+    );
+}
+
+#[cfg(feature = "dot")]
+#[test]
+fn test_enum_dot() {
+    #[derive(DebugStub)]
+    #[debug_stub(dot)]
+    enum TestEnum {
+        VariantA(u64, #[debug_stub = "ReplaceValue"] StructWithoutDebug),
+        VariantB,
+    }
+
+    assert_eq!(
+        TestEnum::VariantA(42, StructWithoutDebug).to_dot(),
+        r#"digraph {
+    node0 [label="VariantA"];
+    node1 [label="42"];
+    node0 -> node1 [label="0"];
+    node2 [label="ReplaceValue"];
+    node0 -> node2 [label="1"];
+}
+"#
+    );
+
+    assert_eq!(
+        TestEnum::VariantB.to_dot(),
+        "digraph {\n    node0 [label=\"VariantB\"];\n}\n"
+    );
+}
+
+#[test]
+fn test_enum_shallow() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(shallow)] Option<Vec<u8>>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Some(vec![1, 2]))),
+        "VariantA(Some(..))"
+    );
+}
+
+#[test]
+fn test_enum_scientific() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(scientific = "E")] f64),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(-2.5e-10)),
+        "VariantA(-2.5E-10)"
+    );
+}
+
+#[test]
+fn test_enum_stable_layout() {
+    #[derive(DebugStub)]
+    #[debug_stub(stable_layout)]
+    enum TestEnum {
+        VariantA(u8, #[debug_stub = "Hidden"] StructWithoutDebug),
+        VariantB { a: Option<u8> },
+        VariantC,
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(1, StructWithoutDebug)),
+        "VariantA(1, Hidden)"
+    );
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantB { a: Some(2) }),
+        "VariantB {\n    a: Some(\n        2,\n    ),\n}"
+    );
+    assert_eq!(format!("{:#?}", TestEnum::VariantC), "VariantC");
+}
+
+#[test]
+fn test_enum_separators() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA {
+            #[debug_stub(separators = "'")]
+            rows: usize,
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA { rows: 1_000_000 }),
+        "VariantA { rows: 1'000'000 }"
+    );
+}
+
+#[test]
+fn test_enum_opaque_generics() {
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used)]
+    enum TestEnum<T, U> {
+        VariantA(#[debug_stub = "Callback"] Box<dyn FnMut(T) + Send>),
+        VariantB(U),
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::<StructWithoutDebug, u8>::VariantA(Box::new(|_| {}))
+        ),
+        "VariantA(Callback)"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::<StructWithoutDebug, u8>::VariantB(2)),
+        "VariantB(2)"
+    );
+}
+
+#[test]
+fn test_enum_method() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(method = "len")] String),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA("abc".to_string())),
+        "VariantA(3)"
+    );
+}
+
+#[test]
+fn test_enum_getter() {
+    fn first(values: &[u8]) -> Option<&u8> {
+        values.first()
+    }
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA {
+            #[debug_stub(getter = "first")]
+            values: Vec<u8>,
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA { values: vec![5, 6] }),
+        "VariantA { values: Some(5) }"
+    );
+}
+
+#[test]
+fn test_enum_expr() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA {
+            #[debug_stub(expr = "items.len()")]
+            items: Vec<StructWithoutDebug>,
+            #[debug_stub = "Hidden"]
+            other: StructWithoutDebug,
+        },
+        VariantB(#[debug_stub(expr = "tuple_1 * 2")] StructWithoutDebug, u8),
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantA {
+                items: vec![StructWithoutDebug],
+                other: StructWithoutDebug,
+            }
+        ),
+        "VariantA { items: 1, other: Hidden }"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantB(StructWithoutDebug, 3)),
+        "VariantB(6, 3)"
+    );
+}
+
+#[test]
+fn test_enum_inherit_precision() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(each(none = "-"), inherit_precision, max_len = 30)] Vec<Option<f32>>),
+    }
+
+    assert_eq!(
+        format!("{:.3?}", TestEnum::VariantA(vec![Some(1.0), None])),
+        "VariantA([Some(1.000), -])"
+    );
+    assert_eq!(
+        format!("{:#.1?}", TestEnum::VariantA(vec![None])),
+        "VariantA(\n    [\n        -,\n    ],\n)"
+    );
+}
+
+#[test]
+fn test_enum_with() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(with = |v, f| write!(f, "{:02x}", v))] u8),
+    }
+
+    assert_eq!(format!("{:?}", TestEnum::VariantA(10)), "VariantA(0a)");
+}
+
+#[test]
+fn test_enum_len() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(len)] Vec<StructWithoutDebug>),
+        VariantB {
+            #[debug_stub(len)]
+            s: String,
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(vec![StructWithoutDebug])),
+        "VariantA(Vec(len = 1))"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantB { s: String::new() }),
+        "VariantB { s: String(len = 0) }"
+    );
+}
+
+#[test]
+fn test_enum_debug_fields() {
+    #[derive(DebugStub)]
+    #[debug_stub(debug_fields)]
+    enum TestEnum {
+        VariantA(u64, #[debug_stub = "_"] StructWithoutDebug),
+        VariantB {
+            #[debug_stub(len)]
+            a: String,
+        },
+        VariantC,
+    }
+
+    assert_eq!(
+        TestEnum::VariantA(42, StructWithoutDebug).debug_fields(),
+        vec![("0", "42".to_string()), ("1", "_".to_string())]
+    );
+    assert_eq!(
+        TestEnum::VariantB {
+            a: "Foo".to_string()
+        }
+        .debug_fields(),
+        vec![("a", "String(len = 3)".to_string())]
+    );
+    assert_eq!(TestEnum::VariantC.debug_fields(), vec![]);
+}
+
+#[test]
+fn test_enum_max_output() {
+    #[derive(DebugStub)]
+    #[debug_stub(max_output = 8)]
+    enum TestEnum {
+        Request {
+            #[debug_stub(priority = 1)]
+            id: u8,
+            body: String,
+        },
+        Ping(u8),
+    }
+
+    let request = TestEnum::Request {
+        id: 1,
+        body: "payload".to_string(),
+    };
+    assert_eq!(format!("{:?}", request), "Request { id: 1, body: … }");
+    assert_eq!(format!("{:?}", TestEnum::Ping(7)), "Ping(7)");
+}
+
+#[test]
+fn test_enum_brief() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        Running(#[debug_stub(brief)] u32, Vec<u8>),
+        Failed {
+            #[debug_stub(brief, len)]
+            log: String,
+            code: i32,
+        },
+        Done,
+    }
+
+    assert_eq!(
+        TestEnum::Running(7, vec![1, 2]).brief().to_string(),
+        "Running 0=7"
+    );
+    assert_eq!(
+        TestEnum::Failed {
+            log: "oops".to_string(),
+            code: 1,
+        }
+        .brief()
+        .to_string(),
+        "Failed log=String(len = 4)"
+    );
+    assert_eq!(TestEnum::Done.brief().to_string(), "Done");
+}
+
+#[test]
+fn test_enum_skip_if() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(skip_if = "Option::is_none")] Option<u8>, u8),
+        VariantB {
+            #[debug_stub(skip_if = "String::is_empty", len)]
+            a: String,
+            #[debug_stub = "Hidden"]
+            #[debug_stub(skip_if = "Vec::is_empty")]
+            b: Vec<u8>,
+        },
+    }
+
+    assert_eq!(format!("{:?}", TestEnum::VariantA(None, 1)), "VariantA(1)");
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Some(2), 1)),
+        "VariantA(Some(2), 1)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantB {
+                a: String::new(),
+                b: vec![1],
+            }
+        ),
+        "VariantB { b: Hidden }"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantB {
+                a: "x".to_string(),
+                b: vec![],
+            }
+        ),
+        "VariantB { a: String(len = 1) }"
+    );
+}
+
+#[test]
+fn test_enum_type_name() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(
+            #[allow(dead_code)]
+            #[debug_stub(type_name)]
+            StructWithoutDebug,
+        ),
+        VariantB {
+            #[allow(dead_code)]
+            #[debug_stub(type_name)]
+            a: u32,
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(StructWithoutDebug)),
+        format!("VariantA({})", std::any::type_name::<StructWithoutDebug>())
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantB { a: 42 }),
+        "VariantB { a: u32 }"
+    );
+}
+
+#[test]
+fn test_enum_hash() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(hash)] &'static str),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA("user@example.com")),
+        "VariantA(#13756aeb)"
+    );
+}
+
+#[test]
+fn test_enum_limit() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(limit = 0)] Vec<u32>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(vec![1, 2])),
+        "VariantA([… and 2 more entries])"
+    );
+    assert_eq!(format!("{:?}", TestEnum::VariantA(vec![])), "VariantA([])");
+}
+
+#[cfg(feature = "sha256")]
+#[test]
+fn test_enum_sha256() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(sha256)] [u8; 3]),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(*b"abc")),
+        "VariantA(sha256:ba7816bf8f01cfea…)"
+    );
+}
+
+#[test]
+fn test_enum_explain() {
+    #[derive(DebugStub)]
+    #[debug_stub(explain, ignore_generics)]
+    #[allow(dead_code)]
+    enum TestEnum<T> {
+        VariantA(#[debug_stub(type_name)] T, u8),
+        VariantB {
+            #[debug_stub(limit = 3)]
+            a: Vec<u8>,
+        },
+        VariantC,
+    }
+
+    assert_eq!(
+        TestEnum::<()>::DEBUG_STUB_EXPLAIN,
+        "TestEnum\n  bounds: none\n  VariantA.0: type_name\n  VariantA.1: debug\n  VariantB.a: limit = 3"
+    );
+}
+
+#[test]
+fn test_enum_display() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(sanitize, display)] &'static str),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA("a\tb\rc")),
+        r"VariantA(a\tb\rc)"
+    );
+}
+
+#[test]
+fn test_enum_truncate() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA {
+            #[debug_stub(truncate = 1)]
+            a: String,
+        },
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantA {
+                a: "\"quoted\"".to_string()
+            }
+        ),
+        r#"VariantA { a: "\"…" (8 chars) }"#
+    );
+}
+
+#[test]
+fn test_enum_first() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(first = 1)] [u8; 3]),
+    }
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA([1, 2, 3])),
         r#"VariantA(
-    None,
+    [
+        1,
+        … and 2 more entries,
+    ],
 )"#
     );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_enum_utc_chrono() {
+    use chrono::{DateTime, FixedOffset, TimeZone};
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(utc = "chrono")] DateTime<FixedOffset>),
+    }
+
+    let a = FixedOffset::west_opt(3600)
+        .unwrap()
+        .with_ymd_and_hms(2024, 5, 1, 11, 34, 56)
+        .unwrap();
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(a)),
+        "VariantA(2024-05-01T12:34:56Z)"
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_enum_rfc3339_time() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(rfc3339 = "time")] SystemTime),
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantA(UNIX_EPOCH + Duration::from_secs(86_400))
+        ),
+        "VariantA(1970-01-02T00:00:00Z)"
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_enum_json() {
+    use serde_json::{json, Value};
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(json(redact("secret")))] Value),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(json!({ "secret": [1, 2] }))),
+        r#"VariantA({"secret":"<redacted>"})"#
+    );
+}
+
+#[test]
+fn test_enum_byte_order() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(le)] u16),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(u16::from_le(0x0100))),
+        format!("VariantA({:#06x} (le: 256))", u16::from_le(0x0100))
+    );
+}
+
+#[test]
+fn test_enum_head_tail() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA {
+            #[debug_stub(head = 1, tail = 1)]
+            a: Vec<&'static str>,
+        },
+    }
+
+    assert_eq!(
+        format!(
+            "{:#?}",
+            TestEnum::VariantA {
+                a: vec!["x", "y", "z"]
+            }
+        ),
+        r#"VariantA {
+    a: [
+        "x",
+        … and 1 more entries,
+        "z",
+    ],
+}"#
+    );
+}
+
+#[test]
+fn test_enum_crc32() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(crc32)] [u8; 3]),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(*b"abc")),
+        "VariantA(crc32:352441c2)"
+    );
+}
+
+#[test]
+fn test_enum_ptr() {
+    use std::sync::Arc;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(ptr)] Arc<StructWithoutDebug>),
+    }
 
+    let value = Arc::new(StructWithoutDebug);
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Some(StructWithoutDebug))),
-        "VariantA(Some(StructWithoutDebugReplaceValue))"
+        format!("{:?}", TestEnum::VariantA(value.clone())),
+        format!("VariantA({:p})", value)
     );
+}
+
+#[test]
+fn test_enum_optional_none() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(none = "<not yet loaded>")] Option<u8>),
+    }
 
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Some(StructWithoutDebug))),
+        format!("{:#?}", TestEnum::VariantA(None)),
         r#"VariantA(
-    Some(
-        StructWithoutDebugReplaceValue,
-    ),
+    <not yet loaded>,
 )"#
     );
 }
 
 #[test]
-fn test_enum_result_both() {
+fn test_enum_trait() {
     #[derive(DebugStub)]
+    #[debug_stub(trait = "AltDebug")]
     enum TestEnum {
-        VariantA(
-            #[debug_stub(
-                ok = "StructWithoutDebugReplaceValue",
-                err = "ErrorWithoutDebugReplaceValue"
-            )]
-            Result<StructWithoutDebug, StructWithoutDebug>,
+        VariantA(#[debug_stub = "ReplacementValue"] StructWithoutDebug),
+    }
+
+    impl fmt::Debug for TestEnum {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("TestEnum")
+        }
+    }
+
+    let value = TestEnum::VariantA(StructWithoutDebug);
+    assert_eq!(format!("{:?}", value), "TestEnum");
+    assert_eq!(
+        format!("{:?}", value.debug_stub()),
+        "VariantA(ReplacementValue)"
+    );
+}
+
+#[test]
+fn test_enum_types() {
+    #[derive(DebugStub)]
+    #[debug_stub(types("ffi::*" = "<ffi>"))]
+    #[allow(dead_code)]
+    enum TestEnum {
+        VariantA(ffi::Handle, u8),
+        VariantB { a: ffi::Buffer<StructWithoutDebug> },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(ffi::Handle, 42)),
+        "VariantA(<ffi>, 42)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantB {
+                a: ffi::Buffer(StructWithoutDebug)
+            }
+        ),
+        "VariantB { a: <ffi> }"
+    );
+}
+
+#[test]
+fn test_enum_nested_optional_result() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(err(some = "Foreign"))] Result<u8, Option<StructWithoutDebug>>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Err(Some(StructWithoutDebug)))),
+        "VariantA(Err(Some(Foreign)))"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Ok(42))),
+        "VariantA(Ok(42))"
+    );
+}
+
+#[test]
+fn test_enum_each() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(each = "X")] Vec<StructWithoutDebug>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(vec![StructWithoutDebug])),
+        "VariantA([X])"
+    );
+}
+
+#[test]
+fn test_enum_mark_used() {
+    #[derive(DebugStub)]
+    #[debug_stub(mark_used)]
+    enum TestEnum {
+        VariantA(u8, #[debug_stub = "ReplacementValue"] StructWithoutDebug),
+        VariantB {
+            #[debug_stub = "ReplacementValue"]
+            a: StructWithoutDebug,
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(42, StructWithoutDebug)),
+        "VariantA(42, ReplacementValue)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantB {
+                a: StructWithoutDebug
+            }
+        ),
+        "VariantB { a: ReplacementValue }"
+    );
+}
+
+#[test]
+fn test_enum_map() {
+    use std::collections::HashMap;
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct HashableWithoutDebug;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(key = "X")] HashMap<HashableWithoutDebug, u8>),
+    }
+
+    let map = vec![(HashableWithoutDebug, 42)].into_iter().collect();
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(map)),
+        "VariantA({X: 42})"
+    );
+}
+
+#[test]
+fn test_enum_set() {
+    use std::collections::HashSet;
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct HashableWithoutDebug;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(set = "X")] HashSet<HashableWithoutDebug>),
+    }
+
+    let set = vec![HashableWithoutDebug].into_iter().collect();
+    assert_eq!(format!("{:?}", TestEnum::VariantA(set)), "VariantA({X})");
+}
+
+#[test]
+fn test_enum_fingerprint() {
+    mod v1 {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub enum TestEnum {
+            VariantA(u8),
+            VariantB,
+        }
+    }
+
+    mod v2 {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub enum TestEnum {
+            VariantB,
+            VariantA(u8),
+        }
+    }
+
+    mod v3 {
+        use super::*;
+
+        #[derive(DebugStub)]
+        #[debug_stub(fingerprint)]
+        #[allow(dead_code)]
+        pub enum TestEnum {
+            VariantA(u8),
+            VariantB {},
+        }
+    }
+
+    assert_ne!(
+        v1::TestEnum::DEBUG_FORMAT_FINGERPRINT,
+        v2::TestEnum::DEBUG_FORMAT_FINGERPRINT
+    );
+    assert_ne!(
+        v1::TestEnum::DEBUG_FORMAT_FINGERPRINT,
+        v3::TestEnum::DEBUG_FORMAT_FINGERPRINT
+    );
+}
+
+#[test]
+fn test_enum_err_passthrough() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(ok = "Foreign", err_max_len = 4)] Result<StructWithoutDebug, String>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Err("abc".to_string()))),
+        r#"VariantA(Err("abc…))"#
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
+        "VariantA(Ok(Foreign))"
+    );
+}
+
+#[test]
+fn test_enum_smart_pointer() {
+    #[derive(DebugStub)]
+    #[allow(dead_code)]
+    enum TestEnum {
+        VariantA(#[debug_stub(boxed = "ReplacementValue")] Box<StructWithoutDebug>),
+        VariantB {
+            #[debug_stub(boxed(type_name))]
+            a: Box<u8>,
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Box::new(StructWithoutDebug))),
+        "VariantA(Box(ReplacementValue))"
+    );
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantB { a: Box::new(42) }),
+        "VariantB { a: Box(u8) }"
+    );
+}
+
+#[test]
+fn test_enum_mutex() {
+    use std::sync::Mutex;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(mutex = "ReplacementValue")] Mutex<StructWithoutDebug>),
+    }
+
+    let value = TestEnum::VariantA(Mutex::new(StructWithoutDebug));
+    assert_eq!(format!("{:?}", value), "VariantA(Mutex(ReplacementValue))");
+
+    let TestEnum::VariantA(mutex) = &value;
+    let _guard = mutex.lock().unwrap();
+    assert_eq!(format!("{:?}", value), "VariantA(Mutex(<locked>))");
+}
+
+#[test]
+fn test_enum_const_generic_array() {
+    #[derive(DebugStub)]
+    enum TestEnum<const N: usize> {
+        VariantA(#[debug_stub(len)] [u8; N]),
+        VariantB {
+            #[debug_stub(each = "X")]
+            a: [StructWithoutDebug; N],
+        },
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA([0; 2])),
+        "VariantA(Array(len = 2))"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantB {
+                a: [StructWithoutDebug, StructWithoutDebug]
+            }
+        ),
+        "VariantB { a: [X, X] }"
+    );
+}
+
+#[test]
+fn test_enum_bound() {
+    #[derive(DebugStub)]
+    #[debug_stub(ignore_generics, bound = "T: Debug")]
+    #[allow(dead_code)]
+    enum TestEnum<T, U> {
+        VariantA(T),
+        VariantB(#[debug_stub = "ReplacementValue"] U),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::<u8, StructWithoutDebug>::VariantA(42)),
+        "VariantA(42)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::<u8, StructWithoutDebug>::VariantB(StructWithoutDebug)
         ),
+        "VariantB(ReplacementValue)"
+    );
+}
+
+#[test]
+fn test_enum_rwlock() {
+    use std::sync::RwLock;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(rwlock(len))] RwLock<Vec<u8>>),
+    }
+
+    let value = TestEnum::VariantA(RwLock::new(vec![1, 2]));
+    assert_eq!(format!("{:?}", value), "VariantA(RwLock(Vec(len = 2)))");
+
+    let TestEnum::VariantA(lock) = &value;
+    let _guard = lock.write().unwrap();
+    assert_eq!(format!("{:?}", value), "VariantA(RwLock(<write-locked>))");
+}
+
+#[test]
+fn test_enum_pipeline() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(len, mask(keep_start = 4, keep_end = 1))] Vec<u8>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(vec![1, 2, 3])),
+        "VariantA(Vec(*******))"
+    );
+}
+
+#[test]
+fn test_enum_refcell() {
+    use std::cell::RefCell;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(refcell(len))] RefCell<Vec<u8>>),
+    }
+
+    let value = TestEnum::VariantA(RefCell::new(vec![1, 2]));
+    assert_eq!(format!("{:?}", value), "VariantA(RefCell(Vec(len = 2)))");
+
+    let TestEnum::VariantA(cell) = &value;
+    let _guard = cell.borrow_mut();
+    assert_eq!(
+        format!("{:?}", value),
+        "VariantA(RefCell(<mutably borrowed>))"
+    );
+}
+
+#[test]
+fn test_enum_on_error() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(on_error = "<failed>")] Vec<u8>),
+    }
+
+    assert_eq!(
+        format!("{:#?}", TestEnum::VariantA(vec![1])),
+        "VariantA(\n    [\n        1,\n    ],\n)"
+    );
+}
+
+#[test]
+fn test_enum_cell() {
+    use std::cell::Cell;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(cell)] Cell<u8>),
+    }
+
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Cell::new(42))),
+        "VariantA(Cell(42))"
+    );
+}
+
+#[test]
+fn test_enum_filter_keys() {
+    use std::collections::BTreeMap;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(filter_keys = "user_", key = "X")] BTreeMap<String, u8>),
     }
 
+    let map = vec![("user_id".to_string(), 1), ("port".to_string(), 2)]
+        .into_iter()
+        .collect();
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Err(StructWithoutDebug))),
-        "VariantA(Err(ErrorWithoutDebugReplaceValue))"
+        format!("{:?}", TestEnum::VariantA(map)),
+        "VariantA({X: 1, … and 1 more entries})"
     );
+}
+
+#[test]
+fn test_enum_weak() {
+    use std::rc::{Rc, Weak};
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(weak)] Weak<u8>),
+    }
 
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Err(StructWithoutDebug))),
-        r#"VariantA(
-    Err(
-        ErrorWithoutDebugReplaceValue,
-    ),
-)"#
+        format!("{:?}", TestEnum::VariantA(Weak::new())),
+        "VariantA(Weak(dangling))"
     );
 
+    let strong = Rc::new(42);
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
-        "VariantA(Ok(StructWithoutDebugReplaceValue))"
+        format!("{:?}", TestEnum::VariantA(Rc::downgrade(&strong))),
+        "VariantA(Weak(alive))"
     );
+}
+
+#[test]
+fn test_enum_counts() {
+    use std::sync::Arc;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(counts)] Arc<u8>),
+    }
 
+    let value = Arc::new(42);
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
-        r#"VariantA(
-    Ok(
-        StructWithoutDebugReplaceValue,
-    ),
-)"#
+        format!("{:?}", TestEnum::VariantA(value.clone())),
+        "VariantA(Arc(strong = 2, weak = 0, 42))"
     );
 }
 
 #[test]
-fn test_enum_result_ok() {
+fn test_enum_resolve() {
+    fn lookup(id: u32) -> Option<String> {
+        Some(format!("entity-{}", id))
+    }
+
     #[derive(DebugStub)]
     enum TestEnum {
-        VariantA(
-            #[debug_stub(ok = "StructWithoutDebugReplaceValue")] Result<StructWithoutDebug, String>,
-        ),
+        VariantA(#[debug_stub(resolve = "lookup")] u32),
     }
 
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
-        "VariantA(Ok(StructWithoutDebugReplaceValue))"
+        format!("{:?}", TestEnum::VariantA(1)),
+        r#"VariantA(1 "entity-1")"#
     );
+}
+
+#[test]
+fn test_enum_atomic() {
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(atomic(max_len = 2))] AtomicUsize),
+    }
 
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Ok(StructWithoutDebug))),
-        r#"VariantA(
-    Ok(
-        StructWithoutDebugReplaceValue,
-    ),
-)"#
+        format!("{:?}", TestEnum::VariantA(AtomicUsize::new(12345))),
+        "VariantA(12…)"
     );
+}
+
+#[test]
+fn test_enum_uninit() {
+    use std::mem::MaybeUninit;
+
+    #[derive(DebugStub)]
+    #[allow(dead_code)]
+    enum TestEnum {
+        VariantA(#[debug_stub(uninit)] MaybeUninit<u8>),
+        VariantB(#[debug_stub(unsafe_assume_init)] MaybeUninit<u8>),
+    }
 
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Err("Foo".to_string()))),
-        "VariantA(Err(\"Foo\"))"
+        format!("{:?}", TestEnum::VariantA(MaybeUninit::uninit())),
+        "VariantA(<uninit>)"
     );
-
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Err("Foo".to_string()))),
-        r#"VariantA(
-    Err(
-        "Foo",
-    ),
-)"#
+        format!("{:?}", TestEnum::VariantB(MaybeUninit::new(42))),
+        "VariantB(42)"
     );
 }
 
 #[test]
-fn test_enum_result_err() {
-    struct ErrorWithoutDebug;
+fn test_enum_deref_to() {
+    use std::rc::Rc;
 
     #[derive(DebugStub)]
     enum TestEnum {
-        VariantA(
-            #[debug_stub(err = "ErrorWithoutDebugReplaceValue")] Result<String, ErrorWithoutDebug>,
-        ),
+        VariantA(#[debug_stub(deref_to = "str")] Rc<String>),
     }
 
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Err(ErrorWithoutDebug))),
-        "VariantA(Err(ErrorWithoutDebugReplaceValue))"
+        format!("{:?}", TestEnum::VariantA(Rc::new("text".to_string()))),
+        r#"VariantA("text")"#
     );
+}
+
+#[test]
+fn test_enum_once() {
+    use std::sync::OnceLock;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(once = "ReplacementValue")] OnceLock<StructWithoutDebug>),
+    }
+
+    let value = TestEnum::VariantA(OnceLock::new());
+    assert_eq!(format!("{:?}", value), "VariantA(Uninit)");
+
+    let TestEnum::VariantA(cell) = &value;
+    let _ = cell.set(StructWithoutDebug);
+    assert_eq!(format!("{:?}", value), "VariantA(Init(ReplacementValue))");
+}
+
+#[test]
+fn test_enum_addr_style() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(ptr, addr_style = "full")] *const u8),
+    }
 
+    let width = std::mem::size_of::<usize>() * 2;
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Err(ErrorWithoutDebug))),
-        r#"VariantA(
-    Err(
-        ErrorWithoutDebugReplaceValue,
-    ),
-)"#
+        format!("{:?}", TestEnum::VariantA(0x10 as *const u8)),
+        format!("VariantA(0x{:0width$x})", 0x10, width = width)
     );
+}
+
+#[test]
+fn test_enum_cow() {
+    use std::borrow::Cow;
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(cow)] Cow<'static, str>),
+    }
 
     assert_eq!(
-        format!("{:?}", TestEnum::VariantA(Ok("Foo".to_string()))),
-        "VariantA(Ok(\"Foo\"))"
+        format!("{:?}", TestEnum::VariantA(Cow::Borrowed("a"))),
+        r#"VariantA(Borrowed("a"))"#
     );
+}
+
+#[test]
+fn test_enum_dedup_key() {
+    #[derive(DebugStub)]
+    #[debug_stub(dedup_key)]
+    enum TestEnum<T> {
+        VariantA(T),
+        VariantB,
+    }
 
     assert_eq!(
-        format!("{:#?}", TestEnum::VariantA(Ok("Foo".to_string()))),
-        r#"VariantA(
-    Ok(
-        "Foo",
-    ),
-)"#
+        TestEnum::VariantA(1).debug_dedup_key(),
+        TestEnum::VariantA(1).debug_dedup_key()
+    );
+    assert_ne!(
+        TestEnum::VariantA(1).debug_dedup_key(),
+        TestEnum::<u8>::VariantB.debug_dedup_key()
     );
 }
 
 #[test]
-fn test_enum_optional_compare_std() {
-    mod a {
-        use debug_stub_derive::DebugStub;
+fn test_enum_duration() {
+    use std::time::Duration;
 
-        #[derive(DebugStub)]
-        pub enum TestEnum {
-            VariantA(Option<String>, Option<String>),
-            VariantB {
-                a: Option<String>,
-                b: Option<String>,
-            },
-        }
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(duration)] Duration),
     }
 
-    mod b {
-        #[derive(Debug)]
-        pub enum TestEnum {
-            VariantA(Option<String>, Option<String>),
-            VariantB {
-                a: Option<String>,
-                b: Option<String>,
-            },
-        }
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(Duration::from_micros(1_001))),
+        "VariantA(1.001ms)"
+    );
+}
+
+#[test]
+fn test_enum_elapsed() {
+    use std::time::{Duration, Instant};
+
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(elapsed)] Instant),
     }
 
-    let enum_a_a = a::TestEnum::VariantA(None, Some("Foo".to_string()));
-    let enum_a_b = a::TestEnum::VariantB {
-        a: Some("Foo".to_string()),
-        b: None,
-    };
+    let output = format!(
+        "{:?}",
+        TestEnum::VariantA(Instant::now() - Duration::from_secs(3600))
+    );
+    assert!(output.starts_with("VariantA(3600"), "{}", output);
+    assert!(output.ends_with("s ago)"), "{}", output);
+}
 
-    let enum_b_a = b::TestEnum::VariantA(None, Some("Foo".to_string()));
-    let enum_b_b = b::TestEnum::VariantB {
-        a: Some("Foo".to_string()),
-        b: None,
-    };
+#[test]
+fn test_enum_bits() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(bits(format = "hhhh_hhhh_llll_llll", h = "high", l = "low:b"))] u16),
+    }
 
-    assert_eq!(format!("{:?}", enum_a_a), format!("{:?}", enum_b_a));
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(0x2a05)),
+        "VariantA(high=42 low=0b00000101)"
+    );
+}
 
-    assert_eq!(format!("{:#?}", enum_a_a), format!("{:#?}", enum_b_a));
+#[test]
+fn test_enum_variant_counts() {
+    #[derive(DebugStub)]
+    #[debug_stub(variant_index)]
+    #[allow(dead_code)]
+    enum Item<T> {
+        Some(T),
+        None,
+    }
 
-    assert_eq!(format!("{:?}", enum_a_b), format!("{:?}", enum_b_b));
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(variant_counts)] Vec<Item<u8>>),
+    }
 
-    assert_eq!(format!("{:#?}", enum_a_b), format!("{:#?}", enum_b_b));
+    assert_eq!(
+        format!(
+            "{:?}",
+            TestEnum::VariantA(vec![Item::Some(1), Item::None, Item::Some(2)])
+        ),
+        "VariantA({Some: 2, None: 1})"
+    );
 }
 
 #[test]
-fn test_enum_result_compare_std() {
-    mod a {
-        use debug_stub_derive::DebugStub;
+fn test_enum_path() {
+    use std::path::PathBuf;
 
-        #[derive(DebugStub)]
-        pub enum TestEnum {
-            VariantA(Result<String, bool>, Result<String, bool>),
-            VariantB {
-                a: Result<String, bool>,
-                b: Result<String, bool>,
-            },
-        }
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(path)] PathBuf),
     }
 
-    mod b {
-        #[derive(Debug)]
-        pub enum TestEnum {
-            VariantA(Result<String, bool>, Result<String, bool>),
-            VariantB {
-                a: Result<String, bool>,
-                b: Result<String, bool>,
-            },
-        }
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(PathBuf::from("/tmp/a b"))),
+        "VariantA(/tmp/a b)"
+    );
+}
+
+#[test]
+fn test_enum_pure() {
+    #[derive(DebugStub)]
+    #[debug_stub(pure)]
+    enum TestEnum {
+        VariantA(#[debug_stub(cell)] std::cell::Cell<u8>),
     }
 
-    let enum_a_a = a::TestEnum::VariantA(Err(true), Ok("Foo".to_string()));
-    let enum_a_b = a::TestEnum::VariantB {
-        a: Ok("Foo".to_string()),
-        b: Err(true),
-    };
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(std::cell::Cell::new(42))),
+        "VariantA(Cell(42))"
+    );
+}
 
-    let enum_b_a = b::TestEnum::VariantA(Err(true), Ok("Foo".to_string()));
-    let enum_b_b = b::TestEnum::VariantB {
-        a: Ok("Foo".to_string()),
-        b: Err(true),
-    };
+#[test]
+fn test_enum_c_str() {
+    use std::ffi::CString;
 
-    assert_eq!(format!("{:?}", enum_a_a), format!("{:?}", enum_b_a));
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(c_str)] CString),
+    }
 
-    assert_eq!(format!("{:#?}", enum_a_a), format!("{:#?}", enum_b_a));
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(CString::new("name").unwrap())),
+        r#"VariantA("name")"#
+    );
+}
 
-    assert_eq!(format!("{:?}", enum_a_b), format!("{:?}", enum_b_b));
+#[test]
+fn test_enum_bytes() {
+    #[derive(DebugStub)]
+    enum TestEnum {
+        VariantA(#[debug_stub(bytes)] u64),
+    }
 
-    assert_eq!(format!("{:#?}", enum_a_b), format!("{:#?}", enum_b_b));
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(u64::MAX)),
+        "VariantA(16.00 EiB)"
+    );
 }
 
 #[test]
-fn test_enum_generic() {
-    use std::marker::PhantomData;
-
+fn test_enum_any_of() {
     #[derive(DebugStub)]
-    enum Enum<T> {
-        A(T),
+    #[debug_stub(ignore_generics, bound = "T: 'static")]
+    enum TestEnum<T> {
+        VariantA(#[debug_stub(any_of(u8))] T),
     }
 
+    assert_eq!(format!("{:?}", TestEnum::VariantA(1_u8)), "VariantA(1)");
+    assert_eq!(
+        format!("{:?}", TestEnum::VariantA(StructWithoutDebug)),
+        "VariantA(lib::StructWithoutDebug)"
+    );
+}
+
+#[test]
+fn test_enum_precision() {
     #[derive(DebugStub)]
-    #[debug_stub(ignore_generics)]
-    struct B<T>(PhantomData<T>);
+    enum TestEnum {
+        VariantA(#[debug_stub(precision = 2)] [f32; 2]),
+    }
 
-    assert_eq!(format!("{:?}", Enum::A(5)), "A(5)");
     assert_eq!(
-        format!("{:?}", B(PhantomData::<StructWithoutDebug>)),
-        "B(PhantomData)"
+        format!("{:#?}", TestEnum::VariantA([0.5, 1.0 / 3.0])),
+        "VariantA(\n    [\n        0.50,\n        0.33,\n    ],\n)"
     );
 }